@@ -1,5 +1,3 @@
-#![feature(duration_as_u128)]
-
 use std::io::Read;
 use std::iter;
 use std::time::{Duration, Instant};
@@ -17,11 +15,11 @@ fn main() {
 
     let nmos = iter::repeat_with(|| run_program(mem.clone(), Nmos::standard()))
         .take(3)
-        .fold(0.0/0.0, f64::max);
+        .fold(f64::NAN, f64::max);
     println!("NMOS MHz: {}", nmos);
     let cmos = iter::repeat_with(|| run_program(mem.clone(), Cmos::new()))
         .take(3)
-        .fold(0.0/0.0, f64::max);
+        .fold(f64::NAN, f64::max);
     println!("CMOS MHz: {}", cmos);
 }
 
@@ -40,16 +38,11 @@ fn run_program<C: Cpu>(mem: Vec<u8>, mut cpu: C) -> f64 {
     cpu.set_pc(0x0400);
 
     let now = Instant::now();
-    loop {
-        match main_loop(&mut sys, &mut cpu) {
-            Some(()) => break,
-            None => {
-                if cpu.halted() {
-                    panic!("Unexpected KIL instruction");
-                } else {
-                    panic!("Unexpected interruption");
-                }
-            }
+    if main_loop(&mut sys, &mut cpu).is_none() {
+        if cpu.halted() {
+            panic!("Unexpected KIL instruction");
+        } else {
+            panic!("Unexpected interruption");
         }
     }
     let now = now.elapsed();
@@ -0,0 +1,228 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::VecDeque;
+
+use crate::Device;
+
+const ST_OVERRUN: u8 = 0x04;
+const ST_RDRF: u8 = 0x08;
+const ST_TDRE: u8 = 0x10;
+const ST_IRQ: u8 = 0x80;
+
+// Baud rates for each control register setting, from the standard
+// 1.8432MHz crystal; setting 0 selects the 16x external clock, taken
+// to be the crystal itself.
+const BAUD: [u32; 16] = [
+    115_200, 50, 75, 110, 135, 150, 300, 600, 1200, 1800, 2400, 3600, 4800,
+    7200, 9600, 19200,
+];
+
+// The 6551 Asynchronous Communications Interface Adapter, with the
+// host side of the serial line as a pair of byte queues.
+#[derive(Clone, Debug)]
+pub struct Acia {
+    cpu_hz: u64,
+    // The WDC 65C51 always reports the transmit register as empty, and
+    // a byte written while another is being sent replaces it.
+    wdc: bool,
+    status: u8,
+    command: u8,
+    control: u8,
+    tx_data: Option<u8>,
+    tx_shift: Option<(u8, u64)>,
+    rx_data: u8,
+    rx_shift: Option<(u8, u64)>,
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+}
+
+impl Acia {
+    // The clock rate of the CPU is needed to time transfers.
+    pub fn new(cpu_hz: u64) -> Acia {
+        Acia {
+            cpu_hz,
+            wdc: false,
+            status: ST_TDRE,
+            command: 0,
+            control: 0,
+            tx_data: None,
+            tx_shift: None,
+            rx_data: 0,
+            rx_shift: None,
+            input: VecDeque::new(),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn wdc(cpu_hz: u64) -> Acia {
+        Acia {
+            wdc: true,
+            ..Acia::new(cpu_hz)
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.status = ST_TDRE;
+        self.command = 0;
+        self.control = 0;
+        self.tx_data = None;
+        self.tx_shift = None;
+        self.rx_shift = None;
+    }
+
+    // Queues bytes to be received from the serial line.
+    pub fn send(&mut self, data: &[u8]) {
+        self.input.extend(data);
+    }
+
+    // Takes the bytes transmitted so far.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    // Bytes still waiting to be received.
+    pub fn pending_input(&self) -> usize {
+        self.input.len()
+    }
+
+    pub fn frame_cycles(&self) -> u64 {
+        let data = 8 - u64::from((self.control >> 5) & 3);
+        let parity = u64::from((self.command >> 5) & 1);
+        let stop = if self.control & 0x80 != 0 { 2 } else { 1 };
+        let baud = u64::from(BAUD[(self.control & 0x0f) as usize]);
+        let bits = 1 + data + parity + stop;
+        (bits * self.cpu_hz).div_ceil(baud)
+    }
+
+    fn receiver_enabled(&self) -> bool {
+        self.command & 0x01 != 0
+    }
+
+    fn tx_irq_enabled(&self) -> bool {
+        (self.command >> 2) & 3 == 0b01
+    }
+
+    fn rx_irq_enabled(&self) -> bool {
+        self.command & 0x02 == 0
+    }
+
+    fn echo(&self) -> bool {
+        self.command & 0x10 != 0 && (self.command >> 2) & 3 == 0
+    }
+
+    fn data_mask(&self) -> u8 {
+        0xff >> ((self.control >> 5) & 3)
+    }
+
+    fn start_tx(&mut self, val: u8) {
+        let val = val & self.data_mask();
+        self.tx_shift = Some((val, self.frame_cycles()));
+    }
+
+    fn tick_tx(&mut self) {
+        if let Some((val, cycles)) = self.tx_shift {
+            if cycles > 1 {
+                self.tx_shift = Some((val, cycles - 1));
+                return;
+            }
+            self.output.push(val);
+            self.tx_shift = None;
+        }
+        if let Some(val) = self.tx_data.take() {
+            self.start_tx(val);
+            self.status |= ST_TDRE;
+            if self.tx_irq_enabled() {
+                self.status |= ST_IRQ;
+            }
+        }
+    }
+
+    fn tick_rx(&mut self) {
+        match self.rx_shift {
+            Some((val, cycles)) if cycles > 1 => {
+                self.rx_shift = Some((val, cycles - 1));
+            }
+            Some((val, _)) => {
+                self.rx_shift = None;
+                if self.status & ST_RDRF != 0 {
+                    self.status |= ST_OVERRUN;
+                } else {
+                    self.rx_data = val;
+                    self.status |= ST_RDRF;
+                }
+                if self.rx_irq_enabled() {
+                    self.status |= ST_IRQ;
+                }
+                if self.echo() {
+                    self.output.push(val);
+                }
+            }
+            None => {
+                if self.receiver_enabled() {
+                    if let Some(val) = self.input.pop_front() {
+                        let val = val & self.data_mask();
+                        self.rx_shift = Some((val, self.frame_cycles()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Device for Acia {
+    fn read(&mut self, reg: u8) -> u8 {
+        match reg & 3 {
+            0 => {
+                self.status &= !(ST_RDRF | ST_OVERRUN | 0x03);
+                self.rx_data
+            }
+            1 => {
+                let mut status = self.status;
+                if self.wdc {
+                    status |= ST_TDRE;
+                }
+                self.status &= !ST_IRQ;
+                status
+            }
+            2 => self.command,
+            3 => self.control,
+            _ => unreachable!(),
+        }
+    }
+
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 3 {
+            0 => {
+                if self.wdc {
+                    self.start_tx(val);
+                } else {
+                    self.tx_data = Some(val);
+                    self.status &= !ST_TDRE;
+                }
+            }
+            // Programmed reset.
+            1 => {
+                self.command &= 0xe0;
+                self.status &= !ST_OVERRUN;
+            }
+            2 => self.command = val,
+            3 => self.control = val,
+            _ => unreachable!(),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.tick_tx();
+        self.tick_rx();
+    }
+
+    fn irq(&self) -> bool {
+        self.status & ST_IRQ != 0
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::io::{Read, Write};
+
+use crate::{Cpu, Device, LoadError, Nmos, Pia, Sys};
+
+// An Apple-1, with the Woz Monitor (or any 256-byte ROM) at $FF00 and
+// the keyboard and display PIA at $D010.
+pub struct Apple1 {
+    pub cpu: Nmos,
+    pub sys: Apple1Sys,
+}
+
+impl Apple1 {
+    // RAM starts at $0000, and is between 4K and 32K.
+    pub fn new(ram_kb: usize, rom: &[u8]) -> Apple1 {
+        assert!((4..=32).contains(&ram_kb), "RAM must be 4K-32K");
+        assert_eq!(rom.len(), 256, "ROM must be 256 bytes");
+        let mut pia = Pia::new();
+        pia.set_capture(true);
+        // The display is always ready (PB7 low).
+        pia.set_pb(0x00);
+        let mut cpu = Nmos::default();
+        cpu.reset();
+        Apple1 {
+            cpu,
+            sys: Apple1Sys {
+                ram: vec![0; ram_kb * 1024],
+                rom: rom.to_vec(),
+                pia,
+                input: None,
+                output: None,
+                display: Vec::new(),
+                cycles: 0,
+            },
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.sys.cycles
+    }
+
+    pub fn run_instruction(&mut self) {
+        self.cpu.run_instruction(&mut self.sys);
+    }
+
+    // Runs whole instructions until at least the given number of cycles
+    // have passed.
+    pub fn run_cycles(&mut self, cycles: u64) {
+        let end = self.sys.cycles + cycles;
+        while self.sys.cycles < end && !self.cpu.halted() {
+            self.run_instruction();
+        }
+    }
+
+    // Queues keystrokes, as ASCII; lowercase letters are shifted, and
+    // newlines become the Return key.
+    pub fn send_keys(&mut self, keys: &[u8]) {
+        let keys: Vec<u8> = keys.iter().map(|&k| key_code(k)).collect();
+        self.sys.pia.send_keys(&keys);
+    }
+
+    // Reads keys from the stream whenever the keyboard is polled with
+    // no key waiting, which blocks if the stream does.
+    pub fn set_input(&mut self, input: Box<dyn Read>) {
+        self.sys.input = Some(input);
+    }
+
+    // Sends the display output to the sink, instead of keeping it for
+    // take_output().
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        self.sys.output = Some(output);
+    }
+
+    // Takes the text displayed so far, as ASCII with newlines.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.sys.display)
+    }
+
+    pub fn load(&mut self, addr: u16, data: &[u8]) -> Result<(), LoadError> {
+        let addr = addr as usize;
+        self.sys
+            .ram
+            .get_mut(addr..(addr + data.len()))
+            .ok_or(LoadError::Invalid("data past end of RAM"))?
+            .copy_from_slice(data);
+        Ok(())
+    }
+}
+
+fn key_code(key: u8) -> u8 {
+    match key {
+        b'\n' => 0x8d,
+        _ => key.to_ascii_uppercase() | 0x80,
+    }
+}
+
+pub struct Apple1Sys {
+    pub ram: Vec<u8>,
+    pub rom: Vec<u8>,
+    pub pia: Pia,
+    input: Option<Box<dyn Read>>,
+    output: Option<Box<dyn Write>>,
+    display: Vec<u8>,
+    cycles: u64,
+}
+
+impl Apple1Sys {
+    fn poll_input(&mut self) {
+        if self.pia.pending_keys() > 0 || self.pia.read(1) & 0x80 != 0 {
+            return;
+        }
+        if let Some(input) = self.input.as_mut() {
+            let mut key = [0u8];
+            if let Ok(1) = input.read(&mut key) {
+                self.pia.strobe_a(key_code(key[0]));
+            }
+        }
+    }
+
+    fn display(&mut self) {
+        for val in self.pia.take_output() {
+            let ch = match val & 0x7f {
+                0x0d => b'\n',
+                ch => ch,
+            };
+            match self.output.as_mut() {
+                Some(output) => {
+                    let _ = output.write_all(&[ch]);
+                    let _ = output.flush();
+                }
+                None => self.display.push(ch),
+            }
+        }
+    }
+}
+
+impl Sys for Apple1Sys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let val = match addr {
+            0xd010..=0xd01f => {
+                if addr & 3 == 1 {
+                    self.poll_input();
+                }
+                self.pia.read(addr as u8)
+            }
+            0xff00..=0xffff => self.rom[(addr & 0xff) as usize],
+            _ => self.ram.get(addr as usize).cloned().unwrap_or(0),
+        };
+        self.pia.tick();
+        self.cycles += 1;
+        Some(val)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        match addr {
+            0xd010..=0xd01f => {
+                self.pia.write(addr as u8, val);
+                self.display();
+            }
+            _ => {
+                if let Some(mem) = self.ram.get_mut(addr as usize) {
+                    *mem = val;
+                }
+            }
+        }
+        self.pia.tick();
+        self.cycles += 1;
+        Some(())
+    }
+}
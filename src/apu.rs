@@ -0,0 +1,845 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::io::{self, Write};
+
+use crate::dma::Dma;
+use crate::{CycleKind, Device, NmiLength, Sys};
+
+const NTSC_CPU_HZ: u32 = 1_789_773;
+
+const LENGTHS: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12, 16, 24,
+    18, 48, 20, 96, 22, 192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+const DUTIES: [u8; 4] = [0b0100_0000, 0b0110_0000, 0b0111_1000, 0b1001_1111];
+
+const TRIANGLE: [u8; 32] = [
+    15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, 0, 1, 2, 3, 4, 5, 6,
+    7, 8, 9, 10, 11, 12, 13, 14, 15,
+];
+
+const NOISE_PERIODS: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+
+const DMC_RATES: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+
+// The frame counter steps, in CPU cycles after it is reset.
+const STEP_1: u32 = 7457;
+const STEP_2: u32 = 14913;
+const STEP_3: u32 = 22371;
+const STEP_4: u32 = 29829;
+const STEP_5: u32 = 37281;
+
+#[derive(Clone, Debug, Default)]
+struct Envelope {
+    start: bool,
+    divider: u8,
+    decay: u8,
+}
+
+impl Envelope {
+    fn clock(&mut self, ctrl: u8) {
+        if self.start {
+            self.start = false;
+            self.decay = 15;
+            self.divider = ctrl & 0x0f;
+        } else if self.divider == 0 {
+            self.divider = ctrl & 0x0f;
+            if self.decay > 0 {
+                self.decay -= 1;
+            } else if ctrl & 0x20 != 0 {
+                self.decay = 15;
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    fn volume(&self, ctrl: u8) -> u8 {
+        if ctrl & 0x10 != 0 {
+            ctrl & 0x0f
+        } else {
+            self.decay
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Length {
+    enabled: bool,
+    count: u8,
+}
+
+impl Length {
+    fn load(&mut self, val: u8) {
+        if self.enabled {
+            self.count = LENGTHS[(val >> 3) as usize];
+        }
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.count = 0;
+        }
+    }
+
+    fn clock(&mut self, halt: bool) {
+        if !halt && self.count > 0 {
+            self.count -= 1;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Pulse {
+    // Pulse 1 negates with ones' complement.
+    ones_complement: bool,
+    ctrl: u8,
+    sweep: u8,
+    sweep_reload: bool,
+    sweep_divider: u8,
+    period: u16,
+    timer: u16,
+    step: u8,
+    envelope: Envelope,
+    length: Length,
+}
+
+impl Pulse {
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 3 {
+            0 => self.ctrl = val,
+            1 => {
+                self.sweep = val;
+                self.sweep_reload = true;
+            }
+            2 => self.period = (self.period & 0x700) | val as u16,
+            _ => {
+                self.period = (self.period & 0xff) | ((val as u16 & 7) << 8);
+                self.length.load(val);
+                self.step = 0;
+                self.envelope.start = true;
+            }
+        }
+    }
+
+    fn target(&self) -> u16 {
+        let change = self.period >> (self.sweep & 7);
+        if self.sweep & 0x08 == 0 {
+            self.period + change
+        } else if self.ones_complement {
+            self.period.saturating_sub(change + 1)
+        } else {
+            self.period.saturating_sub(change)
+        }
+    }
+
+    fn muted(&self) -> bool {
+        self.period < 8 || self.target() > 0x7ff
+    }
+
+    // Clocked every other CPU cycle.
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            self.step = (self.step + 7) & 7;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn clock_quarter(&mut self) {
+        self.envelope.clock(self.ctrl);
+    }
+
+    fn clock_half(&mut self) {
+        self.length.clock(self.ctrl & 0x20 != 0);
+        let enabled = self.sweep & 0x80 != 0 && self.sweep & 7 != 0;
+        if self.sweep_divider == 0 && enabled && !self.muted() {
+            self.period = self.target();
+        }
+        if self.sweep_divider == 0 || self.sweep_reload {
+            self.sweep_divider = (self.sweep >> 4) & 7;
+            self.sweep_reload = false;
+        } else {
+            self.sweep_divider -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        let duty = DUTIES[(self.ctrl >> 6) as usize];
+        if self.length.count == 0
+            || self.muted()
+            || duty & (0x80 >> self.step) == 0
+        {
+            0
+        } else {
+            self.envelope.volume(self.ctrl)
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Triangle {
+    ctrl: u8,
+    period: u16,
+    timer: u16,
+    step: u8,
+    linear: u8,
+    linear_reload: bool,
+    length: Length,
+}
+
+impl Triangle {
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 3 {
+            0 => self.ctrl = val,
+            1 => (),
+            2 => self.period = (self.period & 0x700) | val as u16,
+            _ => {
+                self.period = (self.period & 0xff) | ((val as u16 & 7) << 8);
+                self.length.load(val);
+                self.linear_reload = true;
+            }
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.linear > 0 && self.length.count > 0 {
+                self.step = (self.step + 1) & 31;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn clock_quarter(&mut self) {
+        if self.linear_reload {
+            self.linear = self.ctrl & 0x7f;
+        } else if self.linear > 0 {
+            self.linear -= 1;
+        }
+        if self.ctrl & 0x80 == 0 {
+            self.linear_reload = false;
+        }
+    }
+
+    fn clock_half(&mut self) {
+        self.length.clock(self.ctrl & 0x80 != 0);
+    }
+
+    // The sequencer holds its last value when silenced, as on the real
+    // chip.
+    fn output(&self) -> u8 {
+        TRIANGLE[self.step as usize]
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Noise {
+    ctrl: u8,
+    mode: bool,
+    period: u16,
+    timer: u16,
+    shift: u16,
+    envelope: Envelope,
+    length: Length,
+}
+
+impl Noise {
+    fn new() -> Noise {
+        Noise {
+            ctrl: 0,
+            mode: false,
+            period: NOISE_PERIODS[0],
+            timer: 0,
+            shift: 1,
+            envelope: Envelope::default(),
+            length: Length::default(),
+        }
+    }
+
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 3 {
+            0 => self.ctrl = val,
+            1 => (),
+            2 => {
+                self.mode = val & 0x80 != 0;
+                self.period = NOISE_PERIODS[(val & 0x0f) as usize];
+            }
+            _ => {
+                self.length.load(val);
+                self.envelope.start = true;
+            }
+        }
+    }
+
+    // Clocked every CPU cycle; the periods are in CPU cycles.
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period - 1;
+            let tap = if self.mode { 6 } else { 1 };
+            let feedback = (self.shift ^ (self.shift >> tap)) & 1;
+            self.shift = (self.shift >> 1) | (feedback << 14);
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn clock_quarter(&mut self) {
+        self.envelope.clock(self.ctrl);
+    }
+
+    fn clock_half(&mut self) {
+        self.length.clock(self.ctrl & 0x20 != 0);
+    }
+
+    fn output(&self) -> u8 {
+        if self.length.count == 0 || self.shift & 1 != 0 {
+            0
+        } else {
+            self.envelope.volume(self.ctrl)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Dmc {
+    ctrl: u8,
+    level: u8,
+    start_addr: u16,
+    start_len: u16,
+    addr: u16,
+    remaining: u16,
+    buffer: Option<u8>,
+    shift: u8,
+    bits: u8,
+    silent: bool,
+    period: u16,
+    timer: u16,
+    irq: bool,
+}
+
+impl Dmc {
+    fn new() -> Dmc {
+        Dmc {
+            ctrl: 0,
+            level: 0,
+            start_addr: 0xc000,
+            start_len: 1,
+            addr: 0xc000,
+            remaining: 0,
+            buffer: None,
+            shift: 0,
+            bits: 8,
+            silent: true,
+            period: DMC_RATES[0],
+            timer: DMC_RATES[0] - 1,
+            irq: false,
+        }
+    }
+
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 3 {
+            0 => {
+                self.ctrl = val;
+                self.period = DMC_RATES[(val & 0x0f) as usize];
+                if val & 0x80 == 0 {
+                    self.irq = false;
+                }
+            }
+            1 => self.level = val & 0x7f,
+            2 => self.start_addr = 0xc000 | ((val as u16) << 6),
+            _ => self.start_len = ((val as u16) << 4) | 1,
+        }
+    }
+
+    fn restart(&mut self) {
+        self.addr = self.start_addr;
+        self.remaining = self.start_len;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.irq = false;
+        if !enabled {
+            self.remaining = 0;
+        } else if self.remaining == 0 {
+            self.restart();
+        }
+    }
+
+    fn request(&self) -> Option<u16> {
+        if self.buffer.is_none() && self.remaining > 0 {
+            Some(self.addr)
+        } else {
+            None
+        }
+    }
+
+    fn fill(&mut self, val: u8) {
+        self.buffer = Some(val);
+        self.addr = self.addr.checked_add(1).unwrap_or(0x8000);
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            if self.ctrl & 0x40 != 0 {
+                self.restart();
+            } else if self.ctrl & 0x80 != 0 {
+                self.irq = true;
+            }
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.period - 1;
+        if !self.silent {
+            if self.shift & 1 != 0 {
+                if self.level <= 125 {
+                    self.level += 2;
+                }
+            } else if self.level >= 2 {
+                self.level -= 2;
+            }
+        }
+        self.shift >>= 1;
+        self.bits -= 1;
+        if self.bits == 0 {
+            self.bits = 8;
+            match self.buffer.take() {
+                Some(val) => {
+                    self.silent = false;
+                    self.shift = val;
+                }
+                None => self.silent = true,
+            }
+        }
+    }
+}
+
+// The 2A03's audio processing unit, registers $4000-$4013, $4015 and
+// $4017, clocked once per CPU cycle. Samples are mixed with the usual
+// nonlinear approximation, high-pass filtered, and averaged down to
+// the chosen rate.
+//
+// The DMC's sample fetches are left to the Sys: when dmc_request()
+// returns an address, the byte there should be passed to dmc_fill().
+#[derive(Clone, Debug)]
+pub struct Apu {
+    pulse: [Pulse; 2],
+    triangle: Triangle,
+    noise: Noise,
+    dmc: Dmc,
+    five_step: bool,
+    irq_inhibit: bool,
+    frame_irq: bool,
+    frame_cycle: u32,
+    frame_reset: Option<u8>,
+    odd: bool,
+    sample_rate: u32,
+    cycles_per_sample: f64,
+    sample_time: f64,
+    sample_sum: f64,
+    sample_count: u32,
+    filter: (f64, f64),
+    filter_coeff: f64,
+    samples: Vec<f32>,
+}
+
+impl Apu {
+    pub fn new(sample_rate: u32) -> Apu {
+        let mut pulse = [Pulse::default(), Pulse::default()];
+        pulse[0].ones_complement = true;
+        // A 90Hz high-pass, to remove the DC offset.
+        let rc = 1.0 / (2.0 * std::f64::consts::PI * 90.0);
+        let dt = 1.0 / f64::from(sample_rate);
+        Apu {
+            pulse,
+            triangle: Triangle::default(),
+            noise: Noise::new(),
+            dmc: Dmc::new(),
+            five_step: false,
+            irq_inhibit: false,
+            frame_irq: false,
+            frame_cycle: 0,
+            frame_reset: None,
+            odd: false,
+            sample_rate,
+            cycles_per_sample: f64::from(NTSC_CPU_HZ) / f64::from(sample_rate),
+            sample_time: 0.0,
+            sample_sum: 0.0,
+            sample_count: 0,
+            filter: (0.0, 0.0),
+            filter_coeff: rc / (rc + dt),
+            samples: Vec::new(),
+        }
+    }
+
+    // Silences the channels, as the reset line does; the frame counter
+    // mode is kept.
+    pub fn reset(&mut self) {
+        self.write(0x15, 0);
+        self.frame_irq = false;
+        self.frame_cycle = 0;
+        self.dmc.level &= 1;
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.samples)
+    }
+
+    pub fn take_samples_i16(&mut self) -> Vec<i16> {
+        self.take_samples()
+            .into_iter()
+            .map(|s| (s.clamp(-1.0, 1.0) * 32767.0) as i16)
+            .collect()
+    }
+
+    pub fn dmc_request(&self) -> Option<u16> {
+        self.dmc.request()
+    }
+
+    pub fn dmc_fill(&mut self, val: u8) {
+        self.dmc.fill(val);
+    }
+
+    pub fn frame_irq(&self) -> bool {
+        self.frame_irq
+    }
+
+    pub fn dmc_irq(&self) -> bool {
+        self.dmc.irq
+    }
+
+    fn clock_quarter(&mut self) {
+        self.pulse[0].clock_quarter();
+        self.pulse[1].clock_quarter();
+        self.triangle.clock_quarter();
+        self.noise.clock_quarter();
+    }
+
+    fn clock_half(&mut self) {
+        self.pulse[0].clock_half();
+        self.pulse[1].clock_half();
+        self.triangle.clock_half();
+        self.noise.clock_half();
+    }
+
+    fn clock_frame(&mut self) {
+        if let Some(delay) = self.frame_reset {
+            if delay > 1 {
+                self.frame_reset = Some(delay - 1);
+            } else {
+                self.frame_reset = None;
+                self.frame_cycle = 0;
+                if self.five_step {
+                    self.clock_quarter();
+                    self.clock_half();
+                }
+                return;
+            }
+        }
+        self.frame_cycle += 1;
+        match (self.frame_cycle, self.five_step) {
+            (STEP_1, _) | (STEP_3, _) => self.clock_quarter(),
+            (STEP_2, _) => {
+                self.clock_quarter();
+                self.clock_half();
+            }
+            // The IRQ flag is set on three cycles in a row, so that
+            // reading $4015 at the wrong moment doesn't clear it.
+            (c, false) if c == STEP_4 - 1 => self.set_frame_irq(),
+            (STEP_4, false) => {
+                self.set_frame_irq();
+                self.clock_quarter();
+                self.clock_half();
+            }
+            (c, false) if c == STEP_4 + 1 => {
+                self.set_frame_irq();
+                self.frame_cycle = 0;
+            }
+            (STEP_5, true) => {
+                self.clock_quarter();
+                self.clock_half();
+            }
+            (c, true) if c == STEP_5 + 1 => self.frame_cycle = 0,
+            _ => (),
+        }
+    }
+
+    fn set_frame_irq(&mut self) {
+        if !self.irq_inhibit {
+            self.frame_irq = true;
+        }
+    }
+
+    fn mix(&self) -> f64 {
+        let p = f64::from(self.pulse[0].output() + self.pulse[1].output());
+        let t = f64::from(self.triangle.output());
+        let n = f64::from(self.noise.output());
+        let d = f64::from(self.dmc.level);
+        let pulse = if p == 0.0 {
+            0.0
+        } else {
+            95.88 / (8128.0 / p + 100.0)
+        };
+        let tnd = t / 8227.0 + n / 12241.0 + d / 22638.0;
+        let tnd = if tnd == 0.0 {
+            0.0
+        } else {
+            159.79 / (1.0 / tnd + 100.0)
+        };
+        pulse + tnd
+    }
+
+    fn sample(&mut self) {
+        self.sample_sum += self.mix();
+        self.sample_count += 1;
+        self.sample_time += 1.0;
+        if self.sample_time < self.cycles_per_sample {
+            return;
+        }
+        self.sample_time -= self.cycles_per_sample;
+        let x = self.sample_sum / f64::from(self.sample_count);
+        self.sample_sum = 0.0;
+        self.sample_count = 0;
+        let (last_x, last_y) = self.filter;
+        let y = self.filter_coeff * (last_y + x - last_x);
+        self.filter = (x, y);
+        self.samples.push(y as f32);
+    }
+}
+
+impl Device for Apu {
+    // Only $4015 can be read; bit 5 is open bus, and left clear.
+    fn read(&mut self, reg: u8) -> u8 {
+        if reg & 0x1f != 0x15 {
+            return 0;
+        }
+        let status = (self.pulse[0].length.count > 0) as u8
+            | ((self.pulse[1].length.count > 0) as u8) << 1
+            | ((self.triangle.length.count > 0) as u8) << 2
+            | ((self.noise.length.count > 0) as u8) << 3
+            | ((self.dmc.remaining > 0) as u8) << 4
+            | (self.frame_irq as u8) << 6
+            | (self.dmc.irq as u8) << 7;
+        self.frame_irq = false;
+        status
+    }
+
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 0x1f {
+            reg @ 0x00..=0x03 => self.pulse[0].write(reg, val),
+            reg @ 0x04..=0x07 => self.pulse[1].write(reg, val),
+            reg @ 0x08..=0x0b => self.triangle.write(reg, val),
+            reg @ 0x0c..=0x0f => self.noise.write(reg, val),
+            reg @ 0x10..=0x13 => self.dmc.write(reg, val),
+            0x15 => {
+                self.pulse[0].length.set_enabled(val & 0x01 != 0);
+                self.pulse[1].length.set_enabled(val & 0x02 != 0);
+                self.triangle.length.set_enabled(val & 0x04 != 0);
+                self.noise.length.set_enabled(val & 0x08 != 0);
+                self.dmc.set_enabled(val & 0x10 != 0);
+            }
+            // The new mode takes effect three or four cycles later,
+            // depending on where in the APU cycle the write lands.
+            0x17 => {
+                self.five_step = val & 0x80 != 0;
+                self.irq_inhibit = val & 0x40 != 0;
+                if self.irq_inhibit {
+                    self.frame_irq = false;
+                }
+                self.frame_reset = Some(if self.odd { 4 } else { 3 });
+            }
+            _ => (),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.clock_frame();
+        if self.odd {
+            self.pulse[0].clock_timer();
+            self.pulse[1].clock_timer();
+        }
+        self.triangle.clock_timer();
+        self.noise.clock_timer();
+        self.dmc.clock_timer();
+        self.odd = !self.odd;
+        self.sample();
+    }
+
+    fn irq(&self) -> bool {
+        self.frame_irq || self.dmc.irq
+    }
+}
+
+// Writes 16-bit mono samples as a WAV file.
+pub fn write_wav<W: Write>(
+    mut out: W,
+    sample_rate: u32,
+    samples: &[i16],
+) -> io::Result<()> {
+    let data_len = (samples.len() * 2) as u32;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&sample_rate.to_le_bytes())?;
+    out.write_all(&(sample_rate * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        out.write_all(&sample.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+// The rest of the 2A03 around the CPU: maps the APU into the wrapped
+// Sys, ticks it every cycle, and ORs in its IRQ.
+//
+// A write to $4014 starts an OAM DMA to $2004, and the DMC fetches its
+// samples by DMA. Like RDY, the DMA pauses the CPU only on a read,
+// which then returns None for each stolen cycle; run_instruction
+// should be retried until it returns Some.
+pub struct Rp2a03<S: Sys> {
+    pub sys: S,
+    pub apu: Apu,
+    dma: Dma,
+    cycles: u64,
+}
+
+impl<S: Sys> Rp2a03<S> {
+    pub fn new(sys: S, sample_rate: u32) -> Rp2a03<S> {
+        Rp2a03 {
+            sys,
+            apu: Apu::new(sample_rate),
+            dma: Dma::default(),
+            cycles: 0,
+        }
+    }
+
+    // The cycles run, including those stolen by DMA.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    fn tick(&mut self) {
+        self.apu.tick();
+        self.dma.set_dmc(self.apu.dmc_request().is_some());
+        self.cycles += 1;
+    }
+}
+
+// $4016 writes, and reads other than $4015, still go to the wrapped
+// Sys, for the controllers.
+fn apu_reg(addr: u16, write: bool) -> bool {
+    match addr {
+        0x4015 => true,
+        0x4000..=0x4013 | 0x4017 => write,
+        _ => false,
+    }
+}
+
+impl<S: Sys> Sys for Rp2a03<S> {
+    #[inline]
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.read_kind(addr, CycleKind::Data)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.write_kind(addr, val, CycleKind::Data)
+    }
+
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        if self.dma.active() {
+            let get = self.cycles & 1 == 0;
+            self.dma
+                .cycle(&mut self.sys, &mut self.apu, addr, kind, get)?;
+            self.tick();
+            return None;
+        }
+        let val = if apu_reg(addr, false) {
+            self.apu.read(addr as u8)
+        } else {
+            self.sys.read_kind(addr, kind)?
+        };
+        self.tick();
+        Some(val)
+    }
+
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
+        if addr == 0x4014 {
+            self.dma.start_oam(val);
+        } else if apu_reg(addr, true) {
+            self.apu.write(addr as u8, val);
+        } else {
+            self.sys.write_kind(addr, val, kind)?;
+        }
+        self.tick();
+        Some(())
+    }
+
+    #[inline]
+    fn set_sync(&mut self, set: bool) {
+        self.sys.set_sync(set);
+    }
+
+    #[inline]
+    fn set_vp(&mut self, set: bool) {
+        self.sys.set_vp(set);
+    }
+
+    #[inline]
+    fn set_ml(&mut self, set: bool) {
+        self.sys.set_ml(set);
+    }
+
+    #[inline]
+    fn poll_nmi(&mut self) -> bool {
+        self.sys.poll_nmi()
+    }
+
+    #[inline]
+    fn peek_nmi(&self) -> bool {
+        self.sys.peek_nmi()
+    }
+
+    #[inline]
+    fn nmi_length(&self) -> NmiLength {
+        self.sys.nmi_length()
+    }
+
+    #[inline]
+    fn irq(&self) -> bool {
+        self.apu.irq() || self.sys.irq()
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{Cpu, Device, Frame, Nmos, Riot, Sys, Tia};
+
+// The cartridge bankswitching schemes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Bankswitch {
+    // 2K or 4K, mirrored to fill the 4K window.
+    None,
+    // 8K, 16K and 32K Atari carts: 4K banks selected by accessing
+    // $1FF8-$1FF9, $1FF6-$1FF9 or $1FF4-$1FFB.
+    F8,
+    F6,
+    F4,
+    // Parker Bros. 8K carts: three 1K windows selected by accessing
+    // $1FE0-$1FF7, and the last 1K bank fixed at $1C00.
+    E0,
+    // Tigervision (3F) carts: a 2K bank at $1000 selected by writes
+    // to $00-$3F, and the last 2K bank fixed at $1800.
+    Tigervision,
+}
+
+impl Bankswitch {
+    // Guesses the scheme from the size of the ROM, which is always
+    // right for the Atari schemes; E0 and Tigervision must be asked for.
+    pub fn detect(rom: &[u8]) -> Option<Bankswitch> {
+        match rom.len() {
+            0x800 | 0x1000 => Some(Bankswitch::None),
+            0x2000 => Some(Bankswitch::F8),
+            0x4000 => Some(Bankswitch::F6),
+            0x8000 => Some(Bankswitch::F4),
+            _ => None,
+        }
+    }
+
+    fn valid_size(self, len: usize) -> bool {
+        match self {
+            Bankswitch::None => len == 0x800 || len == 0x1000,
+            Bankswitch::F8 | Bankswitch::E0 => len == 0x2000,
+            Bankswitch::F6 => len == 0x4000,
+            Bankswitch::F4 => len == 0x8000,
+            Bankswitch::Tigervision => len >= 0x1000 && len.is_power_of_two(),
+        }
+    }
+}
+
+pub struct Atari2600Cart {
+    pub rom: Vec<u8>,
+    scheme: Bankswitch,
+    // The ROM offset mapped into each 1K window.
+    windows: [usize; 4],
+}
+
+impl Atari2600Cart {
+    fn new(rom: &[u8], scheme: Bankswitch) -> Atari2600Cart {
+        assert!(
+            scheme.valid_size(rom.len()),
+            "wrong ROM size for {:?}",
+            scheme
+        );
+        let mut cart = Atari2600Cart {
+            rom: rom.to_vec(),
+            scheme,
+            windows: [0; 4],
+        };
+        cart.reset();
+        cart
+    }
+
+    pub fn scheme(&self) -> Bankswitch {
+        self.scheme
+    }
+
+    // The ROM offset of the 1K window at $1000, $1400, $1800 or $1C00.
+    pub fn window(&self, n: usize) -> usize {
+        self.windows[n]
+    }
+
+    // The Atari schemes start in their last bank, the others as their
+    // carts are known to.
+    fn reset(&mut self) {
+        let last = self.rom.len() - 0x400;
+        self.windows = match self.scheme {
+            Bankswitch::None => [0, 0x400, 0x800, 0xc00],
+            Bankswitch::E0 => [0x1000, 0x1400, 0x1800, last],
+            Bankswitch::Tigervision => [0, 0x400, last - 0x400, last],
+            _ => {
+                let bank = self.rom.len() - 0x1000;
+                [bank, bank + 0x400, bank + 0x800, bank + 0xc00]
+            }
+        };
+        if self.rom.len() == 0x800 {
+            self.windows = [0, 0x400, 0, 0x400];
+        }
+    }
+
+    fn select_4k(&mut self, bank: usize) {
+        for (n, window) in self.windows.iter_mut().enumerate() {
+            *window = bank * 0x1000 + n * 0x400;
+        }
+    }
+
+    // Switches banks on any access to a hotspot, read or write.
+    fn access(&mut self, addr: u16) {
+        let addr = addr & 0x0fff;
+        match self.scheme {
+            Bankswitch::F8 if (0xff8..=0xff9).contains(&addr) => {
+                self.select_4k((addr - 0xff8) as usize)
+            }
+            Bankswitch::F6 if (0xff6..=0xff9).contains(&addr) => {
+                self.select_4k((addr - 0xff6) as usize)
+            }
+            Bankswitch::F4 if (0xff4..=0xffb).contains(&addr) => {
+                self.select_4k((addr - 0xff4) as usize)
+            }
+            Bankswitch::E0 if (0xfe0..=0xff7).contains(&addr) => {
+                let n = ((addr - 0xfe0) / 8) as usize;
+                self.windows[n] = ((addr & 7) as usize) * 0x400;
+            }
+            _ => (),
+        }
+    }
+
+    fn write_tia(&mut self, addr: u16, val: u8) {
+        if self.scheme == Bankswitch::Tigervision && addr < 0x40 {
+            let banks = self.rom.len() / 0x800;
+            let bank = (val as usize % banks) * 0x800;
+            self.windows[0] = bank;
+            self.windows[1] = bank + 0x400;
+        }
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        let addr = (addr & 0x0fff) as usize;
+        self.rom[self.windows[addr >> 10] + (addr & 0x3ff)]
+    }
+}
+
+// A headless Atari 2600 (NTSC). The TIA draws into a frame buffer, and
+// WSYNC pauses the CPU through RDY until the end of the line.
+//
+// The RIOT's port A holds the joysticks, and port B the console
+// switches; both are active low.
+pub struct Atari2600 {
+    pub cpu: Nmos,
+    pub sys: Atari2600Sys,
+}
+
+impl Atari2600 {
+    // Panics if the bankswitching scheme can't be guessed from the size
+    // of the ROM.
+    pub fn new(rom: &[u8]) -> Atari2600 {
+        let scheme = Bankswitch::detect(rom).expect("unknown ROM size");
+        Atari2600::with_bankswitch(rom, scheme)
+    }
+
+    pub fn with_bankswitch(rom: &[u8], scheme: Bankswitch) -> Atari2600 {
+        let mut riot = Riot::new();
+        riot.set_pa(0xff);
+        // Color, with both difficulty switches on B.
+        riot.set_pb(0x0b);
+        // The bus ignores A13-A15 and has no interrupt lines, so a plain
+        // Nmos behaves as the 6507 does here.
+        let mut cpu = Nmos::default();
+        cpu.reset();
+        Atari2600 {
+            cpu,
+            sys: Atari2600Sys {
+                tia: Tia::new(),
+                riot,
+                cart: Atari2600Cart::new(rom, scheme),
+                bus: 0,
+                cycles: 0,
+            },
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.sys.cart.reset();
+        self.sys.riot.reset();
+        self.cpu.reset();
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.sys.cycles
+    }
+
+    // Runs one instruction, including any cycles it spends paused by
+    // WSYNC. A halted CPU lets one cycle pass instead.
+    pub fn run_instruction(&mut self) {
+        while !self.cpu.halted() {
+            if self.cpu.run_instruction(&mut self.sys).is_some() {
+                return;
+            }
+        }
+        self.sys.tick();
+    }
+
+    // Runs until the TIA finishes the next frame, and returns it.
+    pub fn run_frame(&mut self) -> &Frame {
+        let frames = self.sys.tia.frame_count();
+        while self.sys.tia.frame_count() == frames {
+            self.run_instruction();
+        }
+        self.sys.tia.frame()
+    }
+
+    pub fn frame(&self) -> &Frame {
+        self.sys.tia.frame()
+    }
+
+    // The joystick directions for SWCHA, with player 0 in the high
+    // nibble: right, left, down and up from bit 7 down.
+    pub fn set_joysticks(&mut self, swcha: u8) {
+        self.sys.riot.set_pa(swcha);
+    }
+
+    // The console switches for SWCHB: reset in bit 0, select in bit 1,
+    // color in bit 3, and the difficulties in bits 6 and 7.
+    pub fn set_switches(&mut self, swchb: u8) {
+        self.sys.riot.set_pb(swchb);
+    }
+
+    pub fn set_fire(&mut self, player: usize, pressed: bool) {
+        self.sys.tia.set_fire(player, pressed);
+    }
+}
+
+pub struct Atari2600Sys {
+    pub tia: Tia,
+    pub riot: Riot,
+    pub cart: Atari2600Cart,
+    bus: u8,
+    cycles: u64,
+}
+
+impl Atari2600Sys {
+    fn tick(&mut self) {
+        self.tia.tick();
+        self.riot.tick();
+        self.cycles += 1;
+    }
+}
+
+// A12 selects the cart; otherwise A7 low selects the TIA, and A7 high
+// the RIOT, with A9 choosing between its RAM and registers.
+impl Sys for Atari2600Sys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        if self.tia.wsync() {
+            self.tick();
+            return None;
+        }
+        let val = if addr & 0x1000 != 0 {
+            self.cart.access(addr);
+            self.cart.read(addr)
+        } else if addr & 0x80 == 0 {
+            // The TIA only drives bits 6 and 7.
+            self.tia.read(addr as u8) | (self.bus & 0x3f)
+        } else if addr & 0x200 == 0 {
+            self.riot.ram[(addr & 0x7f) as usize]
+        } else {
+            self.riot.read(addr as u8 & 0x1f)
+        };
+        self.bus = val;
+        self.tick();
+        Some(val)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        if addr & 0x1000 != 0 {
+            self.cart.access(addr);
+        } else if addr & 0x80 == 0 {
+            self.cart.write_tia(addr, val);
+            self.tia.write(addr as u8, val);
+        } else if addr & 0x200 == 0 {
+            self.riot.ram[(addr & 0x7f) as usize] = val;
+        } else {
+            self.riot.write(addr as u8 & 0x1f, val);
+        }
+        self.bus = val;
+        self.tick();
+        Some(())
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{CycleKind, NmiLength, Sys};
+
+pub trait MappedSys: Sys {
+    fn mapped(&self, addr: u16) -> bool;
+}
+
+// Returns the last value driven on the data bus for reads from
+// addresses that nothing responds to.
+pub struct OpenBus<T: MappedSys> {
+    pub sys: T,
+    data: u8,
+}
+
+impl<T: MappedSys> OpenBus<T> {
+    pub fn new(sys: T) -> OpenBus<T> {
+        OpenBus { sys, data: 0 }
+    }
+
+    pub fn data(&self) -> u8 {
+        self.data
+    }
+}
+
+impl<T: MappedSys> Sys for OpenBus<T> {
+    #[inline]
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.read_kind(addr, CycleKind::Data)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.write_kind(addr, val, CycleKind::Data)
+    }
+
+    #[inline]
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        if self.sys.mapped(addr) {
+            // Only latch the value once the read actually happens, so
+            // a paused read does not disturb the bus.
+            self.data = self.sys.read_kind(addr, kind)?;
+        }
+        Some(self.data)
+    }
+
+    #[inline]
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
+        self.sys.write_kind(addr, val, kind)?;
+        self.data = val;
+        Some(())
+    }
+
+    #[inline]
+    fn set_sync(&mut self, set: bool) {
+        self.sys.set_sync(set);
+    }
+
+    #[inline]
+    fn set_vp(&mut self, set: bool) {
+        self.sys.set_vp(set);
+    }
+
+    #[inline]
+    fn set_ml(&mut self, set: bool) {
+        self.sys.set_ml(set);
+    }
+
+    #[inline]
+    fn poll_nmi(&mut self) -> bool {
+        self.sys.poll_nmi()
+    }
+
+    #[inline]
+    fn peek_nmi(&self) -> bool {
+        self.sys.peek_nmi()
+    }
+
+    #[inline]
+    fn nmi_length(&self) -> NmiLength {
+        self.sys.nmi_length()
+    }
+
+    #[inline]
+    fn irq(&self) -> bool {
+        self.sys.irq()
+    }
+}
@@ -0,0 +1,530 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::Device;
+
+const INT_TA: u8 = 0x01;
+const INT_TB: u8 = 0x02;
+const INT_ALARM: u8 = 0x04;
+const INT_SP: u8 = 0x08;
+const INT_FLAG: u8 = 0x10;
+
+const CR_START: u8 = 0x01;
+const CR_PBON: u8 = 0x02;
+const CR_TOGGLE: u8 = 0x04;
+const CR_ONE_SHOT: u8 = 0x08;
+const CR_LOAD: u8 = 0x10;
+
+#[derive(Clone, Copy, Default, Debug)]
+struct Tod {
+    tenths: u8,
+    sec: u8,
+    min: u8,
+    hr: u8,
+}
+
+impl Tod {
+    fn advance(&mut self) {
+        self.tenths += 1;
+        if self.tenths < 10 {
+            return;
+        }
+        self.tenths = 0;
+        self.sec = bcd_inc(self.sec, 0x59);
+        if self.sec != 0 {
+            return;
+        }
+        self.min = bcd_inc(self.min, 0x59);
+        if self.min != 0 {
+            return;
+        }
+        let pm = self.hr & 0x80;
+        self.hr = match self.hr & 0x1f {
+            0x11 => (pm ^ 0x80) | 0x12,
+            0x12 => pm | 0x01,
+            hr => pm | bcd_inc(hr, 0x12),
+        };
+    }
+
+    fn reg(&self, reg: u8) -> u8 {
+        match reg & 3 {
+            0 => self.tenths,
+            1 => self.sec,
+            2 => self.min,
+            3 => self.hr,
+            _ => unreachable!(),
+        }
+    }
+
+    fn set_reg(&mut self, reg: u8, val: u8) {
+        match reg & 3 {
+            0 => self.tenths = val & 0x0f,
+            1 => self.sec = val & 0x7f,
+            2 => self.min = val & 0x7f,
+            3 => self.hr = val & 0x9f,
+            _ => unreachable!(),
+        }
+    }
+
+    fn matches(&self, other: &Tod) -> bool {
+        self.tenths == other.tenths
+            && self.sec == other.sec
+            && self.min == other.min
+            && self.hr == other.hr
+    }
+}
+
+fn bcd_inc(val: u8, max: u8) -> u8 {
+    if val >= max {
+        0
+    } else if val & 0x0f >= 9 {
+        (val & 0xf0) + 0x10
+    } else {
+        val + 1
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+struct Timer {
+    counter: u16,
+    latch: u16,
+    cr: u8,
+    delay: u8,
+    out: bool,
+    pulse: bool,
+}
+
+impl Timer {
+    fn running(&self) -> bool {
+        self.cr & CR_START != 0
+    }
+
+    fn write_cr(&mut self, val: u8) {
+        if val & CR_START != 0 && !self.running() {
+            // Counting starts two cycles after the timer is started.
+            self.delay = 2;
+            self.out = true;
+        }
+        if val & CR_LOAD != 0 {
+            self.counter = self.latch;
+            self.delay = self.delay.max(1);
+        }
+        self.cr = val & !CR_LOAD;
+    }
+
+    fn write_hi(&mut self, val: u8) {
+        self.latch = (self.latch & 0x00ff) | u16::from(val) << 8;
+        if !self.running() {
+            self.counter = self.latch;
+            // A one-shot timer is also started by a write of the high
+            // byte of its latch.
+            if self.cr & CR_ONE_SHOT != 0 {
+                self.write_cr(self.cr | CR_START);
+            }
+        }
+    }
+
+    // Returns true on underflow.
+    fn count(&mut self) -> bool {
+        if self.counter != 0 {
+            self.counter -= 1;
+            return false;
+        }
+        self.counter = self.latch;
+        if self.cr & CR_ONE_SHOT != 0 {
+            self.cr &= !CR_START;
+        }
+        if self.cr & CR_TOGGLE != 0 {
+            self.out = !self.out;
+        } else {
+            self.pulse = true;
+        }
+        true
+    }
+
+    fn ready(&mut self) -> bool {
+        if self.delay > 0 {
+            self.delay -= 1;
+            return false;
+        }
+        self.running()
+    }
+
+    fn pin(&self) -> Option<bool> {
+        if self.cr & CR_PBON == 0 {
+            None
+        } else if self.cr & CR_TOGGLE != 0 {
+            Some(self.out)
+        } else {
+            Some(self.pulse)
+        }
+    }
+}
+
+// The 6526 Complex Interface Adapter. The TOD clock is driven by the
+// host calling pulse_tod() at the power line frequency.
+//
+// On the C64 the interrupt output of the second CIA drives NMI; it
+// stays asserted until the ICR is read, so a new NMI edge (see
+// poll_nmi) only comes from an interrupt after that read, and the
+// length to report is NmiLength::Plenty.
+#[derive(Clone, Debug)]
+pub struct Cia {
+    pra: u8,
+    prb: u8,
+    ddra: u8,
+    ddrb: u8,
+    pa_in: u8,
+    pb_in: u8,
+    ta: Timer,
+    tb: Timer,
+    tod: Tod,
+    alarm: Tod,
+    tod_latch: Option<Tod>,
+    tod_stopped: bool,
+    tod_div: u8,
+    sdr: u8,
+    sr: u8,
+    sr_bits: u8,
+    sr_half: bool,
+    sr_pending: bool,
+    sp: bool,
+    cnt: bool,
+    flag: bool,
+    icr: u8,
+    mask: u8,
+    irq: bool,
+    irq_delay: bool,
+    icr_read: bool,
+    nmi_edge: bool,
+}
+
+impl Cia {
+    pub fn new() -> Cia {
+        Cia {
+            pra: 0,
+            prb: 0,
+            ddra: 0,
+            ddrb: 0,
+            pa_in: 0xff,
+            pb_in: 0xff,
+            ta: Timer {
+                latch: 0xffff,
+                counter: 0xffff,
+                ..Timer::default()
+            },
+            tb: Timer {
+                latch: 0xffff,
+                counter: 0xffff,
+                ..Timer::default()
+            },
+            tod: Tod {
+                hr: 0x01,
+                ..Tod::default()
+            },
+            alarm: Tod::default(),
+            tod_latch: None,
+            tod_stopped: true,
+            tod_div: 0,
+            sdr: 0,
+            sr: 0,
+            sr_bits: 0,
+            sr_half: false,
+            sr_pending: false,
+            sp: true,
+            cnt: true,
+            flag: true,
+            icr: 0,
+            mask: 0,
+            irq: false,
+            irq_delay: false,
+            icr_read: false,
+            nmi_edge: false,
+        }
+    }
+
+    pub fn pa(&self) -> u8 {
+        (self.pra & self.ddra) | (self.pa_in & !self.ddra)
+    }
+
+    // PB6 and PB7 may be driven by the timers instead.
+    pub fn pb(&self) -> u8 {
+        let mut pb = (self.prb & self.ddrb) | (self.pb_in & !self.ddrb);
+        if let Some(pin) = self.ta.pin() {
+            pb = (pb & !0x40) | ((pin as u8) << 6);
+        }
+        if let Some(pin) = self.tb.pin() {
+            pb = (pb & !0x80) | ((pin as u8) << 7);
+        }
+        pb
+    }
+
+    pub fn set_pa(&mut self, val: u8) {
+        self.pa_in = val;
+    }
+
+    pub fn set_pb(&mut self, val: u8) {
+        self.pb_in = val;
+    }
+
+    // The serial port lines; SP is an output while CRA bit 6 is set.
+    pub fn sp(&self) -> bool {
+        self.sp
+    }
+
+    pub fn set_sp(&mut self, set: bool) {
+        if self.ta.cr & 0x40 == 0 {
+            self.sp = set;
+        }
+    }
+
+    pub fn cnt(&self) -> bool {
+        self.cnt
+    }
+
+    pub fn set_cnt(&mut self, set: bool) {
+        let rise = set && !self.cnt;
+        self.cnt = set;
+        if !rise {
+            return;
+        }
+        if self.ta.cr & 0x40 == 0 {
+            self.sr = (self.sr << 1) | (self.sp as u8);
+            self.sr_bits += 1;
+            if self.sr_bits == 8 {
+                self.sr_bits = 0;
+                self.sdr = self.sr;
+                self.set_flags(INT_SP);
+            }
+        }
+        let mut ta_under = false;
+        if self.ta.cr & 0x20 != 0 && self.ta.running() {
+            ta_under = self.ta.count();
+            if ta_under {
+                self.set_flags(INT_TA);
+            }
+        }
+        if self.tb.cr & 0x60 == 0x20 && self.tb.running() && self.tb.count() {
+            self.set_flags(INT_TB);
+        }
+        if ta_under {
+            self.tb_on_ta_underflow();
+        }
+    }
+
+    // A falling edge on FLAG sets its interrupt flag.
+    pub fn set_flag(&mut self, set: bool) {
+        if self.flag && !set {
+            self.set_flags(INT_FLAG);
+        }
+        self.flag = set;
+    }
+
+    // One cycle of the 50 or 60Hz TOD input, as selected by CRA bit 7.
+    pub fn pulse_tod(&mut self) {
+        self.tod_div += 1;
+        let div = if self.ta.cr & 0x80 != 0 { 5 } else { 6 };
+        if self.tod_div < div {
+            return;
+        }
+        self.tod_div = 0;
+        if !self.tod_stopped {
+            self.tod.advance();
+            self.check_alarm();
+        }
+    }
+
+    pub fn poll_nmi(&mut self) -> bool {
+        let edge = self.nmi_edge;
+        self.nmi_edge = false;
+        edge
+    }
+
+    pub fn peek_nmi(&self) -> bool {
+        self.nmi_edge
+    }
+
+    fn check_alarm(&mut self) {
+        if self.tod.matches(&self.alarm) {
+            self.set_flags(INT_ALARM);
+        }
+    }
+
+    // The interrupt output follows the flags a cycle later, and an
+    // interrupt that happens in the same cycle the ICR is read is lost,
+    // although its flag is still set.
+    fn set_flags(&mut self, flags: u8) {
+        self.icr |= flags;
+        if flags & self.mask != 0 && !self.irq && !self.icr_read {
+            self.irq_delay = true;
+        }
+    }
+
+    fn tb_on_ta_underflow(&mut self) {
+        let count = match self.tb.cr & 0x60 {
+            0x40 => true,
+            0x60 => self.cnt,
+            _ => false,
+        };
+        if count && self.tb.running() && self.tb.count() {
+            self.set_flags(INT_TB);
+        }
+    }
+
+    fn shift_out(&mut self) {
+        if self.sr_bits == 0 {
+            if !self.sr_pending {
+                return;
+            }
+            self.sr_pending = false;
+            self.sr = self.sdr;
+            self.sr_bits = 8;
+        }
+        // Two timer A underflows per bit.
+        self.sr_half = !self.sr_half;
+        self.cnt = !self.sr_half;
+        if self.sr_half {
+            self.sp = (self.sr & 0x80) != 0;
+            self.sr <<= 1;
+            return;
+        }
+        self.sr_bits -= 1;
+        if self.sr_bits == 0 {
+            self.set_flags(INT_SP);
+        }
+    }
+}
+
+impl Default for Cia {
+    fn default() -> Cia {
+        Cia::new()
+    }
+}
+
+impl Device for Cia {
+    fn read(&mut self, reg: u8) -> u8 {
+        match reg & 0x0f {
+            0x0 => self.pa(),
+            0x1 => self.pb(),
+            0x2 => self.ddra,
+            0x3 => self.ddrb,
+            0x4 => self.ta.counter as u8,
+            0x5 => (self.ta.counter >> 8) as u8,
+            0x6 => self.tb.counter as u8,
+            0x7 => (self.tb.counter >> 8) as u8,
+            // Reading the hours latches the clock until the tenths are
+            // read.
+            0x8..=0xb => {
+                let tod = self.tod_latch.unwrap_or(self.tod);
+                match reg & 3 {
+                    3 => self.tod_latch = Some(tod),
+                    0 => self.tod_latch = None,
+                    _ => (),
+                }
+                tod.reg(reg)
+            }
+            0xc => self.sdr,
+            0xd => {
+                let val = self.icr | ((self.irq as u8) << 7);
+                self.icr = 0;
+                self.irq = false;
+                self.irq_delay = false;
+                self.icr_read = true;
+                val
+            }
+            0xe => self.ta.cr,
+            0xf => self.tb.cr,
+            _ => unreachable!(),
+        }
+    }
+
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 0x0f {
+            0x0 => self.pra = val,
+            0x1 => self.prb = val,
+            0x2 => self.ddra = val,
+            0x3 => self.ddrb = val,
+            0x4 => self.ta.latch = (self.ta.latch & 0xff00) | u16::from(val),
+            0x5 => self.ta.write_hi(val),
+            0x6 => self.tb.latch = (self.tb.latch & 0xff00) | u16::from(val),
+            0x7 => self.tb.write_hi(val),
+            // Writing the hours stops the clock until the tenths are
+            // written.
+            0x8..=0xb => {
+                if self.tb.cr & 0x80 != 0 {
+                    self.alarm.set_reg(reg, val);
+                } else {
+                    self.tod.set_reg(reg, val);
+                    match reg & 3 {
+                        3 => self.tod_stopped = true,
+                        0 => self.tod_stopped = false,
+                        _ => (),
+                    }
+                }
+                self.check_alarm();
+            }
+            0xc => {
+                self.sdr = val;
+                if self.ta.cr & 0x40 != 0 {
+                    self.sr_pending = true;
+                }
+            }
+            0xd => {
+                if val & 0x80 != 0 {
+                    self.mask |= val & 0x1f;
+                } else {
+                    self.mask &= !val;
+                }
+                if self.icr & self.mask != 0 && !self.irq {
+                    self.irq_delay = true;
+                }
+            }
+            0xe => {
+                if (self.ta.cr ^ val) & 0x40 != 0 {
+                    self.sr_bits = 0;
+                    self.sr_half = false;
+                }
+                self.ta.write_cr(val);
+            }
+            0xf => self.tb.write_cr(val),
+            _ => unreachable!(),
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.irq_delay {
+            self.irq_delay = false;
+            self.irq = true;
+            self.nmi_edge = true;
+        }
+        self.ta.pulse = false;
+        self.tb.pulse = false;
+
+        let ta_under = self.ta.ready() && self.ta.cr & 0x20 == 0 && {
+            let under = self.ta.count();
+            if under {
+                self.set_flags(INT_TA);
+            }
+            under
+        };
+        if self.tb.ready() && self.tb.cr & 0x60 == 0 && self.tb.count() {
+            self.set_flags(INT_TB);
+        }
+        if ta_under {
+            self.tb_on_ta_underflow();
+            if self.ta.cr & 0x40 != 0 {
+                self.shift_out();
+            }
+        }
+        self.icr_read = false;
+    }
+
+    fn irq(&self) -> bool {
+        self.irq
+    }
+}
@@ -9,7 +9,7 @@
 use std::fmt;
 
 use crate::mi::*;
-use crate::{Cpu, Flags, Status, Sys};
+use crate::{Cpu, CycleKind, Flags, Status, Sys};
 
 mod ops;
 
@@ -34,6 +34,7 @@ pub struct Cmos {
 }
 
 impl Cmos {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> impl Cpu {
         Cmos {
             ..Default::default()
@@ -48,10 +49,10 @@ impl Cpu for Cmos {
             sys.set_sync(true);
             self.check_prev_signals(sys);
             if self.do_int {
-                self.read(sys, self.pc)?;
+                self.read_kind(sys, self.pc, CycleKind::Dummy)?;
                 self.op = 0x00;
             } else {
-                self.op = self.fetch_operand(sys)?.0;
+                self.op = self.fetch_opcode(sys)?.0;
             }
             sys.set_sync(false);
             self.exec(sys)?;
@@ -349,9 +350,10 @@ impl Cmos {
     }
 
     fn addr_zpi<S: Sys>(&mut self, sys: &mut S, reg: Byte) -> Option<Addr> {
-        self.base1 = Addr::zp(self.read(sys, self.pc)?);
+        self.base1 = Addr::zp(self.peek_operand(sys)?);
         // Read from pc instead of base address
-        self.fetch_operand(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.pc += 1;
         Some(self.base1.no_carry(reg))
     }
 
@@ -368,14 +370,14 @@ impl Cmos {
         write: bool,
     ) -> Option<Addr> {
         self.lo_byte = self.fetch_operand(sys)?;
-        self.hi_byte = self.read(sys, self.pc)?;
+        self.hi_byte = self.peek_operand(sys)?;
         self.base1 = self.addr();
         if self.base1.check_carry(reg) {
             // On px, read from pc instead of no-carry base + reg
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         } else if write {
             // On no-px write, read from final address
-            self.read(sys, self.base1 + reg)?;
+            self.dummy_read(sys, self.base1 + reg)?;
         } else {
             self.op_cycle += 1;
         }
@@ -385,15 +387,16 @@ impl Cmos {
 
     fn addr_izp<S: Sys>(&mut self, sys: &mut S) -> Option<Addr> {
         self.base1 = self.addr_zp(sys)?;
-        Some(self.fetch_vector_zp(sys, self.base1)?)
+        self.fetch_vector_zp(sys, self.base1)
     }
 
     fn addr_izx<S: Sys>(&mut self, sys: &mut S) -> Option<Addr> {
-        self.base1 = Addr::zp(self.read(sys, self.pc)?);
+        self.base1 = Addr::zp(self.peek_operand(sys)?);
         // Read from pc instead of base address
-        self.fetch_operand(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.pc += 1;
         self.base1 = self.base1.no_carry(self.x);
-        Some(self.fetch_vector_zp(sys, self.base1)?)
+        self.fetch_vector_zp(sys, self.base1)
     }
 
     fn addr_izy<S>(&mut self, sys: &mut S, write: bool) -> Option<Addr>
@@ -408,7 +411,7 @@ impl Cmos {
 
         if write || self.addr().check_carry(self.y) {
             // Read from vector hi addr instead of no-carry vector + y
-            self.read(sys, self.base1)?;
+            self.dummy_read(sys, self.base1)?;
         } else {
             self.op_cycle += 1;
         }
@@ -417,7 +420,7 @@ impl Cmos {
 
     fn implicit<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         self.poll_signals(sys);
-        self.read(sys, self.pc)?;
+        self.dummy_read(sys, self.pc)?;
         Some(())
     }
 
@@ -431,11 +434,13 @@ impl Cmos {
         F: Fn(&mut Self, Byte) -> Byte,
         S: Sys,
     {
+        sys.set_ml(true);
         self.lo_byte = self.read(sys, addr)?;
         // Read from the address instead of writing
-        self.read(sys, addr)?;
+        self.dummy_read(sys, addr)?;
         self.lo_byte = op(self, self.lo_byte);
         self.store(sys, addr, self.lo_byte)?;
+        sys.set_ml(false);
         Some(())
     }
 
@@ -449,11 +454,11 @@ impl Cmos {
                 // Poll if this is the final cycle
                 self.poll_signals(sys);
             }
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
             if px {
                 self.poll_signals(sys);
                 // Read from pc instead of no-carry pc + offset
-                self.read(sys, self.pc)?;
+                self.dummy_read(sys, self.pc)?;
             }
             self.pc += offset;
         }
@@ -470,7 +475,8 @@ impl Cmos {
         }
         self.lo_byte = self.read(sys, addr)?;
         if self.flags.d {
-            self.load(sys, self.pc)?;
+            self.poll_signals(sys);
+            self.dummy_read(sys, self.pc)?;
         }
         op(self, self.lo_byte);
         Some(())
@@ -486,30 +492,74 @@ impl Cmos {
         Some(Addr::from_bytes(self.lo_byte, self.hi_byte))
     }
 
+    fn fetch_opcode<S: Sys>(&mut self, sys: &mut S) -> Option<Byte> {
+        let val = self.read_kind(sys, self.pc, CycleKind::Opcode)?;
+        self.pc += 1;
+        Some(val)
+    }
+
+    // Read the next operand byte without incrementing PC.
+    fn peek_operand<S: Sys>(&mut self, sys: &mut S) -> Option<Byte> {
+        self.read_kind(sys, self.pc, CycleKind::Operand)
+    }
+
     fn fetch_operand<S: Sys>(&mut self, sys: &mut S) -> Option<Byte> {
-        let val = self.read(sys, self.pc)?;
+        let val = self.peek_operand(sys)?;
         self.pc += 1;
         Some(val)
     }
 
-    fn read<S: Sys>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte> {
-        let val = sys.read(addr.0)?;
+    fn read_kind<S: Sys>(
+        &mut self,
+        sys: &mut S,
+        addr: Addr,
+        kind: CycleKind,
+    ) -> Option<Byte> {
+        let val = sys.read_kind(addr.0, kind)?;
         self.op_cycle += 1;
         Some(MachineInt(val))
     }
 
+    fn read<S: Sys>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte> {
+        self.read_kind(sys, addr, CycleKind::Data)
+    }
+
+    fn dummy_read<S>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte>
+    where
+        S: Sys,
+    {
+        self.read_kind(sys, addr, CycleKind::Dummy)
+    }
+
+    fn read_vector<S>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte>
+    where
+        S: Sys,
+    {
+        self.read_kind(sys, addr, CycleKind::Vector)
+    }
+
     fn load<S: Sys>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte> {
         self.poll_signals(sys);
         self.read(sys, addr)
     }
 
+    fn write_kind<S: Sys>(
+        &mut self,
+        sys: &mut S,
+        addr: Addr,
+        val: Byte,
+        kind: CycleKind,
+    ) -> Option<()> {
+        sys.write_kind(addr.0, val.0, kind)?;
+        self.op_cycle += 1;
+        Some(())
+    }
+
     fn write<S>(&mut self, sys: &mut S, addr: Addr, val: Byte) -> Option<()>
     where
         S: Sys,
     {
-        sys.write(addr.0, val.0)?;
-        self.op_cycle += 1;
-        Some(())
+        self.write_kind(sys, addr, val, CycleKind::Data)
     }
 
     fn store<S>(&mut self, sys: &mut S, addr: Addr, val: Byte) -> Option<()>
@@ -521,11 +571,21 @@ impl Cmos {
     }
 
     fn read_stack<S: Sys>(&mut self, sys: &mut S) -> Option<Byte> {
-        self.read(sys, Addr::stack(self.sp))
+        self.read_kind(sys, Addr::stack(self.sp), CycleKind::Stack)
     }
 
     fn write_stack<S: Sys>(&mut self, sys: &mut S, val: Byte) -> Option<()> {
-        self.write(sys, Addr::stack(self.sp), val)
+        self.write_kind(sys, Addr::stack(self.sp), val, CycleKind::Stack)
+    }
+
+    fn pull<S: Sys>(&mut self, sys: &mut S) -> Option<Byte> {
+        self.poll_signals(sys);
+        self.read_stack(sys)
+    }
+
+    fn push<S: Sys>(&mut self, sys: &mut S, val: Byte) -> Option<()> {
+        self.poll_signals(sys);
+        self.write_stack(sys, val)
     }
 }
 
@@ -564,12 +624,13 @@ impl Cmos {
         S: Sys,
     {
         if self.op_cycle == 1 {
-            self.base1 = Addr::zp(self.read(sys, self.pc)?);
+            self.base1 = Addr::zp(self.peek_operand(sys)?);
         }
 
         // Read from pc instead of base address
         // op_cycle == 2
-        self.fetch_operand(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.pc += 1;
         Some(self.base1.no_carry(reg))
         // op_cycle == 3
     }
@@ -585,17 +646,17 @@ impl Cmos {
         }
 
         if self.op_cycle == 2 {
-            self.hi_byte = self.read(sys, self.pc)?;
+            self.hi_byte = self.peek_operand(sys)?;
         }
 
         // op_cycle == 3
         self.base1 = self.addr();
         if self.base1.check_carry(reg) {
             // On px, read from pc instead of no-carry base + reg
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         } else if write {
             // On no-px write, read from final address
-            self.read(sys, self.base1 + reg)?;
+            self.dummy_read(sys, self.base1 + reg)?;
         } else {
             self.op_cycle += 1;
         }
@@ -606,16 +667,17 @@ impl Cmos {
 
     fn cycle_addr_izx<S: Sys>(&mut self, sys: &mut S) -> Option<Addr> {
         if self.op_cycle == 1 {
-            self.base1 = Addr::zp(self.read(sys, self.pc)?);
+            self.base1 = Addr::zp(self.peek_operand(sys)?);
         }
         if self.op_cycle == 2 {
             // Read from pc instead of base address
-            self.fetch_operand(sys)?;
+            self.dummy_read(sys, self.pc)?;
+            self.pc += 1;
             self.base1 = self.base1.no_carry(self.x);
         }
 
         // op_cycle >= 3
-        Some(self.cycle_fetch_vector_zp(sys, self.base1, 3)?)
+        self.cycle_fetch_vector_zp(sys, self.base1, 3)
         // op_cycle == 5
     }
 
@@ -638,7 +700,7 @@ impl Cmos {
         // op_cycle == 4
         if write || self.addr().check_carry(self.y) {
             // Read from vector hi addr instead of vector + y
-            self.read(sys, self.base1)?;
+            self.dummy_read(sys, self.base1)?;
         } else {
             self.op_cycle += 1;
         }
@@ -652,7 +714,7 @@ impl Cmos {
         }
 
         // op_cycle >= 2
-        Some(self.cycle_fetch_vector_zp(sys, self.base1, 2)?)
+        self.cycle_fetch_vector_zp(sys, self.base1, 2)
         // op_cycle == 4
     }
 
@@ -668,15 +730,18 @@ impl Cmos {
     {
         let start_cycle = MachineInt(start_cycle);
         if self.op_cycle == start_cycle {
+            sys.set_ml(true);
             self.lo_byte = self.read(sys, addr)?;
         }
         if self.op_cycle == start_cycle + 1 {
             // Read from the address instead of writing
-            self.read(sys, addr)?;
+            self.dummy_read(sys, addr)?;
             self.lo_byte = op(self, self.lo_byte);
         }
         // op_cycle == start_cycle + 2
-        self.store(sys, addr, self.lo_byte)
+        self.store(sys, addr, self.lo_byte)?;
+        sys.set_ml(false);
+        Some(())
     }
 
     fn cycle_decimal<F, S: Sys>(
@@ -699,7 +764,8 @@ impl Cmos {
 
         // op_cycle = start_cycle + 1
         if self.flags.d {
-            self.load(sys, self.pc)?;
+            self.poll_signals(sys);
+            self.dummy_read(sys, self.pc)?;
         }
         op(self, self.lo_byte);
         Some(())
@@ -722,14 +788,14 @@ impl Cmos {
                     // Poll if this is the final cycle
                     self.poll_signals(sys);
                 }
-                self.read(sys, self.pc)?;
+                self.dummy_read(sys, self.pc)?;
             }
             if self.op_cycle == 3 {
                 let offset = BranchOffset::as_from(self.lo_byte);
                 if self.pc.check_carry(offset) {
                     self.poll_signals(sys);
                     // Read from pc instead of no-carry pc + offset
-                    self.read(sys, self.pc)?;
+                    self.dummy_read(sys, self.pc)?;
                 }
                 self.pc += offset;
             }
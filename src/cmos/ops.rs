@@ -18,21 +18,21 @@ impl Cmos {
     fn op_00<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         // PC is incremented for BRK but not NMI/IRQ
         if self.do_int {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         } else {
             self.fetch_operand(sys)?;
         }
 
         // The stack writes become reads for RES
         if self.reset {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
         } else {
             self.write_stack(sys, self.pc.hi())?;
         }
         self.sp -= 1;
 
         if self.reset {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
         } else {
             self.write_stack(sys, self.pc.lo())?;
         }
@@ -41,7 +41,7 @@ impl Cmos {
         self.base1 = self.signal_vector(sys);
 
         if self.reset {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
         } else if self.do_int {
             // Clear B flag in saved status for NMI/IRQ
             self.write_stack(sys, self.flags.to_byte() & 0b1110_1111)?;
@@ -50,8 +50,10 @@ impl Cmos {
         }
         self.sp -= 1;
 
-        self.lo_byte = self.read(sys, self.base1)?;
-        self.hi_byte = self.read(sys, self.base1 + 1)?;
+        sys.set_vp(true);
+        self.lo_byte = self.read_vector(sys, self.base1)?;
+        self.hi_byte = self.read_vector(sys, self.base1 + 1)?;
+        sys.set_vp(false);
         self.pc = Addr::from_bytes(self.lo_byte, self.hi_byte);
 
         self.flags.i = true;
@@ -109,8 +111,8 @@ impl Cmos {
 
     // PHP
     fn op_08<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.store(sys, Addr::stack(self.sp), self.flags.to_byte())?;
+        self.dummy_read(sys, self.pc)?;
+        self.push(sys, self.flags.to_byte())?;
         self.sp -= 1;
         Some(())
     }
@@ -272,7 +274,7 @@ impl Cmos {
     // JSR $nnnn
     fn op_20<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         self.lo_byte = self.fetch_operand(sys)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.write_stack(sys, self.pc.hi())?;
         self.sp -= 1;
         self.write_stack(sys, self.pc.lo())?;
@@ -333,10 +335,10 @@ impl Cmos {
 
     // PLP
     fn op_28<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
-        let p = self.load(sys, Addr::stack(self.sp))?;
+        let p = self.pull(sys)?;
         self.flags.from_byte(p);
         Some(())
     }
@@ -503,8 +505,8 @@ impl Cmos {
 
     // RTI
     fn op_40<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
         let p = self.read_stack(sys)?;
         self.sp += 1;
@@ -566,8 +568,8 @@ impl Cmos {
 
     // PHA
     fn op_48<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.store(sys, Addr::stack(self.sp), self.a)?;
+        self.dummy_read(sys, self.pc)?;
+        self.push(sys, self.a)?;
         self.sp -= 1;
         Some(())
     }
@@ -692,8 +694,8 @@ impl Cmos {
 
     // PHY
     fn op_5a<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.store(sys, Addr::stack(self.sp), self.y)?;
+        self.dummy_read(sys, self.pc)?;
+        self.push(sys, self.y)?;
         self.sp -= 1;
         Some(())
     }
@@ -708,10 +710,10 @@ impl Cmos {
     fn op_5c<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         self.base1 =
             Addr::from_bytes(self.addr_abs(sys)?.lo(), MachineInt(0xff));
-        self.read(sys, self.base1)?;
-        self.read(sys, MachineInt(0xffff))?;
-        self.read(sys, MachineInt(0xffff))?;
-        self.read(sys, MachineInt(0xffff))?;
+        self.dummy_read(sys, self.base1)?;
+        self.dummy_read(sys, MachineInt(0xffff))?;
+        self.dummy_read(sys, MachineInt(0xffff))?;
+        self.dummy_read(sys, MachineInt(0xffff))?;
         self.load(sys, MachineInt(0xffff))?;
         Some(())
     }
@@ -739,15 +741,16 @@ impl Cmos {
 
     // RTS
     fn op_60<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
         self.lo_byte = self.read_stack(sys)?;
         self.sp += 1;
         self.hi_byte = self.read_stack(sys)?;
         self.pc = Addr::from_bytes(self.lo_byte, self.hi_byte);
         self.poll_signals(sys);
-        self.fetch_operand(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.pc += 1;
         Some(())
     }
 
@@ -796,10 +799,10 @@ impl Cmos {
 
     // PLA
     fn op_68<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
-        self.a = self.load(sys, Addr::stack(self.sp))?;
+        self.a = self.pull(sys)?;
         self.flags.nz(self.a);
         Some(())
     }
@@ -811,7 +814,8 @@ impl Cmos {
         }
         self.lo_byte = self.fetch_operand(sys)?;
         if self.flags.d {
-            self.load(sys, self.pc)?;
+            self.poll_signals(sys);
+            self.dummy_read(sys, self.pc)?;
         }
         self.ADC(self.lo_byte);
         Some(())
@@ -921,10 +925,10 @@ impl Cmos {
 
     // PLY
     fn op_7a<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
-        self.y = self.load(sys, Addr::stack(self.sp))?;
+        self.y = self.pull(sys)?;
         self.flags.nz(self.y);
         Some(())
     }
@@ -938,9 +942,10 @@ impl Cmos {
     // JMP ($nnnn,X)
     fn op_7c<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         self.lo_byte = self.fetch_operand(sys)?;
-        self.hi_byte = self.read(sys, self.pc)?;
+        self.hi_byte = self.peek_operand(sys)?;
         self.base1 = self.addr() + self.x;
-        self.fetch_operand(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.pc += 1;
         self.lo_byte = self.read(sys, self.base1)?;
         self.hi_byte = self.load(sys, self.base1 + 1)?;
         self.pc = Addr::from_bytes(self.lo_byte, self.hi_byte);
@@ -1579,8 +1584,8 @@ impl Cmos {
 
     // PHX
     fn op_da<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.store(sys, Addr::stack(self.sp), self.x)?;
+        self.dummy_read(sys, self.pc)?;
+        self.push(sys, self.x)?;
         self.sp -= 1;
         Some(())
     }
@@ -1684,7 +1689,8 @@ impl Cmos {
         }
         self.lo_byte = self.fetch_operand(sys)?;
         if self.flags.d {
-            self.load(sys, self.pc)?;
+            self.poll_signals(sys);
+            self.dummy_read(sys, self.pc)?;
         }
         self.SBC(self.lo_byte);
         Some(())
@@ -1790,10 +1796,10 @@ impl Cmos {
 
     // PLX
     fn op_fa<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
-        self.x = self.load(sys, Addr::stack(self.sp))?;
+        self.x = self.pull(sys)?;
         self.flags.nz(self.x);
         Some(())
     }
@@ -1831,10 +1837,7 @@ impl Cmos {
 }
 
 impl Cmos {
-    #[cfg_attr(
-        feature = "cargo-clippy",
-        allow(clippy::cyclomatic_complexity)
-    )]
+    #[allow(clippy::cognitive_complexity)]
     pub(crate) fn exec<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         match self.op {
             0x00 => self.op_00(sys)?,
@@ -2093,7 +2096,6 @@ impl Cmos {
             0xfd => self.op_fd(sys)?,
             0xfe => self.op_fe(sys)?,
             0xff => self.op_ff(sys)?,
-            _ => unreachable!(),
         }
         Some(())
     }
@@ -16,7 +16,7 @@ impl Cmos {
     fn cycle_op_00<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
             if self.do_int {
-                self.read(sys, self.pc)?;
+                self.dummy_read(sys, self.pc)?;
             } else {
                 self.fetch_operand(sys)?;
             }
@@ -24,7 +24,7 @@ impl Cmos {
 
         if self.op_cycle == 2 {
             if self.reset {
-                self.read_stack(sys)?;
+                self.dummy_read(sys, Addr::stack(self.sp))?;
             } else {
                 self.write_stack(sys, self.pc.hi())?;
             }
@@ -33,7 +33,7 @@ impl Cmos {
 
         if self.op_cycle == 3 {
             if self.reset {
-                self.read_stack(sys)?;
+                self.dummy_read(sys, Addr::stack(self.sp))?;
             } else {
                 self.write_stack(sys, self.pc.lo())?;
             }
@@ -43,7 +43,7 @@ impl Cmos {
 
         if self.op_cycle == 4 {
             if self.reset {
-                self.read_stack(sys)?;
+                self.dummy_read(sys, Addr::stack(self.sp))?;
             } else if self.do_int {
                 self.write_stack(sys, self.flags.to_byte() & 0b1110_1111)?;
             } else {
@@ -53,11 +53,13 @@ impl Cmos {
         }
 
         if self.op_cycle == 5 {
-            self.lo_byte = self.read(sys, self.base1)?;
+            sys.set_vp(true);
+            self.lo_byte = self.read_vector(sys, self.base1)?;
         }
 
         // op_cycle == 6
-        self.hi_byte = self.read(sys, self.base1 + 1)?;
+        self.hi_byte = self.read_vector(sys, self.base1 + 1)?;
+        sys.set_vp(false);
         self.pc = Addr::from_bytes(self.lo_byte, self.hi_byte);
         self.flags.i = true;
         self.flags.d = false;
@@ -117,11 +119,11 @@ impl Cmos {
     // PHP
     fn cycle_op_08<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         // op_cycle == 2
-        self.store(sys, Addr::stack(self.sp), self.flags.to_byte())?;
+        self.push(sys, self.flags.to_byte())?;
         self.sp -= 1;
         Some(())
     }
@@ -286,7 +288,7 @@ impl Cmos {
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
         }
 
         if self.op_cycle == 3 {
@@ -360,16 +362,16 @@ impl Cmos {
     // PLP
     fn cycle_op_28<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
         // op_cycle == 3
-        let p = self.load(sys, Addr::stack(self.sp))?;
+        let p = self.pull(sys)?;
         self.flags.from_byte(p);
         Some(())
     }
@@ -536,11 +538,11 @@ impl Cmos {
     // RTI
     fn cycle_op_40<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
@@ -615,11 +617,11 @@ impl Cmos {
     // PHA
     fn cycle_op_48<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         // op_cycle == 2
-        self.store(sys, Addr::stack(self.sp), self.a)?;
+        self.push(sys, self.a)?;
         self.sp -= 1;
         Some(())
     }
@@ -747,11 +749,11 @@ impl Cmos {
     // PHY
     fn cycle_op_5a<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         // op_cycle == 2
-        self.store(sys, Addr::stack(self.sp), self.y)?;
+        self.push(sys, self.y)?;
         self.sp -= 1;
         Some(())
     }
@@ -768,11 +770,11 @@ impl Cmos {
         }
 
         if self.op_cycle == 3 {
-            self.load(sys, self.base1)?;
+            self.dummy_read(sys, self.base1)?;
         }
 
         while self.op_cycle < 7 {
-            self.read(sys, MachineInt(0xffff))?;
+            self.dummy_read(sys, MachineInt(0xffff))?;
         }
         self.load(sys, MachineInt(0xffff))?;
         Some(())
@@ -805,11 +807,11 @@ impl Cmos {
     // RTS
     fn cycle_op_60<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
@@ -825,7 +827,8 @@ impl Cmos {
         }
 
         // op_cycle == 5
-        self.fetch_operand(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.pc += 1;
         Some(())
     }
 
@@ -878,16 +881,16 @@ impl Cmos {
     // PLA
     fn cycle_op_68<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
         // op_cycle == 3
-        self.a = self.load(sys, Addr::stack(self.sp))?;
+        self.a = self.pull(sys)?;
         self.flags.nz(self.a);
         Some(())
     }
@@ -903,7 +906,8 @@ impl Cmos {
 
         // op_cycle == 2
         if self.flags.d {
-            self.load(sys, self.pc)?;
+            self.poll_signals(sys);
+            self.dummy_read(sys, self.pc)?;
         }
         self.ADC(self.lo_byte);
         Some(())
@@ -1024,16 +1028,16 @@ impl Cmos {
     // PLY
     fn cycle_op_7a<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
         // op_cycle == 3
-        self.y = self.load(sys, Addr::stack(self.sp))?;
+        self.y = self.pull(sys)?;
         self.flags.nz(self.y);
         Some(())
     }
@@ -1047,12 +1051,13 @@ impl Cmos {
         }
 
         if self.op_cycle == 2 {
-            self.hi_byte = self.read(sys, self.pc)?;
+            self.hi_byte = self.peek_operand(sys)?;
             self.base1 = self.addr() + self.x;
         }
 
         if self.op_cycle == 3 {
-            self.fetch_operand(sys)?;
+            self.dummy_read(sys, self.pc)?;
+            self.pc += 1;
         }
 
         if self.op_cycle == 4 {
@@ -1670,11 +1675,11 @@ impl Cmos {
     // PHX
     fn cycle_op_da<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         // op_cycle == 2
-        self.store(sys, Addr::stack(self.sp), self.x)?;
+        self.push(sys, self.x)?;
         self.sp -= 1;
         Some(())
     }
@@ -1775,7 +1780,8 @@ impl Cmos {
 
         // op_cycle == 2
         if self.flags.d {
-            self.load(sys, self.pc)?;
+            self.poll_signals(sys);
+            self.dummy_read(sys, self.pc)?;
         }
         self.SBC(self.lo_byte);
         Some(())
@@ -1892,16 +1898,16 @@ impl Cmos {
     // PLX
     fn cycle_op_fa<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
         // op_cycle == 3
-        self.x = self.load(sys, Addr::stack(self.sp))?;
+        self.x = self.pull(sys)?;
         self.flags.nz(self.x);
         Some(())
     }
@@ -1943,10 +1949,7 @@ impl Cmos {
 }
 
 impl Cmos {
-    #[cfg_attr(
-        feature = "cargo-clippy",
-        allow(clippy::cyclomatic_complexity)
-    )]
+    #[allow(clippy::cognitive_complexity)]
     pub(crate) fn cycle_exec<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         match self.op {
             0x00 => self.cycle_op_00(sys)?,
@@ -2205,7 +2208,6 @@ impl Cmos {
             0xfd => self.cycle_op_fd(sys)?,
             0xfe => self.cycle_op_fe(sys)?,
             0xff => self.op_ff(sys)?,
-            _ => unreachable!(),
         }
         Some(())
     }
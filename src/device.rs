@@ -0,0 +1,20 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// A peripheral chip on the CPU bus. A Sys maps its registers into the
+// address space, and calls tick() once per CPU cycle, after any access
+// made in that cycle.
+pub trait Device {
+    fn read(&mut self, reg: u8) -> u8;
+
+    fn write(&mut self, reg: u8, val: u8);
+
+    fn tick(&mut self);
+
+    fn irq(&self) -> bool;
+}
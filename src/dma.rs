@@ -0,0 +1,101 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{Apu, CycleKind, Sys};
+
+// The 2A03's DMA unit, which halts the CPU on its next read cycle, then
+// takes over the bus until the OAM and DMC transfers are done. DMA
+// reads happen on "get" cycles and writes on "put" cycles, so an
+// alignment cycle may be needed.
+//
+// An OAM transfer is a halt cycle, perhaps an alignment cycle, then 256
+// reads and writes to $2004: 513 or 514 cycles. A DMC fetch is a halt
+// cycle, a dummy cycle, perhaps an alignment cycle, then the read: 3 or
+// 4 cycles, more if the CPU is writing, and fewer when it overlaps an
+// OAM transfer.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Dma {
+    // The next OAM source address.
+    oam: Option<u16>,
+    latch: Option<u8>,
+    dmc: bool,
+    running: bool,
+    need_halt: bool,
+    need_dummy: bool,
+}
+
+impl Dma {
+    pub(crate) fn start_oam(&mut self, page: u8) {
+        self.oam = Some((page as u16) << 8);
+        self.latch = None;
+        self.need_halt = true;
+    }
+
+    pub(crate) fn set_dmc(&mut self, pending: bool) {
+        if pending && !self.dmc {
+            self.need_halt = true;
+            self.need_dummy = true;
+        }
+        self.dmc = pending;
+    }
+
+    pub(crate) fn active(&self) -> bool {
+        self.oam.is_some() || self.dmc
+    }
+
+    // Runs one cycle in place of the CPU's read from addr. Any cycle
+    // that isn't a transfer repeats that read, as the real chip does.
+    pub(crate) fn cycle<S: Sys>(
+        &mut self,
+        sys: &mut S,
+        apu: &mut Apu,
+        addr: u16,
+        kind: CycleKind,
+        get: bool,
+    ) -> Option<()> {
+        if !self.running {
+            sys.read_kind(addr, kind)?;
+            self.running = true;
+            self.need_halt = false;
+            return Some(());
+        }
+
+        let dmc_ready = get && self.dmc && !self.need_halt && !self.need_dummy;
+        match (apu.dmc_request(), self.oam, self.latch) {
+            (Some(src), _, _) if dmc_ready => {
+                // A paused fetch leaves the request in place to retry.
+                let val = sys.read_kind(src, CycleKind::Data)?;
+                apu.dmc_fill(val);
+                self.dmc = false;
+            }
+            (_, Some(src), None) if get => {
+                self.latch = Some(sys.read_kind(src, CycleKind::Data)?);
+            }
+            (_, Some(src), Some(val)) if !get => {
+                sys.write_kind(0x2004, val, CycleKind::Data)?;
+                self.latch = None;
+                let src = src.wrapping_add(1);
+                self.oam = if src & 0xff == 0 { None } else { Some(src) };
+            }
+            _ => {
+                sys.read_kind(addr, kind)?;
+            }
+        }
+
+        // OAM cycles count as halt and dummy cycles for the DMC.
+        if self.need_halt {
+            self.need_halt = false;
+        } else {
+            self.need_dummy = false;
+        }
+        if !self.active() {
+            self.running = false;
+        }
+        Some(())
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{Acia, Cmos, Cpu, Device, Hd44780, Sys, Via};
+
+const LCD_E: u8 = 0x80;
+const LCD_RW: u8 = 0x40;
+const LCD_RS: u8 = 0x20;
+
+// A breadboard 65C02 computer in the style of Ben Eater's, with the
+// usual address decoding: RAM at $0000-$3FFF, a 65C51 ACIA at $5000,
+// a 6522 VIA at $6000, and a 32K ROM at $8000. The VIA drives a 16x2
+// HD44780 LCD, with the data bus on port B and E, RW and RS on PA7,
+// PA6 and PA5. The ACIA and VIA share the IRQ line.
+pub struct BenEater {
+    pub cpu: Cmos,
+    pub sys: BenEaterSys,
+}
+
+impl BenEater {
+    pub fn new(rom: &[u8], cpu_hz: u64) -> BenEater {
+        assert_eq!(rom.len(), 0x8000, "ROM must be 32K");
+        let mut cpu = Cmos::default();
+        cpu.reset();
+        BenEater {
+            cpu,
+            sys: BenEaterSys {
+                ram: vec![0; 0x8000],
+                rom: rom.to_vec(),
+                acia: Acia::wdc(cpu_hz),
+                via: Via::new(),
+                lcd: Hd44780::new(16, 2, cpu_hz),
+                cycles: 0,
+            },
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.sys.acia.reset();
+        self.sys.via.reset();
+        self.sys.update_lcd();
+        self.cpu.reset();
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.sys.cycles
+    }
+
+    pub fn run_instruction(&mut self) {
+        self.cpu.run_instruction(&mut self.sys);
+    }
+
+    // Runs whole instructions until at least the given number of cycles
+    // have passed.
+    pub fn run_cycles(&mut self, cycles: u64) {
+        let end = self.sys.cycles + cycles;
+        while self.sys.cycles < end && !self.cpu.halted() {
+            self.run_instruction();
+        }
+    }
+
+    pub fn lcd(&self) -> &Hd44780 {
+        &self.sys.lcd
+    }
+
+    // The rows of the LCD, joined with newlines.
+    pub fn lcd_text(&self) -> String {
+        self.sys.lcd.text()
+    }
+}
+
+// The RAM is a 32K chip, but only the lower half is decoded.
+pub struct BenEaterSys {
+    pub ram: Vec<u8>,
+    pub rom: Vec<u8>,
+    pub acia: Acia,
+    pub via: Via,
+    pub lcd: Hd44780,
+    cycles: u64,
+}
+
+impl BenEaterSys {
+    // Presents the VIA pins to the LCD, and puts anything the LCD
+    // drives back on port B.
+    fn update_lcd(&mut self) {
+        let pa = self.via.pa();
+        let data = self.lcd.bus(
+            pa & LCD_E != 0,
+            pa & LCD_RW != 0,
+            pa & LCD_RS != 0,
+            self.via.pb(),
+        );
+        self.via.set_pb(data.unwrap_or(0xff));
+    }
+
+    fn tick(&mut self) {
+        self.acia.tick();
+        self.via.tick();
+        self.lcd.tick();
+        self.cycles += 1;
+    }
+}
+
+impl Sys for BenEaterSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let val = match addr {
+            0x0000..=0x3fff => self.ram[addr as usize],
+            0x8000..=0xffff => self.rom[(addr & 0x7fff) as usize],
+            // The VIA is selected by A14 and A13, the ACIA by A14 and
+            // A12; both respond at $7000.
+            _ => {
+                let mut val = 0xff;
+                if addr & 0x2000 != 0 {
+                    self.update_lcd();
+                    val &= self.via.read(addr as u8 & 0x0f);
+                }
+                if addr & 0x1000 != 0 {
+                    val &= self.acia.read(addr as u8 & 0x03);
+                }
+                val
+            }
+        };
+        self.tick();
+        Some(val)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        match addr {
+            0x0000..=0x3fff => self.ram[addr as usize] = val,
+            0x8000..=0xffff => (),
+            _ => {
+                if addr & 0x2000 != 0 {
+                    self.via.write(addr as u8 & 0x0f, val);
+                    self.update_lcd();
+                }
+                if addr & 0x1000 != 0 {
+                    self.acia.write(addr as u8 & 0x03, val);
+                }
+            }
+        }
+        self.tick();
+        Some(())
+    }
+
+    fn irq(&self) -> bool {
+        self.acia.irq() || self.via.irq()
+    }
+}
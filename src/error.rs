@@ -0,0 +1,33 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::error::Error;
+use std::fmt;
+
+// The ways a file loader can reject its input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    // The data doesn't start with the format's signature.
+    BadMagic,
+    // The data ends before its header or contents say it should.
+    Truncated,
+    // A field holds a value the loader can't handle.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::BadMagic => write!(f, "unrecognized file format"),
+            LoadError::Truncated => write!(f, "file is truncated"),
+            LoadError::Invalid(what) => write!(f, "invalid file: {}", what),
+        }
+    }
+}
+
+impl Error for LoadError {}
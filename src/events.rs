@@ -0,0 +1,184 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::VecDeque;
+
+use crate::{CycleKind, NmiLength, Sys};
+
+type Callback<S> = Box<dyn FnOnce(&mut Events<S>)>;
+
+// Counts the bus cycles of the wrapped Sys and runs callbacks at the
+// cycles they were scheduled for. Callbacks can drive the IRQ and NMI
+// lines, which are combined with those of the wrapped Sys.
+pub struct Events<S: Sys> {
+    pub sys: S,
+    cycle: u64,
+    seq: u64,
+    queue: VecDeque<(u64, u64, Callback<S>)>,
+    irq: bool,
+    // The latched falling edge, kept until the CPU polls it, and the
+    // level of the line.
+    nmi: bool,
+    nmi_line: bool,
+    nmi_length: NmiLength,
+    // Counts NMI pulses, so the end of one doesn't cut short the next.
+    nmi_pulse: u64,
+}
+
+impl<S: Sys> Events<S> {
+    pub fn new(sys: S) -> Events<S> {
+        Events {
+            sys,
+            cycle: 0,
+            seq: 0,
+            queue: VecDeque::new(),
+            irq: false,
+            nmi: false,
+            nmi_line: false,
+            nmi_length: NmiLength::Plenty,
+            nmi_pulse: 0,
+        }
+    }
+
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    // The callback runs before the bus access of the given cycle, or
+    // before the next one if that cycle has already started. Callbacks
+    // for the same cycle run in the order they were scheduled.
+    pub fn schedule<F>(&mut self, cycle: u64, f: F)
+    where
+        F: FnOnce(&mut Events<S>) + 'static,
+    {
+        let key = (cycle, self.seq);
+        self.seq += 1;
+        let pos = self.queue.partition_point(|e| (e.0, e.1) < key);
+        self.queue.insert(pos, (key.0, key.1, Box::new(f)));
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn set_irq(&mut self, set: bool) {
+        self.irq = set;
+    }
+
+    pub fn nmi_line(&self) -> bool {
+        self.nmi_line
+    }
+
+    // Starts an NMI pulse that lasts the given number of cycles; a
+    // line that is held low indefinitely is a very long pulse. The line
+    // is released when the pulse ends, but the edge stays latched until
+    // the CPU polls it. A pulse while the line is still low only
+    // extends it.
+    pub fn pulse_nmi(&mut self, cycles: u64) {
+        if !self.nmi_line {
+            self.nmi = true;
+        }
+        self.nmi_line = true;
+        self.nmi_length = match cycles {
+            0 | 1 => NmiLength::One,
+            2 => NmiLength::Two,
+            _ => NmiLength::Plenty,
+        };
+        self.nmi_pulse += 1;
+        let pulse = self.nmi_pulse;
+        let end = self.cycle.saturating_add(cycles.max(1));
+        self.schedule(end, move |ev| {
+            if ev.nmi_pulse == pulse {
+                ev.nmi_line = false;
+            }
+        });
+    }
+
+    fn run_events(&mut self) {
+        while self.queue.front().is_some_and(|e| e.0 <= self.cycle) {
+            let (_, _, f) = self.queue.pop_front().unwrap();
+            f(self);
+        }
+    }
+}
+
+impl<S: Sys> Sys for Events<S> {
+    #[inline]
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.read_kind(addr, CycleKind::Data)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.write_kind(addr, val, CycleKind::Data)
+    }
+
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        self.run_events();
+        let val = self.sys.read_kind(addr, kind)?;
+        self.cycle += 1;
+        // Run the events for the next cycle now, so the CPU sees their
+        // effect on the signals it polls before that cycle's access.
+        self.run_events();
+        Some(val)
+    }
+
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
+        self.run_events();
+        self.sys.write_kind(addr, val, kind)?;
+        self.cycle += 1;
+        self.run_events();
+        Some(())
+    }
+
+    #[inline]
+    fn set_sync(&mut self, set: bool) {
+        self.sys.set_sync(set);
+    }
+
+    #[inline]
+    fn set_vp(&mut self, set: bool) {
+        self.sys.set_vp(set);
+    }
+
+    #[inline]
+    fn set_ml(&mut self, set: bool) {
+        self.sys.set_ml(set);
+    }
+
+    #[inline]
+    fn poll_nmi(&mut self) -> bool {
+        let nmi = self.nmi;
+        self.nmi = false;
+        self.sys.poll_nmi() || nmi
+    }
+
+    #[inline]
+    fn peek_nmi(&self) -> bool {
+        self.nmi || self.sys.peek_nmi()
+    }
+
+    #[inline]
+    fn nmi_length(&self) -> NmiLength {
+        if self.nmi {
+            self.nmi_length
+        } else {
+            self.sys.nmi_length()
+        }
+    }
+
+    #[inline]
+    fn irq(&self) -> bool {
+        self.irq || self.sys.irq()
+    }
+}
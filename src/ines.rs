@@ -0,0 +1,567 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{LoadError, Sys};
+
+// The PPU timing, in dots, that NesSys counts to find vblank and the
+// MMC3's scanlines.
+const DOTS: u16 = 341;
+const LINES: u16 = 262;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mirroring {
+    Horizontal,
+    Vertical,
+    FourScreen,
+    // One-screen, from the first or second nametable.
+    SingleLow,
+    SingleHigh,
+}
+
+// An iNES or NES 2.0 file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ines {
+    pub nes2: bool,
+    pub mapper: u16,
+    // Always zero for iNES.
+    pub submapper: u8,
+    pub mirroring: Mirroring,
+    pub battery: bool,
+    // 512 bytes for $7000-$71FF.
+    pub trainer: Option<Vec<u8>>,
+    // Including any battery-backed RAM.
+    pub prg_ram: usize,
+    pub chr_ram: usize,
+    pub pal: bool,
+    pub prg: Vec<u8>,
+    pub chr: Vec<u8>,
+}
+
+impl Ines {
+    pub fn parse(file: &[u8]) -> Result<Ines, LoadError> {
+        if !file.starts_with(b"NES\x1a") {
+            return Err(LoadError::BadMagic);
+        }
+        if file.len() < 16 {
+            return Err(LoadError::Truncated);
+        }
+        let (flags6, flags7) = (file[6], file[7]);
+        let nes2 = flags7 & 0x0c == 0x08;
+        let mut mapper = u16::from(flags6 >> 4);
+        let mut submapper = 0;
+        let (prg_len, chr_len, prg_ram, chr_ram, pal);
+        if nes2 {
+            mapper |= u16::from(flags7 & 0xf0) | u16::from(file[8] & 0x0f) << 8;
+            submapper = file[8] >> 4;
+            prg_len = rom_size(file[4], file[9] & 0x0f, 0x4000)?;
+            chr_len = rom_size(file[5], file[9] >> 4, 0x2000)?;
+            prg_ram = ram_size(file[10] & 0x0f) + ram_size(file[10] >> 4);
+            chr_ram = ram_size(file[11] & 0x0f) + ram_size(file[11] >> 4);
+            pal = file[12] & 3 == 1;
+        } else {
+            // Old dumping tools left text in bytes 7-15, so the high
+            // nibble of the mapper is only trusted if the end is clean.
+            if file[12..16].iter().all(|&b| b == 0) {
+                mapper |= u16::from(flags7 & 0xf0);
+            }
+            prg_len = usize::from(file[4]) * 0x4000;
+            chr_len = usize::from(file[5]) * 0x2000;
+            prg_ram = usize::from(file[8].max(1)) * 0x2000;
+            chr_ram = if chr_len == 0 { 0x2000 } else { 0 };
+            pal = file[9] & 1 == 1;
+        }
+        if prg_len == 0 {
+            return Err(LoadError::Invalid("no PRG ROM"));
+        }
+        let end = prg_len
+            .checked_add(chr_len)
+            .ok_or(LoadError::Invalid("bad ROM size"))?;
+
+        let mut rest = &file[16..];
+        let trainer = if flags6 & 0x04 != 0 {
+            let trainer = rest.get(..0x200).ok_or(LoadError::Truncated)?;
+            rest = &rest[0x200..];
+            Some(trainer.to_vec())
+        } else {
+            None
+        };
+        let prg = rest.get(..prg_len).ok_or(LoadError::Truncated)?;
+        let chr = rest.get(prg_len..end).ok_or(LoadError::Truncated)?;
+        let mirroring = if flags6 & 0x08 != 0 {
+            Mirroring::FourScreen
+        } else if flags6 & 0x01 != 0 {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        };
+        Ok(Ines {
+            nes2,
+            mapper,
+            submapper,
+            mirroring,
+            battery: flags6 & 0x02 != 0,
+            trainer,
+            prg_ram,
+            chr_ram,
+            pal,
+            prg: prg.to_vec(),
+            chr: chr.to_vec(),
+        })
+    }
+}
+
+// NES 2.0 sizes are counted in units, unless the high nibble is $F, in
+// which case the low byte holds an exponent and multiplier.
+fn rom_size(lsb: u8, msb: u8, unit: usize) -> Result<usize, LoadError> {
+    let size = if msb == 0x0f {
+        1usize
+            .checked_shl(u32::from(lsb >> 2))
+            .and_then(|size| size.checked_mul(usize::from(lsb & 3) * 2 + 1))
+    } else {
+        (usize::from(msb) << 8 | usize::from(lsb)).checked_mul(unit)
+    };
+    size.ok_or(LoadError::Invalid("bad ROM size"))
+}
+
+fn ram_size(shift: u8) -> usize {
+    if shift == 0 {
+        0
+    } else {
+        64 << shift
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Board {
+    Nrom,
+    Mmc1,
+    Uxrom,
+    Cnrom,
+    Mmc3,
+}
+
+// The cartridge side of the CPU bus: PRG ROM at $8000-$FFFF and PRG
+// RAM at $6000-$7FFF, both switched in 8K windows, and the mapper's
+// registers. CHR banking is tracked for a PPU to use.
+pub struct NesCart {
+    pub prg_ram: Vec<u8>,
+    prg: Vec<u8>,
+    board: Board,
+    mirroring: Mirroring,
+    four_screen: bool,
+    // The PRG ROM offset mapped into each 8K window at $8000-$E000,
+    // and the CHR offset of each 1K window.
+    prg_windows: [usize; 4],
+    chr_windows: [usize; 8],
+    chr_len: usize,
+    ram_enabled: bool,
+    ram_writable: bool,
+    // The MMC1's shift register, and the cycle of its last write.
+    shift: u8,
+    shift_count: u8,
+    last_write: Option<u64>,
+    // MMC1 control, CHR 0, CHR 1 and PRG; MMC3 bank select, then R0-R7;
+    // the UxROM and CNROM bank.
+    regs: [u8; 9],
+    // The MMC3 scanline counter.
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq: bool,
+}
+
+impl NesCart {
+    pub fn new(ines: &Ines) -> Result<NesCart, LoadError> {
+        let board = match ines.mapper {
+            0 => Board::Nrom,
+            1 => Board::Mmc1,
+            2 => Board::Uxrom,
+            3 => Board::Cnrom,
+            4 => Board::Mmc3,
+            _ => return Err(LoadError::Invalid("unsupported mapper")),
+        };
+        if !ines.prg.len().is_power_of_two() || ines.prg.len() < 0x2000 {
+            return Err(LoadError::Invalid("bad PRG ROM size"));
+        }
+        let mut prg_ram = vec![0; ines.prg_ram];
+        if let Some(trainer) = ines.trainer.as_ref() {
+            if prg_ram.len() < 0x2000 {
+                prg_ram.resize(0x2000, 0);
+            }
+            prg_ram[0x1000..0x1200].copy_from_slice(trainer);
+        }
+        let mut cart = NesCart {
+            prg_ram,
+            prg: ines.prg.clone(),
+            board,
+            mirroring: ines.mirroring,
+            four_screen: ines.mirroring == Mirroring::FourScreen,
+            prg_windows: [0; 4],
+            chr_windows: [0; 8],
+            chr_len: ines.chr.len().max(ines.chr_ram).max(0x2000),
+            ram_enabled: true,
+            ram_writable: true,
+            shift: 0,
+            shift_count: 0,
+            last_write: None,
+            regs: [0; 9],
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq: false,
+        };
+        cart.reset();
+        Ok(cart)
+    }
+
+    // The MMC1 starts with the last bank fixed at $C000, and the MMC3
+    // with its banks in order; the others start in their first banks.
+    pub fn reset(&mut self) {
+        self.regs = match self.board {
+            Board::Mmc1 => [0x0c, 0, 0, 0, 0, 0, 0, 0, 0],
+            Board::Mmc3 => [0, 0, 2, 4, 5, 6, 7, 0, 1],
+            _ => [0; 9],
+        };
+        self.shift = 0;
+        self.shift_count = 0;
+        self.last_write = None;
+        self.ram_enabled = true;
+        self.ram_writable = true;
+        self.irq_enabled = false;
+        self.irq = false;
+        self.update();
+    }
+
+    pub fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    // The PRG ROM offset of the 8K window at $8000, $A000, $C000 or
+    // $E000.
+    pub fn prg_window(&self, n: usize) -> usize {
+        self.prg_windows[n]
+    }
+
+    // The CHR offset of the 1K window at $0000-$1C00 of PPU space.
+    pub fn chr_window(&self, n: usize) -> usize {
+        self.chr_windows[n]
+    }
+
+    pub fn irq(&self) -> bool {
+        self.irq
+    }
+
+    // Clocks the MMC3's scanline counter, as A12 rising on the PPU bus
+    // does once a rendered line. The other mappers ignore it.
+    pub fn clock_scanline(&mut self) {
+        if self.board != Board::Mmc3 {
+            return;
+        }
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq = true;
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7fff => self.read_ram(addr),
+            0x8000..=0xffff => {
+                let window = self.prg_windows[((addr >> 13) & 3) as usize];
+                Some(self.prg[window + (addr & 0x1fff) as usize])
+            }
+            _ => None,
+        }
+    }
+
+    // The cycle lets the MMC1 ignore the second of two writes on
+    // consecutive cycles, as from a read-modify-write instruction.
+    pub fn write(&mut self, addr: u16, val: u8, cycle: u64) {
+        match addr {
+            0x6000..=0x7fff if self.ram_enabled && self.ram_writable => {
+                let len = self.prg_ram.len();
+                if len != 0 {
+                    self.prg_ram[(addr & 0x1fff) as usize % len] = val;
+                }
+            }
+            0x8000..=0xffff => {
+                match self.board {
+                    Board::Nrom => (),
+                    Board::Mmc1 => self.write_mmc1(addr, val, cycle),
+                    Board::Uxrom | Board::Cnrom => self.regs[0] = val,
+                    Board::Mmc3 => self.write_mmc3(addr, val),
+                }
+                self.update();
+            }
+            _ => (),
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> Option<u8> {
+        if !self.ram_enabled || self.prg_ram.is_empty() {
+            return None;
+        }
+        let len = self.prg_ram.len();
+        Some(self.prg_ram[(addr & 0x1fff) as usize % len])
+    }
+
+    fn write_mmc1(&mut self, addr: u16, val: u8, cycle: u64) {
+        let consecutive = self.last_write == Some(cycle.wrapping_sub(1));
+        self.last_write = Some(cycle);
+        if consecutive {
+            return;
+        }
+        if val & 0x80 != 0 {
+            self.shift = 0;
+            self.shift_count = 0;
+            self.regs[0] |= 0x0c;
+            return;
+        }
+        self.shift |= (val & 1) << self.shift_count;
+        self.shift_count += 1;
+        if self.shift_count == 5 {
+            self.regs[((addr >> 13) & 3) as usize] = self.shift;
+            self.shift = 0;
+            self.shift_count = 0;
+        }
+    }
+
+    fn write_mmc3(&mut self, addr: u16, val: u8) {
+        match (addr & 0xe000, addr & 1) {
+            (0x8000, 0) => self.regs[0] = val,
+            (0x8000, _) => self.regs[1 + (self.regs[0] & 7) as usize] = val,
+            (0xa000, 0) => {
+                if !self.four_screen {
+                    self.mirroring = if val & 1 == 0 {
+                        Mirroring::Vertical
+                    } else {
+                        Mirroring::Horizontal
+                    };
+                }
+            }
+            (0xa000, _) => {
+                self.ram_enabled = val & 0x80 != 0;
+                self.ram_writable = val & 0x40 == 0;
+            }
+            (0xc000, 0) => self.irq_latch = val,
+            (0xc000, _) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            (_, 0) => {
+                self.irq_enabled = false;
+                self.irq = false;
+            }
+            _ => self.irq_enabled = true,
+        }
+    }
+
+    // Recomputes the windows from the registers.
+    fn update(&mut self) {
+        let prg_banks = self.prg.len() / 0x2000;
+        let last = prg_banks - 1;
+        let chr_banks = self.chr_len / 0x400;
+        let mut prg = [0, 1, last.saturating_sub(1), last];
+        let mut chr = [0, 1, 2, 3, 4, 5, 6, 7];
+        match self.board {
+            Board::Nrom => (),
+            Board::Mmc1 => {
+                let [control, chr0, chr1, bank, ..] = self.regs;
+                self.mirroring = match control & 3 {
+                    0 => Mirroring::SingleLow,
+                    1 => Mirroring::SingleHigh,
+                    2 => Mirroring::Vertical,
+                    _ => Mirroring::Horizontal,
+                };
+                // SUROM's 512K is two 256K halves, picked by CHR bit 4.
+                let outer = if prg_banks > 32 {
+                    usize::from(chr0 & 0x10) << 1
+                } else {
+                    0
+                };
+                let bank = outer + usize::from(bank & 0x0f) * 2;
+                let (low, high) = match control & 0x0c {
+                    0x00 | 0x04 => (bank & !2, (bank & !2) + 2),
+                    0x08 => (outer, bank),
+                    _ => (bank, outer + last.saturating_sub(1).min(30)),
+                };
+                prg = [low, low + 1, high, high + 1];
+                self.ram_enabled = self.regs[3] & 0x10 == 0;
+                let (chr0, chr1) = (usize::from(chr0), usize::from(chr1));
+                for (n, window) in chr.iter_mut().enumerate() {
+                    *window = if control & 0x10 == 0 {
+                        (chr0 & !1) * 4 + n
+                    } else if n < 4 {
+                        chr0 * 4 + n
+                    } else {
+                        chr1 * 4 + n - 4
+                    };
+                }
+            }
+            Board::Uxrom => {
+                let bank = usize::from(self.regs[0]) * 2;
+                prg = [bank, bank + 1, last.saturating_sub(1), last];
+            }
+            Board::Cnrom => {
+                let bank = usize::from(self.regs[0]) * 8;
+                for (n, window) in chr.iter_mut().enumerate() {
+                    *window = bank + n;
+                }
+            }
+            Board::Mmc3 => {
+                let select = self.regs[0];
+                let r = |n: usize| usize::from(self.regs[1 + n]);
+                let (r6, r7) = (r(6) & 0x3f, r(7) & 0x3f);
+                prg = if select & 0x40 == 0 {
+                    [r6, r7, last.saturating_sub(1), last]
+                } else {
+                    [last.saturating_sub(1), r7, r6, last]
+                };
+                let two = [r(0) & !1, r(0) | 1, r(1) & !1, r(1) | 1];
+                let one = [r(2), r(3), r(4), r(5)];
+                let (low, high) = if select & 0x80 == 0 {
+                    (two, one)
+                } else {
+                    (one, two)
+                };
+                chr[..4].copy_from_slice(&low);
+                chr[4..].copy_from_slice(&high);
+            }
+        }
+        for (window, bank) in self.prg_windows.iter_mut().zip(&prg) {
+            *window = bank % prg_banks * 0x2000;
+        }
+        for (window, bank) in self.chr_windows.iter_mut().zip(&chr) {
+            *window = bank % chr_banks * 0x400;
+        }
+    }
+}
+
+// The CPU side of an NES: 2K of RAM, the cartridge, and a count of the
+// PPU's dots to give the vblank flag in $2002, the vblank NMI, and the
+// MMC3's scanline clock while $2001 enables rendering. The rest of the
+// PPU and the APU are open bus; wrap it in an Rp2a03 for the APU.
+pub struct NesSys {
+    pub ram: Vec<u8>,
+    pub cart: NesCart,
+    ctrl: u8,
+    mask: u8,
+    vblank: bool,
+    nmi: bool,
+    line: u16,
+    dot: u16,
+    bus: u8,
+    cycles: u64,
+}
+
+impl NesSys {
+    pub fn new(cart: NesCart) -> NesSys {
+        NesSys {
+            ram: vec![0; 0x800],
+            cart,
+            ctrl: 0,
+            mask: 0,
+            vblank: false,
+            nmi: false,
+            line: 0,
+            dot: 0,
+            bus: 0,
+            cycles: 0,
+        }
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    // The scanline the PPU is on, with 241-260 in vblank and 261 the
+    // pre-render line. Odd frames aren't shortened.
+    pub fn scanline(&self) -> u16 {
+        self.line
+    }
+
+    pub fn tick(&mut self) {
+        for _ in 0..3 {
+            self.dot += 1;
+            if self.dot == DOTS {
+                self.dot = 0;
+                self.line = (self.line + 1) % LINES;
+            }
+            match (self.line, self.dot) {
+                (241, 1) => {
+                    self.vblank = true;
+                    self.nmi |= self.ctrl & 0x80 != 0;
+                }
+                (261, 1) => self.vblank = false,
+                (0..=239, 260) | (261, 260) if self.mask & 0x18 != 0 => {
+                    self.cart.clock_scanline();
+                }
+                _ => (),
+            }
+        }
+        self.cycles += 1;
+    }
+}
+
+impl Sys for NesSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let val = match addr {
+            0x0000..=0x1fff => self.ram[(addr & 0x7ff) as usize],
+            0x2000..=0x3fff if addr & 7 == 2 => {
+                let status = (self.vblank as u8) << 7 | (self.bus & 0x1f);
+                self.vblank = false;
+                status
+            }
+            _ => self.cart.read(addr).unwrap_or(self.bus),
+        };
+        self.bus = val;
+        self.tick();
+        Some(val)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        match addr {
+            0x0000..=0x1fff => self.ram[(addr & 0x7ff) as usize] = val,
+            0x2000..=0x3fff => match addr & 7 {
+                0 => {
+                    // Enabling NMI during vblank gives one at once.
+                    let enable = val & 0x80 != 0 && self.ctrl & 0x80 == 0;
+                    self.nmi |= enable && self.vblank;
+                    self.ctrl = val;
+                }
+                1 => self.mask = val,
+                _ => (),
+            },
+            _ => self.cart.write(addr, val, self.cycles),
+        }
+        self.bus = val;
+        self.tick();
+        Some(())
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        let nmi = self.nmi;
+        self.nmi = false;
+        nmi
+    }
+
+    fn peek_nmi(&self) -> bool {
+        self.nmi
+    }
+
+    fn irq(&self) -> bool {
+        self.cart.irq()
+    }
+}
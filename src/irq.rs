@@ -0,0 +1,211 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{CycleKind, NmiLength, Sys};
+
+#[derive(Clone, Default, Debug)]
+pub struct IrqStats {
+    pub asserts: u64,
+    // The cycle the source was last asserted on.
+    pub asserted_at: Option<u64>,
+    // How many assertions the CPU responded to, and the cycles from
+    // assertion to the first IRQ vector read.
+    pub taken: u64,
+    pub last_latency: Option<u64>,
+    pub max_latency: u64,
+    pub total_latency: u64,
+}
+
+struct Source {
+    name: String,
+    asserted: bool,
+    seen: bool,
+    vector: Option<u16>,
+    stats: IrqStats,
+}
+
+// Drives the IRQ line from any number of sources, as if they were
+// wired together on an open-collector line. Sources given a vector
+// take part in a priority encoder: IRQ vector reads return the vector
+// of the first-added asserted source that has one. Like the real
+// thing, this cannot tell BRK from IRQ.
+pub struct IrqController<S: Sys> {
+    pub sys: S,
+    cycle: u64,
+    sources: Vec<Source>,
+    vector: Option<u16>,
+}
+
+impl<S: Sys> IrqController<S> {
+    pub fn new(sys: S) -> IrqController<S> {
+        IrqController {
+            sys,
+            cycle: 0,
+            sources: Vec::new(),
+            vector: None,
+        }
+    }
+
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    // Returns the id used to refer to the source.
+    pub fn add_source(&mut self, name: &str) -> usize {
+        self.sources.push(Source {
+            name: name.to_owned(),
+            asserted: false,
+            seen: false,
+            vector: None,
+            stats: IrqStats::default(),
+        });
+        self.sources.len() - 1
+    }
+
+    pub fn source(&self, name: &str) -> Option<usize> {
+        self.sources.iter().position(|s| s.name == name)
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.sources[id].name
+    }
+
+    pub fn set_vector(&mut self, id: usize, vector: Option<u16>) {
+        self.sources[id].vector = vector;
+    }
+
+    pub fn set(&mut self, id: usize, set: bool) {
+        let source = &mut self.sources[id];
+        if set && !source.asserted {
+            source.seen = false;
+            source.stats.asserts += 1;
+            source.stats.asserted_at = Some(self.cycle);
+        }
+        source.asserted = set;
+    }
+
+    pub fn asserted(&self, id: usize) -> bool {
+        self.sources[id].asserted
+    }
+
+    pub fn stats(&self, id: usize) -> &IrqStats {
+        &self.sources[id].stats
+    }
+
+    // A source that is still asserted keeps the cycle it was asserted
+    // on, so the latency of its pending interrupt is still counted.
+    pub fn reset_stats(&mut self) {
+        for source in &mut self.sources {
+            let asserted_at = if source.asserted {
+                source.stats.asserted_at
+            } else {
+                None
+            };
+            source.stats = IrqStats {
+                asserted_at,
+                ..Default::default()
+            };
+        }
+    }
+
+    fn vector_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0xfffe => {
+                let cycle = self.cycle;
+                for source in self.sources.iter_mut() {
+                    if source.asserted && !source.seen {
+                        source.seen = true;
+                        let stats = &mut source.stats;
+                        let latency = cycle - stats.asserted_at.unwrap();
+                        stats.taken += 1;
+                        stats.last_latency = Some(latency);
+                        stats.max_latency = stats.max_latency.max(latency);
+                        stats.total_latency += latency;
+                    }
+                }
+                self.vector = self
+                    .sources
+                    .iter()
+                    .find(|s| s.asserted && s.vector.is_some())
+                    .and_then(|s| s.vector);
+                self.vector.map(|v| v as u8)
+            }
+            0xffff => self.vector.take().map(|v| (v >> 8) as u8),
+            _ => None,
+        }
+    }
+}
+
+impl<S: Sys> Sys for IrqController<S> {
+    #[inline]
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.read_kind(addr, CycleKind::Data)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.write_kind(addr, val, CycleKind::Data)
+    }
+
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        let mut val = self.sys.read_kind(addr, kind)?;
+        if kind == CycleKind::Vector {
+            if let Some(vec) = self.vector_read(addr) {
+                val = vec;
+            }
+        }
+        self.cycle += 1;
+        Some(val)
+    }
+
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
+        self.sys.write_kind(addr, val, kind)?;
+        self.cycle += 1;
+        Some(())
+    }
+
+    #[inline]
+    fn set_sync(&mut self, set: bool) {
+        self.sys.set_sync(set);
+    }
+
+    #[inline]
+    fn set_vp(&mut self, set: bool) {
+        self.sys.set_vp(set);
+    }
+
+    #[inline]
+    fn set_ml(&mut self, set: bool) {
+        self.sys.set_ml(set);
+    }
+
+    #[inline]
+    fn poll_nmi(&mut self) -> bool {
+        self.sys.poll_nmi()
+    }
+
+    #[inline]
+    fn peek_nmi(&self) -> bool {
+        self.sys.peek_nmi()
+    }
+
+    #[inline]
+    fn nmi_length(&self) -> NmiLength {
+        self.sys.nmi_length()
+    }
+
+    #[inline]
+    fn irq(&self) -> bool {
+        self.sources.iter().any(|s| s.asserted) || self.sys.irq()
+    }
+}
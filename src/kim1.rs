@@ -0,0 +1,381 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::VecDeque;
+
+use crate::{Cpu, CycleKind, Device, LoadError, Nmos, Riot, Sys};
+
+const CPU_HZ: u64 = 1_000_000;
+
+// A digit only counts as lit once its segments have been held for
+// this many cycles.
+const LED_PERSIST: u64 = 16;
+
+const HEX_SEGMENTS: [u8; 16] = [
+    0x3f, 0x06, 0x5b, 0x4f, 0x66, 0x6d, 0x7d, 0x07, 0x7f, 0x6f, 0x77, 0x7c,
+    0x39, 0x5e, 0x79, 0x71,
+];
+
+// A KIM-1, with 1K of RAM and the two 6530s stood in for by RIOTs.
+// The 2K monitor ROM is supplied by the caller, and appears at $1800
+// (and, since A13-A15 are not decoded, at $F800 for the vectors).
+//
+// In TTY mode the jumper that selects the teletype is fitted, and
+// bytes are sent to and decoded from the bit-banged serial lines on
+// PA7 and PB0. In keypad mode the keys are read from the matrix on
+// PA0-PA6, and the six LED digits are latched as they are scanned.
+pub struct Kim1 {
+    pub cpu: Nmos,
+    pub sys: Kim1Sys,
+}
+
+impl Kim1 {
+    pub fn keypad(rom: &[u8]) -> Kim1 {
+        Kim1::new(rom, None)
+    }
+
+    pub fn tty(rom: &[u8], baud: u64) -> Kim1 {
+        Kim1::new(rom, Some(CPU_HZ / baud))
+    }
+
+    fn new(rom: &[u8], bit_cycles: Option<u64>) -> Kim1 {
+        assert_eq!(rom.len(), 0x800, "ROM must be 2K");
+        let mut cpu = Nmos::default();
+        cpu.reset();
+        let mut sys = Kim1Sys {
+            ram: vec![0; 0x400],
+            rom: rom.to_vec(),
+            riot002: Riot::new(),
+            riot003: Riot::new(),
+            bit_cycles,
+            input: VecDeque::new(),
+            rx_frame: None,
+            output: Vec::new(),
+            tx_frame: None,
+            key: None,
+            leds: [0; 6],
+            lit: (0, 0, 0),
+            longest: (0, 0),
+            sst: false,
+            nmi: false,
+            cycles: 0,
+        };
+        sys.update_inputs();
+        Kim1 { cpu, sys }
+    }
+
+    // The RS key.
+    pub fn reset(&mut self) {
+        self.sys.riot002.reset();
+        self.sys.riot003.reset();
+        self.sys.update_inputs();
+        self.cpu.reset();
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.sys.cycles
+    }
+
+    pub fn run_instruction(&mut self) {
+        self.cpu.run_instruction(&mut self.sys);
+    }
+
+    // Runs whole instructions until at least the given number of cycles
+    // have passed.
+    pub fn run_cycles(&mut self, cycles: u64) {
+        let end = self.sys.cycles + cycles;
+        while self.sys.cycles < end && !self.cpu.halted() {
+            self.run_instruction();
+        }
+    }
+
+    pub fn load(&mut self, addr: u16, data: &[u8]) -> Result<(), LoadError> {
+        let addr = addr as usize;
+        self.sys
+            .ram
+            .get_mut(addr..(addr + data.len()))
+            .ok_or(LoadError::Invalid("data past end of RAM"))?
+            .copy_from_slice(data);
+        Ok(())
+    }
+
+    // Queues bytes for the TTY, each one sent when the CPU next reads
+    // port A after the line has gone idle.
+    pub fn send(&mut self, data: &[u8]) {
+        self.sys.input.extend(data);
+    }
+
+    pub fn pending_input(&self) -> usize {
+        self.sys.input.len()
+    }
+
+    // Takes the bytes received from the TTY so far.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.sys.output)
+    }
+
+    // Holds down a key, by its monitor key code (0-F, then $10-$14 for
+    // AD, DA, +, GO and PC), until release_key().
+    pub fn press_key(&mut self, code: u8) {
+        assert!(code < 0x15, "no such key");
+        self.sys.key = Some(code);
+        self.sys.update_inputs();
+    }
+
+    pub fn release_key(&mut self) {
+        self.sys.key = None;
+        self.sys.update_inputs();
+    }
+
+    // The ST key.
+    pub fn press_st(&mut self) {
+        self.sys.nmi = true;
+    }
+
+    // The SST switch, which stops with an NMI after each instruction
+    // outside the monitor ROM.
+    pub fn set_sst(&mut self, on: bool) {
+        self.sys.sst = on;
+    }
+
+    // The segments last lit on each digit, with segment A in bit 0.
+    pub fn leds(&self) -> [u8; 6] {
+        let mut leds = self.sys.leds;
+        self.sys.latch_leds(&mut leds);
+        leds
+    }
+
+    // The digits as hex, with blanks as spaces and anything else as
+    // '?'.
+    pub fn led_text(&self) -> String {
+        self.leds()
+            .iter()
+            .map(|&segs| match segs {
+                0 => ' ',
+                _ => HEX_SEGMENTS
+                    .iter()
+                    .position(|&hex| hex == segs)
+                    .and_then(|d| std::char::from_digit(d as u32, 16))
+                    .map_or('?', |ch| ch.to_ascii_uppercase()),
+            })
+            .collect()
+    }
+}
+
+pub struct Kim1Sys {
+    pub ram: Vec<u8>,
+    pub rom: Vec<u8>,
+    // The 6530-002 at $1740, with its RAM at $17C0 holding the monitor's
+    // variables and vectors, and the 6530-003 at $1700, with its RAM at
+    // $1780.
+    pub riot002: Riot,
+    pub riot003: Riot,
+    bit_cycles: Option<u64>,
+    input: VecDeque<u8>,
+    rx_frame: Option<(u8, u64)>,
+    output: Vec<u8>,
+    tx_frame: Option<(u8, u8, u64)>,
+    key: Option<u8>,
+    leds: [u8; 6],
+    lit: (u8, u8, u64),
+    longest: (u8, u64),
+    sst: bool,
+    nmi: bool,
+    cycles: u64,
+}
+
+impl Kim1Sys {
+    // The output of the 74145 decoder driven by PB1-PB4.
+    fn select(&self) -> u8 {
+        (self.riot002.pb() >> 1) & 0x0f
+    }
+
+    // The level of the TTY line into PA7, as a frame of one start bit,
+    // eight data bits and two stop bits.
+    fn rx_level(&self) -> bool {
+        match (self.rx_frame, self.bit_cycles) {
+            (Some((byte, start)), Some(bit)) => {
+                match (self.cycles - start) / bit {
+                    0 => false,
+                    n @ 1..=8 => byte & (1 << (n - 1)) != 0,
+                    _ => true,
+                }
+            }
+            _ => true,
+        }
+    }
+
+    fn update_inputs(&mut self) {
+        let select = self.select();
+        let mut pa = 0x7f | ((self.rx_level() as u8) << 7);
+        // Each row of the keypad holds seven keys, in key code order
+        // from PA6 down to PA0.
+        if let Some(code) = self.key {
+            if code / 7 == select {
+                pa &= !(0x40 >> (code % 7));
+            }
+        }
+        if select == 3 && self.bit_cycles.is_some() {
+            pa &= !0x01;
+        }
+        self.riot002.set_pa(pa);
+    }
+
+    // Tracks the segments on PA0-PA6 and the digit selected to light
+    // them. Each time a digit is deselected, it keeps the segments it
+    // showed for longest.
+    fn update_leds(&mut self) {
+        let (select, segs) = (self.select(), self.riot002.pa() & 0x7f);
+        if (select, segs) == (self.lit.0, self.lit.1) {
+            return;
+        }
+        self.longest = self.longest_lit();
+        if select != self.lit.0 {
+            let mut leds = self.leds;
+            self.latch_leds(&mut leds);
+            self.leds = leds;
+            self.longest = (0, 0);
+        }
+        self.lit = (select, segs, self.cycles);
+    }
+
+    fn longest_lit(&self) -> (u8, u64) {
+        let (_, segs, since) = self.lit;
+        let held = self.cycles - since;
+        if held > self.longest.1 {
+            (segs, held)
+        } else {
+            self.longest
+        }
+    }
+
+    fn latch_leds(&self, leds: &mut [u8; 6]) {
+        let select = self.lit.0;
+        let (segs, held) = self.longest_lit();
+        if (4..=9).contains(&select) && held >= LED_PERSIST {
+            leds[select as usize - 4] = segs;
+        }
+    }
+
+    fn start_rx(&mut self) {
+        if self.rx_frame.is_some() || self.bit_cycles.is_none() {
+            return;
+        }
+        if let Some(byte) = self.input.pop_front() {
+            self.rx_frame = Some((byte, self.cycles));
+            self.update_inputs();
+        }
+    }
+
+    // Samples PB0 at the middle of each bit, once a start bit is seen.
+    fn sample_tx(&mut self, bit: u64) {
+        let level = self.riot002.pb() & 0x01 != 0;
+        match self.tx_frame {
+            None if !level => self.tx_frame = Some((0, 0, self.cycles)),
+            Some((byte, n, start)) => {
+                if self.cycles - start < bit * (2 * n as u64 + 3) / 2 {
+                    return;
+                }
+                if n < 8 {
+                    let byte = byte | ((level as u8) << n);
+                    self.tx_frame = Some((byte, n + 1, start));
+                } else {
+                    // A missing stop bit is a framing error.
+                    if level {
+                        self.output.push(byte);
+                    }
+                    self.tx_frame = None;
+                }
+            }
+            None => (),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.riot002.tick();
+        self.riot003.tick();
+        self.cycles += 1;
+        if let Some(bit) = self.bit_cycles {
+            if let Some((_, start)) = self.rx_frame {
+                if self.cycles - start >= 11 * bit {
+                    self.rx_frame = None;
+                }
+            }
+            self.sample_tx(bit);
+        }
+        self.update_inputs();
+    }
+}
+
+// The 6530 has no edge detect register, so any write with A2 set goes
+// to the timer.
+fn riot_reg(addr: u16, write: bool) -> u8 {
+    let reg = addr as u8 & 0x0f;
+    if write && reg & 0x04 != 0 {
+        reg | 0x10
+    } else {
+        reg
+    }
+}
+
+impl Sys for Kim1Sys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let addr = addr & 0x1fff;
+        let val = match addr {
+            0x0000..=0x03ff => self.ram[addr as usize],
+            0x1700..=0x173f => self.riot003.read(riot_reg(addr, false)),
+            0x1740..=0x177f => {
+                if addr & 0x0f == 0 {
+                    self.start_rx();
+                }
+                self.riot002.read(riot_reg(addr, false))
+            }
+            0x1780..=0x17bf => self.riot003.ram[(addr & 0x3f) as usize],
+            0x17c0..=0x17ff => self.riot002.ram[(addr & 0x3f) as usize],
+            0x1800..=0x1fff => self.rom[(addr & 0x7ff) as usize],
+            _ => 0,
+        };
+        self.tick();
+        Some(val)
+    }
+
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        if kind == CycleKind::Opcode && self.sst && addr & 0x1fff < 0x1c00 {
+            self.nmi = true;
+        }
+        self.read(addr)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        let addr = addr & 0x1fff;
+        match addr {
+            0x0000..=0x03ff => self.ram[addr as usize] = val,
+            0x1700..=0x173f => self.riot003.write(riot_reg(addr, true), val),
+            0x1740..=0x177f => {
+                self.riot002.write(riot_reg(addr, true), val);
+                self.update_inputs();
+                self.update_leds();
+            }
+            0x1780..=0x17bf => self.riot003.ram[(addr & 0x3f) as usize] = val,
+            0x17c0..=0x17ff => self.riot002.ram[(addr & 0x3f) as usize] = val,
+            _ => (),
+        }
+        self.tick();
+        Some(())
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        let nmi = self.nmi;
+        self.nmi = false;
+        nmi
+    }
+
+    fn peek_nmi(&self) -> bool {
+        self.nmi
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// An HD44780 character LCD controller, driven through its E, RW and
+// RS lines and data bus rather than mapped into memory. Only the text
+// is emulated; CGRAM is stored, but nothing is drawn.
+#[derive(Clone, Debug)]
+pub struct Hd44780 {
+    cols: usize,
+    rows: usize,
+    cycles_per_us: u64,
+    ddram: [u8; 0x80],
+    cgram: [u8; 0x40],
+    addr: u8,
+    cgram_mode: bool,
+    increment: bool,
+    shift_display: bool,
+    display_on: bool,
+    cursor: bool,
+    blink: bool,
+    eight_bit: bool,
+    two_lines: bool,
+    shift: u8,
+    busy: u64,
+    e: bool,
+    // In 4-bit mode, the high nibble of a write waiting for its low
+    // nibble, and the byte whose low nibble is read next.
+    nibble: Option<u8>,
+    read_latch: Option<u8>,
+    data: u8,
+}
+
+impl Hd44780 {
+    pub fn new(cols: usize, rows: usize, cpu_hz: u64) -> Hd44780 {
+        Hd44780 {
+            cols,
+            rows,
+            cycles_per_us: (cpu_hz / 1_000_000).max(1),
+            ddram: [0x20; 0x80],
+            cgram: [0; 0x40],
+            addr: 0,
+            cgram_mode: false,
+            increment: true,
+            shift_display: false,
+            display_on: false,
+            cursor: false,
+            blink: false,
+            eight_bit: true,
+            two_lines: false,
+            shift: 0,
+            busy: 0,
+            e: false,
+            nibble: None,
+            read_latch: None,
+            data: 0,
+        }
+    }
+
+    pub fn display_on(&self) -> bool {
+        self.display_on
+    }
+
+    pub fn cursor(&self) -> Option<(usize, usize)> {
+        if !self.cursor && !self.blink {
+            return None;
+        }
+        let addr = self.addr as usize;
+        (0..self.rows).find_map(|row| {
+            let start = self.row_start(row);
+            if addr < start || addr >= start + 40 {
+                return None;
+            }
+            let col = (addr - start + 40 - self.shift as usize) % 40;
+            if col < self.cols {
+                Some((row, col))
+            } else {
+                None
+            }
+        })
+    }
+
+    // The characters currently visible on each row.
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.rows)
+            .map(|row| {
+                let start = self.row_start(row);
+                (0..self.cols)
+                    .map(|col| {
+                        let col = (col + self.shift as usize) % 40;
+                        let ch = self.ddram[start + col];
+                        if (0x20..0x7f).contains(&ch) {
+                            ch as char
+                        } else {
+                            ' '
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn text(&self) -> String {
+        self.lines().join("\n")
+    }
+
+    pub fn busy(&self) -> bool {
+        self.busy > 0
+    }
+
+    // Call once per CPU cycle.
+    pub fn tick(&mut self) {
+        self.busy = self.busy.saturating_sub(1);
+    }
+
+    // Updates the control lines and data bus. Returns the value the
+    // controller drives on the data bus, during reads.
+    pub fn bus(&mut self, e: bool, rw: bool, rs: bool, data: u8) -> Option<u8> {
+        let fall = self.e && !e;
+        let rise = !self.e && e;
+        self.e = e;
+        if rw {
+            if rise {
+                self.data = self.read(rs);
+            }
+            return if e { Some(self.data) } else { None };
+        }
+        if fall {
+            self.write(rs, data);
+        }
+        None
+    }
+
+    fn row_start(&self, row: usize) -> usize {
+        [0x00, 0x40, self.cols, 0x40 + self.cols][row & 3]
+    }
+
+    fn read(&mut self, rs: bool) -> u8 {
+        if let Some(val) = self.read_latch.take() {
+            return val << 4;
+        }
+        let val = if rs {
+            self.read_data()
+        } else {
+            ((self.busy() as u8) << 7) | (self.addr & 0x7f)
+        };
+        if self.eight_bit {
+            return val;
+        }
+        self.read_latch = Some(val);
+        val & 0xf0
+    }
+
+    fn read_data(&mut self) -> u8 {
+        let val = if self.cgram_mode {
+            self.cgram[(self.addr & 0x3f) as usize]
+        } else {
+            self.ddram[self.addr as usize]
+        };
+        self.step();
+        val
+    }
+
+    fn write(&mut self, rs: bool, data: u8) {
+        let val = if self.eight_bit {
+            data
+        } else {
+            match self.nibble.take() {
+                Some(hi) => hi | (data >> 4),
+                None => {
+                    self.nibble = Some(data & 0xf0);
+                    return;
+                }
+            }
+        };
+        self.read_latch = None;
+        if rs {
+            if self.cgram_mode {
+                self.cgram[(self.addr & 0x3f) as usize] = val;
+            } else {
+                self.ddram[self.addr as usize] = val;
+                if self.shift_display {
+                    self.shift_by(self.increment);
+                }
+            }
+            self.step();
+            self.set_busy(43);
+        } else {
+            self.command(val);
+        }
+    }
+
+    fn command(&mut self, cmd: u8) {
+        self.set_busy(37);
+        match cmd.leading_zeros() {
+            7 => {
+                self.ddram = [0x20; 0x80];
+                self.addr = 0;
+                self.cgram_mode = false;
+                self.increment = true;
+                self.shift = 0;
+                self.set_busy(1520);
+            }
+            6 => {
+                self.addr = 0;
+                self.cgram_mode = false;
+                self.shift = 0;
+                self.set_busy(1520);
+            }
+            5 => {
+                self.increment = cmd & 0x02 != 0;
+                self.shift_display = cmd & 0x01 != 0;
+            }
+            4 => {
+                self.display_on = cmd & 0x04 != 0;
+                self.cursor = cmd & 0x02 != 0;
+                self.blink = cmd & 0x01 != 0;
+            }
+            3 => {
+                let right = cmd & 0x04 != 0;
+                if cmd & 0x08 != 0 {
+                    self.shift_by(right);
+                } else {
+                    self.step_addr(right);
+                }
+            }
+            2 => {
+                // A function set in 8-bit mode is a single write, so
+                // the first nibble of 4-bit mode can switch modes.
+                self.eight_bit = cmd & 0x10 != 0;
+                self.two_lines = cmd & 0x08 != 0;
+                self.nibble = None;
+            }
+            1 => {
+                self.cgram_mode = true;
+                self.addr = cmd & 0x3f;
+            }
+            0 => {
+                self.cgram_mode = false;
+                self.addr = cmd & 0x7f;
+                if !self.valid_addr(self.addr) {
+                    self.addr = 0;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn set_busy(&mut self, us: u64) {
+        self.busy = us * self.cycles_per_us;
+    }
+
+    fn valid_addr(&self, addr: u8) -> bool {
+        if self.two_lines {
+            addr < 0x28 || (0x40..0x68).contains(&addr)
+        } else {
+            addr < 0x50
+        }
+    }
+
+    fn step(&mut self) {
+        let inc = self.increment;
+        self.step_addr(inc);
+    }
+
+    fn step_addr(&mut self, inc: bool) {
+        if self.cgram_mode {
+            let addr = if inc { self.addr + 1 } else { self.addr + 0x3f };
+            self.addr = addr & 0x3f;
+            return;
+        }
+        let (len, wrap): (u8, &[u8]) = if self.two_lines {
+            (0x68, &[0x27, 0x40, 0x67, 0x00])
+        } else {
+            (0x50, &[0x4f, 0x00, 0x4f, 0x00])
+        };
+        self.addr = if inc {
+            match self.addr {
+                a if a == wrap[0] => wrap[1],
+                a if a == wrap[2] => wrap[3],
+                a => (a + 1) % len,
+            }
+        } else {
+            match self.addr {
+                a if a == wrap[1] => wrap[0],
+                a if a == wrap[3] => wrap[2],
+                a => a - 1,
+            }
+        };
+    }
+
+    fn shift_by(&mut self, right: bool) {
+        self.shift = if right {
+            (self.shift + 39) % 40
+        } else {
+            (self.shift + 1) % 40
+        };
+    }
+}
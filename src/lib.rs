@@ -6,21 +6,73 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-#![cfg_attr(feature = "cargo-clippy", feature(tool_lints))]
-
 use std::fmt;
 
 use machine_int::MachineInt;
 
 use self::mi::Byte;
 
-pub use crate::mi::Addr;
+pub use crate::acia::Acia;
+pub use crate::apple1::{Apple1, Apple1Sys};
+pub use crate::apu::{write_wav, Apu, Rp2a03};
+pub use crate::atari2600::{
+    Atari2600, Atari2600Cart, Atari2600Sys, Bankswitch,
+};
+pub use crate::bus::{MappedSys, OpenBus};
+pub use crate::cia::Cia;
 pub use crate::cmos::Cmos;
+pub use crate::device::Device;
+pub use crate::eater::{BenEater, BenEaterSys};
+pub use crate::error::LoadError;
+pub use crate::events::Events;
+pub use crate::ines::{Ines, Mirroring, NesCart, NesSys};
+pub use crate::irq::{IrqController, IrqStats};
+pub use crate::kim1::{Kim1, Kim1Sys};
+pub use crate::lcd::Hd44780;
+pub use crate::loader::{Program, Segment};
+pub use crate::mi::Addr;
 pub use crate::nmos::Nmos;
-
+pub use crate::nsf::{write_reg_log, Nsf, NsfPlayer, NsfSys, RegWrite};
+pub use crate::pia::Pia;
+pub use crate::psid::{Psid, PsidPlayer, PsidSys};
+pub use crate::riot::Riot;
+pub use crate::scheduler::{Clocked, Scheduler, Task};
+pub use crate::symbols::{SourceLine, Symbol, Symbols};
+pub use crate::tia::{Frame, Tia};
+pub use crate::tick::{Access, BusRequest, Phase, Ticker};
+pub use crate::vgm::{write_vgm, ChipWrite, SoundChip, SoundTap};
+pub use crate::via::Via;
+
+mod acia;
+mod apple1;
+mod apu;
+mod atari2600;
+mod bus;
+mod cia;
 mod cmos;
+mod device;
+mod dma;
+mod eater;
+mod error;
+mod events;
+mod ines;
+mod irq;
+mod kim1;
+mod lcd;
+mod loader;
+mod m6507;
 mod mi;
 mod nmos;
+mod nsf;
+mod pia;
+mod psid;
+mod riot;
+mod scheduler;
+mod symbols;
+mod tia;
+mod tick;
+mod vgm;
+mod via;
 
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
 pub enum NmiLength {
@@ -29,14 +81,53 @@ pub enum NmiLength {
     Plenty,
 }
 
+// The kind of bus cycle a read or write belongs to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CycleKind {
+    Opcode,
+    Operand,
+    Data,
+    // Reads and writes whose values are ignored by the CPU, like the
+    // extra read on page crossings, the double write of RMW ops, and
+    // the opcode fetch an interrupt takes the place of.
+    Dummy,
+    Stack,
+    Vector,
+}
+
 pub trait Sys {
     fn read(&mut self, addr: u16) -> Option<u8>;
 
     fn write(&mut self, addr: u16, val: u8) -> Option<()>;
 
+    // The CPU performs all bus accesses through these, so a Sys that
+    // needs to know the kind of cycle can override them.
+    #[inline]
+    fn read_kind(&mut self, addr: u16, _kind: CycleKind) -> Option<u8> {
+        self.read(addr)
+    }
+
+    #[inline]
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        _kind: CycleKind,
+    ) -> Option<()> {
+        self.write(addr, val)
+    }
+
     #[inline]
     fn set_sync(&mut self, _set: bool) {}
 
+    // The VP and ML outputs are only driven by Cmos, during the two
+    // vector pull cycles and the cycles of an RMW op, respectively.
+    #[inline]
+    fn set_vp(&mut self, _set: bool) {}
+
+    #[inline]
+    fn set_ml(&mut self, _set: bool) {}
+
     #[inline]
     fn poll_nmi(&mut self) -> bool {
         false
@@ -179,10 +270,7 @@ impl Flags {
             | self.c
     }
 
-    #[cfg_attr(
-        feature = "cargo-clippy",
-        allow(clippy::wrong_self_convention)
-    )]
+    #[allow(clippy::wrong_self_convention)]
     fn from_byte(&mut self, val: Byte) {
         self.n = val;
         self.v = val & 0x40;
@@ -194,10 +282,7 @@ impl Flags {
 }
 
 impl fmt::Debug for Flags {
-    #[cfg_attr(
-        feature = "cargo-clippy",
-        allow(clippy::many_single_char_names)
-    )]
+    #[allow(clippy::many_single_char_names)]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let n = if self.n() { "N" } else { "n" };
         let v = match self.v.0 {
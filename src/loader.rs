@@ -0,0 +1,410 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::ops::RangeInclusive;
+
+use crate::{LoadError, Sys};
+
+// A block of data and the address it loads at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub addr: u16,
+    pub data: Vec<u8>,
+}
+
+impl Segment {
+    // None if there's no data.
+    pub fn range(&self) -> Option<RangeInclusive<u16>> {
+        let last = self.data.len().checked_sub(1)?;
+        Some(self.addr..=self.addr.wrapping_add(last as u16))
+    }
+}
+
+// A program read from one of the common 6502 binary formats.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Program {
+    pub segments: Vec<Segment>,
+    // Where to start, if the format says.
+    pub entry: Option<u16>,
+    // The XEX INITAD routines, each with the number of segments loaded
+    // before it is called.
+    pub inits: Vec<(usize, u16)>,
+}
+
+impl Program {
+    // Writes each segment through the Sys, in order. Returns None if a
+    // write pauses; loading again starts over from the first byte.
+    pub fn load<S: Sys>(&self, sys: &mut S) -> Option<()> {
+        for seg in &self.segments {
+            for (i, &val) in seg.data.iter().enumerate() {
+                sys.write(seg.addr.wrapping_add(i as u16), val)?;
+            }
+        }
+        Some(())
+    }
+
+    // The ranges of the segments that have data.
+    pub fn ranges(&self) -> Vec<RangeInclusive<u16>> {
+        self.segments.iter().filter_map(Segment::range).collect()
+    }
+
+    fn push(&mut self, addr: u32, data: &[u8]) -> Result<(), LoadError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        if u64::from(addr) + data.len() as u64 > 0x10000 {
+            return Err(LoadError::Invalid("data past $FFFF"));
+        }
+        let addr = addr as u16;
+        self.segments.push(Segment {
+            addr,
+            data: data.to_vec(),
+        });
+        Ok(())
+    }
+
+    // Like push, but joins data that follows on from the last segment,
+    // as the records of the text formats usually do.
+    fn append(&mut self, addr: u32, data: &[u8]) -> Result<(), LoadError> {
+        if let Some(last) = self.segments.last_mut() {
+            if u32::from(last.addr) + last.data.len() as u32 == addr {
+                if u64::from(addr) + data.len() as u64 > 0x10000 {
+                    return Err(LoadError::Invalid("data past $FFFF"));
+                }
+                last.data.extend_from_slice(data);
+                return Ok(());
+            }
+        }
+        self.push(addr, data)
+    }
+
+    // A Commodore PRG: the load address, then the data. A BASIC program
+    // at $0801 that starts with SYS gives the entry point.
+    pub fn from_prg(file: &[u8]) -> Result<Program, LoadError> {
+        if file.len() < 2 {
+            return Err(LoadError::Truncated);
+        }
+        let addr = le16(file);
+        let mut prog = Program::default();
+        prog.push(u32::from(addr), &file[2..])?;
+        if addr == 0x0801 {
+            prog.entry = basic_sys(&file[2..]);
+        }
+        Ok(prog)
+    }
+
+    // An Apple DOS 3.3 B file: the load address and length, then the
+    // data, which BRUN starts at the load address. Anything past the
+    // length is padding.
+    pub fn from_apple_dos(file: &[u8]) -> Result<Program, LoadError> {
+        if file.len() < 4 {
+            return Err(LoadError::Truncated);
+        }
+        let addr = le16(file);
+        let len = le16(&file[2..]) as usize;
+        let data = file.get(4..4 + len).ok_or(LoadError::Truncated)?;
+        let mut prog = Program::default();
+        prog.push(u32::from(addr), data)?;
+        prog.entry = Some(addr);
+        Ok(prog)
+    }
+
+    // An Atari XEX: $FFFF, then segments of a start and end address and
+    // the data, each optionally preceded by another $FFFF. Writing
+    // RUNAD ($02E0) gives the entry point, and writing INITAD ($02E2)
+    // asks for a routine to be called before loading goes on.
+    pub fn from_xex(file: &[u8]) -> Result<Program, LoadError> {
+        if !file.starts_with(&[0xff, 0xff]) {
+            return Err(LoadError::BadMagic);
+        }
+        let mut prog = Program::default();
+        let mut rest = &file[2..];
+        while !rest.is_empty() {
+            if rest.starts_with(&[0xff, 0xff]) {
+                rest = &rest[2..];
+            }
+            if rest.len() < 4 {
+                return Err(LoadError::Truncated);
+            }
+            let (start, end) = (le16(rest), le16(&rest[2..]));
+            if end < start {
+                return Err(LoadError::Invalid("segment ends before start"));
+            }
+            let len = (end - start) as usize + 1;
+            let data = rest.get(4..4 + len).ok_or(LoadError::Truncated)?;
+            rest = &rest[4 + len..];
+            prog.push(u32::from(start), data)?;
+
+            let vector = |addr: u16| -> Option<u16> {
+                let byte = |a: u16| {
+                    a.checked_sub(start).and_then(|i| data.get(i as usize))
+                };
+                match (byte(addr), byte(addr + 1)) {
+                    (Some(&lo), Some(&hi)) => {
+                        Some(u16::from_le_bytes([lo, hi]))
+                    }
+                    _ => None,
+                }
+            };
+            if let Some(run) = vector(0x02e0) {
+                prog.entry = Some(run);
+            }
+            if let Some(init) = vector(0x02e2) {
+                prog.inits.push((prog.segments.len(), init));
+            }
+        }
+        Ok(prog)
+    }
+
+    // Intel HEX, with data, end of file, and extended address records.
+    // Data must stay below $10000.
+    pub fn from_ihex(text: &str) -> Result<Program, LoadError> {
+        let mut prog = Program::default();
+        let mut base = 0;
+        let lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+        if !lines.clone().next().is_some_and(|l| l.starts_with(':')) {
+            return Err(LoadError::BadMagic);
+        }
+        for line in lines {
+            let rec = line
+                .strip_prefix(':')
+                .ok_or(LoadError::Invalid("missing record mark"))
+                .and_then(hex_bytes)?;
+            if rec.len() < 5 || rec.len() != usize::from(rec[0]) + 5 {
+                return Err(LoadError::Invalid("bad record length"));
+            }
+            if rec.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) != 0 {
+                return Err(LoadError::Invalid("bad checksum"));
+            }
+            let addr = u32::from(be16(&rec[1..]));
+            let data = &rec[4..rec.len() - 1];
+            match rec[3] {
+                0x00 => prog.append(base + addr, data)?,
+                0x01 => return Ok(prog),
+                0x02 if data.len() == 2 => {
+                    base = u32::from(be16(data)) << 4;
+                }
+                0x04 if data.len() == 2 => {
+                    base = u32::from(be16(data)) << 16;
+                }
+                0x03 | 0x05 if data.len() == 4 => {
+                    let (hi, lo) = (be16(data), be16(&data[2..]));
+                    // CS:IP, or a linear address.
+                    let entry = if rec[3] == 0x03 {
+                        u32::from(hi) << 4
+                    } else {
+                        u32::from(hi) << 16
+                    } + u32::from(lo);
+                    if entry > 0xffff {
+                        return Err(LoadError::Invalid("entry past $FFFF"));
+                    }
+                    prog.entry = Some(entry as u16);
+                }
+                _ => return Err(LoadError::Invalid("bad record type")),
+            }
+        }
+        Err(LoadError::Truncated)
+    }
+
+    // Motorola S-records, with 16, 24 or 32-bit addresses. Data must
+    // stay below $10000.
+    pub fn from_srec(text: &str) -> Result<Program, LoadError> {
+        let mut prog = Program::default();
+        let lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+        if !lines.clone().next().is_some_and(|l| l.starts_with('S')) {
+            return Err(LoadError::BadMagic);
+        }
+        for line in lines {
+            let kind = line.as_bytes().get(1).cloned().unwrap_or(0);
+            if !line.starts_with('S') || line.len() < 2 {
+                return Err(LoadError::Invalid("missing record mark"));
+            }
+            let hex =
+                line.get(2..).ok_or(LoadError::Invalid("bad hex digit"))?;
+            let rec = hex_bytes(hex)?;
+            if rec.len() < 2 || rec.len() != usize::from(rec[0]) + 1 {
+                return Err(LoadError::Invalid("bad record length"));
+            }
+            if rec.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) != 0xff {
+                return Err(LoadError::Invalid("bad checksum"));
+            }
+            let body = &rec[1..rec.len() - 1];
+            let addr_len = match kind {
+                b'0' | b'1' | b'5' | b'9' => 2,
+                b'2' | b'6' | b'8' => 3,
+                b'3' | b'7' => 4,
+                _ => return Err(LoadError::Invalid("bad record type")),
+            };
+            if body.len() < addr_len {
+                return Err(LoadError::Invalid("bad record length"));
+            }
+            let addr = body[..addr_len]
+                .iter()
+                .fold(0u32, |addr, &b| addr << 8 | u32::from(b));
+            match kind {
+                b'1'..=b'3' => prog.append(addr, &body[addr_len..])?,
+                b'7'..=b'9' => {
+                    if addr > 0xffff {
+                        return Err(LoadError::Invalid("entry past $FFFF"));
+                    }
+                    prog.entry = Some(addr as u16);
+                }
+                _ => (),
+            }
+        }
+        Ok(prog)
+    }
+
+    // An o65 file, relocated so its text segment starts at the given
+    // address, with the data segment after it and the BSS after that;
+    // zero page stays where it was assembled. The entry point is the
+    // start of the text. Undefined references can't be resolved, so
+    // only files without any are loaded.
+    pub fn from_o65(file: &[u8], text: u16) -> Result<Program, LoadError> {
+        if !file.starts_with(&[0x01, 0x00, b'o', b'6', b'5']) {
+            return Err(LoadError::BadMagic);
+        }
+        if file.len() < 26 {
+            return Err(LoadError::Truncated);
+        }
+        let mode = le16(&file[6..]);
+        if mode & 0x2000 != 0 {
+            return Err(LoadError::Invalid("32-bit o65"));
+        }
+        let bytewise = mode & 0x4000 == 0;
+        let field = |n: usize| le16(&file[8 + n * 2..]);
+        let (tbase, tlen) = (field(0), field(1));
+        let (dbase, dlen) = (field(2), field(3));
+        let bbase = field(4);
+        let data = text.wrapping_add(tlen);
+        let bss = data.wrapping_add(dlen);
+        // The amount to add for each segment ID: undefined, absolute,
+        // text, data, BSS, zero page.
+        let deltas = [
+            0,
+            0,
+            text.wrapping_sub(tbase),
+            data.wrapping_sub(dbase),
+            bss.wrapping_sub(bbase),
+            0,
+        ];
+
+        let mut rest = &file[26..];
+        loop {
+            let len = *rest.first().ok_or(LoadError::Truncated)? as usize;
+            if len == 0 {
+                rest = &rest[1..];
+                break;
+            }
+            rest = rest.get(len..).ok_or(LoadError::Truncated)?;
+        }
+        let mut text_seg = take(&mut rest, tlen as usize)?.to_vec();
+        let mut data_seg = take(&mut rest, dlen as usize)?.to_vec();
+        let undefined = le16(take(&mut rest, 2)?);
+        if undefined != 0 {
+            return Err(LoadError::Invalid("undefined o65 references"));
+        }
+        relocate(&mut rest, &mut text_seg, &deltas, bytewise)?;
+        relocate(&mut rest, &mut data_seg, &deltas, bytewise)?;
+
+        let mut prog = Program::default();
+        prog.push(u32::from(text), &text_seg)?;
+        prog.push(u32::from(data), &data_seg)?;
+        prog.entry = Some(text);
+        Ok(prog)
+    }
+}
+
+fn le16(b: &[u8]) -> u16 {
+    u16::from_le_bytes([b[0], b[1]])
+}
+
+fn be16(b: &[u8]) -> u16 {
+    u16::from_be_bytes([b[0], b[1]])
+}
+
+fn take<'a>(rest: &mut &'a [u8], len: usize) -> Result<&'a [u8], LoadError> {
+    let taken = rest.get(..len).ok_or(LoadError::Truncated)?;
+    *rest = &rest[len..];
+    Ok(taken)
+}
+
+fn hex_bytes(hex: &str) -> Result<Vec<u8>, LoadError> {
+    let bad = LoadError::Invalid("bad hex digit");
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err(bad);
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| bad.clone())
+        })
+        .collect()
+}
+
+// The address in a first BASIC line of "SYS nnnn".
+fn basic_sys(data: &[u8]) -> Option<u16> {
+    // Skip the link and line number; $9E is the SYS token.
+    let line = data.get(4..)?;
+    let digits = line.strip_prefix(&[0x9e])?;
+    let digits: Vec<u8> = digits
+        .iter()
+        .cloned()
+        .skip_while(|&c| c == b' ' || c == b'(')
+        .take_while(u8::is_ascii_digit)
+        .collect();
+    std::str::from_utf8(&digits).ok()?.parse().ok()
+}
+
+// Applies an o65 relocation table to a segment. Each entry moves past
+// some bytes, 254 at a time for 255, then gives the kind of address
+// and the segment it points into. Unless relocation is by pages, the
+// low byte of a high byte follows.
+fn relocate(
+    rest: &mut &[u8],
+    seg: &mut [u8],
+    deltas: &[u16; 6],
+    bytewise: bool,
+) -> Result<(), LoadError> {
+    let bad = LoadError::Invalid("bad o65 relocation");
+    let mut pos = usize::MAX;
+    loop {
+        let offset = take(rest, 1)?[0];
+        match offset {
+            0 => return Ok(()),
+            255 => {
+                pos = pos.wrapping_add(254);
+                continue;
+            }
+            _ => pos = pos.wrapping_add(usize::from(offset)),
+        }
+        let kind = take(rest, 1)?[0];
+        let delta = *deltas.get(usize::from(kind & 0x0f)).ok_or(bad.clone())?;
+        if kind & 0x0f == 0 {
+            take(rest, 2)?;
+        }
+        match kind & 0xe0 {
+            0x80 => {
+                let word = seg.get_mut(pos..pos + 2).ok_or(bad.clone())?;
+                let val = le16(word).wrapping_add(delta);
+                word.copy_from_slice(&val.to_le_bytes());
+            }
+            0x40 => {
+                let lo = if bytewise { take(rest, 1)?[0] } else { 0 };
+                let hi = seg.get_mut(pos).ok_or(bad.clone())?;
+                let val = u16::from_le_bytes([lo, *hi]).wrapping_add(delta);
+                *hi = (val >> 8) as u8;
+            }
+            0x20 => {
+                let lo = seg.get_mut(pos).ok_or(bad.clone())?;
+                *lo = lo.wrapping_add(delta as u8);
+            }
+            _ => return Err(bad),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{Cpu, CycleKind, Nmos, Status, Sys};
+
+// The 6507: an Nmos with only 13 address lines, and no IRQ or NMI pins.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct M6507 {
+    cpu: Nmos,
+}
+
+impl M6507 {
+    pub(crate) fn new() -> M6507 {
+        Default::default()
+    }
+}
+
+impl Cpu for M6507 {
+    #[inline]
+    fn run_instruction<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
+        self.cpu.run_instruction(&mut Pins { sys })
+    }
+
+    fn is_nmos(&self) -> bool {
+        true
+    }
+
+    fn instruction_cycle(&self) -> u32 {
+        self.cpu.instruction_cycle()
+    }
+
+    fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    fn pc(&self) -> u16 {
+        self.cpu.pc()
+    }
+
+    fn set_pc(&mut self, val: u16) {
+        self.cpu.set_pc(val);
+    }
+
+    fn sp(&self) -> u8 {
+        self.cpu.sp()
+    }
+
+    fn set_sp(&mut self, val: u8) {
+        self.cpu.set_sp(val);
+    }
+
+    fn a(&self) -> u8 {
+        self.cpu.a()
+    }
+
+    fn set_a(&mut self, val: u8) {
+        self.cpu.set_a(val);
+    }
+
+    fn x(&self) -> u8 {
+        self.cpu.x()
+    }
+
+    fn set_x(&mut self, val: u8) {
+        self.cpu.set_x(val);
+    }
+
+    fn y(&self) -> u8 {
+        self.cpu.y()
+    }
+
+    fn set_y(&mut self, val: u8) {
+        self.cpu.set_y(val);
+    }
+
+    fn status(&self) -> u8 {
+        self.cpu.status()
+    }
+
+    fn set_status(&mut self, val: u8) {
+        self.cpu.set_status(val);
+    }
+
+    fn flag(&self, f: Status) -> bool {
+        self.cpu.flag(f)
+    }
+
+    fn set_flag(&mut self, f: Status, set: bool) {
+        self.cpu.set_flag(f, set);
+    }
+
+    fn halted(&self) -> bool {
+        self.cpu.halted()
+    }
+}
+
+// The pins the 6507 has: the bus with A13-A15 cut off, and SYNC. The
+// interrupt signals are left at their defaults, never asserted.
+struct Pins<'a, S: Sys> {
+    sys: &'a mut S,
+}
+
+impl<'a, S: Sys> Sys for Pins<'a, S> {
+    #[inline]
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.sys.read(addr & 0x1fff)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.sys.write(addr & 0x1fff, val)
+    }
+
+    #[inline]
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        self.sys.read_kind(addr & 0x1fff, kind)
+    }
+
+    #[inline]
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
+        self.sys.write_kind(addr & 0x1fff, val, kind)
+    }
+
+    #[inline]
+    fn set_sync(&mut self, set: bool) {
+        self.sys.set_sync(set);
+    }
+}
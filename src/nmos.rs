@@ -8,8 +8,9 @@
 
 use std::fmt;
 
+use crate::m6507::M6507;
 use crate::mi::*;
-use crate::{Cpu, Flags, Status, Sys};
+use crate::{Cpu, CycleKind, Flags, Status, Sys};
 
 mod ops;
 
@@ -41,6 +42,15 @@ impl Nmos {
     }
 
     pub fn nes() -> impl Cpu {
+        Nmos::new_nes()
+    }
+
+    // The 6507 has only 13 address lines, and no IRQ or NMI pins.
+    pub fn m6507() -> impl Cpu {
+        M6507::new()
+    }
+
+    pub(crate) fn new_nes() -> Nmos {
         Nmos {
             no_decimal: true,
             ..Default::default()
@@ -54,10 +64,10 @@ impl Cpu for Nmos {
         if self.op_cycle == 0 {
             sys.set_sync(true);
             if self.do_int {
-                self.read(sys, self.pc)?;
+                self.read_kind(sys, self.pc, CycleKind::Dummy)?;
                 self.op = 0x00;
             } else {
-                self.op = self.fetch_operand(sys)?.0;
+                self.op = self.fetch_opcode(sys)?.0;
             }
             sys.set_sync(false);
             self.exec(sys)?;
@@ -377,7 +387,7 @@ impl Nmos {
 
     fn addr_zpi<S: Sys>(&mut self, sys: &mut S, reg: Byte) -> Option<Addr> {
         self.base1 = self.addr_zp(sys)?;
-        self.read(sys, self.base1)?;
+        self.dummy_read(sys, self.base1)?;
         Some(self.base1.no_carry(reg))
     }
 
@@ -395,7 +405,7 @@ impl Nmos {
     ) -> Option<Addr> {
         self.base1 = self.addr_abs(sys)?;
         if write || self.base1.check_carry(reg) {
-            self.read(sys, self.base1.no_carry(reg))?;
+            self.dummy_read(sys, self.base1.no_carry(reg))?;
         } else {
             self.op_cycle += 1;
         }
@@ -404,16 +414,16 @@ impl Nmos {
 
     fn addr_izx<S: Sys>(&mut self, sys: &mut S) -> Option<Addr> {
         self.base1 = self.addr_zp(sys)?;
-        self.read(sys, self.base1)?;
+        self.dummy_read(sys, self.base1)?;
         self.base1 = self.base1.no_carry(self.x);
-        Some(self.fetch_vector_zp(sys, self.base1)?)
+        self.fetch_vector_zp(sys, self.base1)
     }
 
     fn addr_izy<S: Sys>(&mut self, sys: &mut S, write: bool) -> Option<Addr> {
         self.base1 = self.addr_zp(sys)?;
         self.base1 = self.fetch_vector_zp(sys, self.base1)?;
         if write || self.base1.check_carry(self.y) {
-            self.read(sys, self.base1.no_carry(self.y))?;
+            self.dummy_read(sys, self.base1.no_carry(self.y))?;
         } else {
             self.op_cycle += 1;
         }
@@ -422,7 +432,7 @@ impl Nmos {
 
     fn implicit<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         self.poll_signals(sys);
-        self.read(sys, self.pc)?;
+        self.dummy_read(sys, self.pc)?;
         Some(())
     }
 
@@ -436,7 +446,7 @@ impl Nmos {
         F: Fn(&mut Self, Byte) -> Byte,
     {
         self.lo_byte = self.read(sys, addr)?;
-        self.write(sys, addr, self.lo_byte)?;
+        self.write_kind(sys, addr, self.lo_byte, CycleKind::Dummy)?;
         self.lo_byte = op(self, self.lo_byte);
         self.store(sys, addr, self.lo_byte)?;
         Some(())
@@ -446,11 +456,11 @@ impl Nmos {
         self.poll_signals(sys);
         self.lo_byte = self.fetch_operand(sys)?;
         if taken {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
             let offset = BranchOffset::as_from(self.lo_byte);
             if self.pc.check_carry(offset) {
                 self.poll_signals(sys);
-                self.read(sys, self.pc.no_carry(offset))?;
+                self.dummy_read(sys, self.pc.no_carry(offset))?;
             }
             self.pc += offset;
         }
@@ -467,8 +477,14 @@ impl Nmos {
         Some(Addr::from_bytes(self.lo_byte, self.hi_byte))
     }
 
+    fn fetch_opcode<S: Sys>(&mut self, sys: &mut S) -> Option<Byte> {
+        let val = self.read_kind(sys, self.pc, CycleKind::Opcode)?;
+        self.pc += 1;
+        Some(val)
+    }
+
     fn fetch_operand<S: Sys>(&mut self, sys: &mut S) -> Option<Byte> {
-        let val = self.read(sys, self.pc)?;
+        let val = self.read_kind(sys, self.pc, CycleKind::Operand)?;
         self.pc += 1;
         Some(val)
     }
@@ -481,24 +497,57 @@ impl Nmos {
         None
     }
 
-    fn read<S: Sys>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte> {
-        let val = sys.read(addr.0)?;
+    fn read_kind<S: Sys>(
+        &mut self,
+        sys: &mut S,
+        addr: Addr,
+        kind: CycleKind,
+    ) -> Option<Byte> {
+        let val = sys.read_kind(addr.0, kind)?;
         self.op_cycle += 1;
         Some(MachineInt(val))
     }
 
+    fn read<S: Sys>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte> {
+        self.read_kind(sys, addr, CycleKind::Data)
+    }
+
+    fn dummy_read<S>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte>
+    where
+        S: Sys,
+    {
+        self.read_kind(sys, addr, CycleKind::Dummy)
+    }
+
+    fn read_vector<S>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte>
+    where
+        S: Sys,
+    {
+        self.read_kind(sys, addr, CycleKind::Vector)
+    }
+
     fn load<S: Sys>(&mut self, sys: &mut S, addr: Addr) -> Option<Byte> {
         self.poll_signals(sys);
         self.read(sys, addr)
     }
 
+    fn write_kind<S: Sys>(
+        &mut self,
+        sys: &mut S,
+        addr: Addr,
+        val: Byte,
+        kind: CycleKind,
+    ) -> Option<()> {
+        sys.write_kind(addr.0, val.0, kind)?;
+        self.op_cycle += 1;
+        Some(())
+    }
+
     fn write<S>(&mut self, sys: &mut S, addr: Addr, val: Byte) -> Option<()>
     where
         S: Sys,
     {
-        sys.write(addr.0, val.0)?;
-        self.op_cycle += 1;
-        Some(())
+        self.write_kind(sys, addr, val, CycleKind::Data)
     }
 
     fn store<S>(&mut self, sys: &mut S, addr: Addr, val: Byte) -> Option<()>
@@ -510,11 +559,21 @@ impl Nmos {
     }
 
     fn read_stack<S: Sys>(&mut self, sys: &mut S) -> Option<Byte> {
-        self.read(sys, Addr::stack(self.sp))
+        self.read_kind(sys, Addr::stack(self.sp), CycleKind::Stack)
     }
 
     fn write_stack<S: Sys>(&mut self, sys: &mut S, val: Byte) -> Option<()> {
-        self.write(sys, Addr::stack(self.sp), val)
+        self.write_kind(sys, Addr::stack(self.sp), val, CycleKind::Stack)
+    }
+
+    fn pull<S: Sys>(&mut self, sys: &mut S) -> Option<Byte> {
+        self.poll_signals(sys);
+        self.read_stack(sys)
+    }
+
+    fn push<S: Sys>(&mut self, sys: &mut S, val: Byte) -> Option<()> {
+        self.poll_signals(sys);
+        self.write_stack(sys, val)
     }
 }
 
@@ -529,7 +588,7 @@ impl Nmos {
         }
 
         // op_cycle == 2
-        self.read(sys, self.base1)?;
+        self.dummy_read(sys, self.base1)?;
         Some(self.base1.no_carry(reg))
         // op_cycle == 3
     }
@@ -557,7 +616,7 @@ impl Nmos {
 
         // op_cycle == 3
         if write || self.base1.check_carry(reg) {
-            self.read(sys, self.base1.no_carry(reg))?;
+            self.dummy_read(sys, self.base1.no_carry(reg))?;
         } else {
             self.op_cycle += 1;
         }
@@ -570,12 +629,12 @@ impl Nmos {
             self.base1 = self.addr_zp(sys)?;
         }
         if self.op_cycle == 2 {
-            self.read(sys, self.base1)?;
+            self.dummy_read(sys, self.base1)?;
             self.base1 = self.base1.no_carry(self.x);
         }
 
         // op_cycle >= 3
-        Some(self.cycle_fetch_vector_zp(sys, self.base1, 3)?)
+        self.cycle_fetch_vector_zp(sys, self.base1, 3)
         // op_cycle == 5
     }
 
@@ -592,7 +651,7 @@ impl Nmos {
 
         // op_cycle == 4
         if write || self.base1.check_carry(self.y) {
-            self.read(sys, self.base1.no_carry(self.y))?;
+            self.dummy_read(sys, self.base1.no_carry(self.y))?;
         } else {
             self.op_cycle += 1;
         }
@@ -632,7 +691,7 @@ impl Nmos {
             self.lo_byte = self.read(sys, addr)?;
         }
         if self.op_cycle == start_cycle + 1 {
-            self.write(sys, addr, self.lo_byte)?;
+            self.write_kind(sys, addr, self.lo_byte, CycleKind::Dummy)?;
             self.lo_byte = op(self, self.lo_byte);
         }
         // op_cycle == start_cycle + 2
@@ -648,13 +707,13 @@ impl Nmos {
         // op_cycle >= 2
         if taken {
             if self.op_cycle == 2 {
-                self.read(sys, self.pc)?;
+                self.dummy_read(sys, self.pc)?;
             }
             if self.op_cycle == 3 {
                 let offset = BranchOffset::as_from(self.lo_byte);
                 if self.pc.check_carry(offset) {
                     self.poll_signals(sys);
-                    self.read(sys, self.pc.no_carry(offset))?;
+                    self.dummy_read(sys, self.pc.no_carry(offset))?;
                 }
                 self.pc += offset;
             }
@@ -668,19 +727,19 @@ impl Nmos {
         }
 
         if self.op_cycle == 2 {
-            self.read(sys, MachineInt(0xffff))?;
+            self.dummy_read(sys, MachineInt(0xffff))?;
         }
 
         if self.op_cycle == 3 {
-            self.read(sys, MachineInt(0xfffe))?;
+            self.dummy_read(sys, MachineInt(0xfffe))?;
         }
 
         if self.op_cycle == 4 {
-            self.read(sys, MachineInt(0xfffe))?;
+            self.dummy_read(sys, MachineInt(0xfffe))?;
         }
 
         // op_cycle >= 5
-        self.read(sys, MachineInt(0xffff))?;
+        self.dummy_read(sys, MachineInt(0xffff))?;
         self.op_cycle -= 1;
         Some(())
     }
@@ -16,21 +16,21 @@ impl Nmos {
     fn op_00<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         // PC is incremented for BRK but not NMI/IRQ
         if self.do_int {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         } else {
             self.fetch_operand(sys)?;
         }
 
         // The stack writes become reads for RES
         if self.reset {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
         } else {
             self.write_stack(sys, self.pc.hi())?;
         }
         self.sp -= 1;
 
         if self.reset {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
         } else {
             self.write_stack(sys, self.pc.lo())?;
         }
@@ -40,7 +40,7 @@ impl Nmos {
         self.base1 = self.signal_vector(sys);
 
         if self.reset {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
         } else if self.do_int {
             // Clear B flag in saved status for NMI/IRQ
             self.write_stack(sys, self.flags.to_byte() & 0b1110_1111)?;
@@ -57,7 +57,7 @@ impl Nmos {
             sys.poll_nmi();
         }
 
-        self.lo_byte = self.read(sys, self.base1)?;
+        self.lo_byte = self.read_vector(sys, self.base1)?;
 
         // TODO: say why needed
         if !self.nmi && sys.peek_nmi() && sys.nmi_length() < NmiLength::Two
@@ -65,7 +65,7 @@ impl Nmos {
             sys.poll_nmi();
         }
 
-        self.hi_byte = self.read(sys, self.base1 + 1)?;
+        self.hi_byte = self.read_vector(sys, self.base1 + 1)?;
         self.pc = Addr::from_bytes(self.lo_byte, self.hi_byte);
 
         self.flags.i = true;
@@ -126,8 +126,8 @@ impl Nmos {
 
     // PHP
     fn op_08<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.store(sys, Addr::stack(self.sp), self.flags.to_byte())?;
+        self.dummy_read(sys, self.pc)?;
+        self.push(sys, self.flags.to_byte())?;
         self.sp -= 1;
         Some(())
     }
@@ -298,7 +298,7 @@ impl Nmos {
     // JSR $nnnn
     fn op_20<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         self.lo_byte = self.fetch_operand(sys)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.write_stack(sys, self.pc.hi())?;
         self.sp -= 1;
         self.write_stack(sys, self.pc.lo())?;
@@ -362,10 +362,10 @@ impl Nmos {
 
     // PLP
     fn op_28<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
-        let p = self.load(sys, Addr::stack(self.sp))?;
+        let p = self.pull(sys)?;
         self.flags.from_byte(p);
         Some(())
     }
@@ -536,8 +536,8 @@ impl Nmos {
 
     // RTI
     fn op_40<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
         let p = self.read_stack(sys)?;
         self.sp += 1;
@@ -602,8 +602,8 @@ impl Nmos {
 
     // PHA
     fn op_48<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.store(sys, Addr::stack(self.sp), self.a)?;
+        self.dummy_read(sys, self.pc)?;
+        self.push(sys, self.a)?;
         self.sp -= 1;
         Some(())
     }
@@ -775,15 +775,16 @@ impl Nmos {
 
     // RTS
     fn op_60<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
         self.lo_byte = self.read_stack(sys)?;
         self.sp += 1;
         self.hi_byte = self.read_stack(sys)?;
         self.pc = Addr::from_bytes(self.lo_byte, self.hi_byte);
         self.poll_signals(sys);
-        self.fetch_operand(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.pc += 1;
         Some(())
     }
 
@@ -841,10 +842,10 @@ impl Nmos {
     fn op_68<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         // on real 6502 if rdy low during dummy read, sp is advanced
         // (and placed on the address bus)
-        self.read(sys, self.pc)?;
-        self.read_stack(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.dummy_read(sys, Addr::stack(self.sp))?;
         self.sp += 1;
-        self.a = self.load(sys, Addr::stack(self.sp))?;
+        self.a = self.pull(sys)?;
         self.flags.nz(self.a);
         Some(())
     }
@@ -1139,7 +1140,7 @@ impl Nmos {
         self.base1 = self.addr_zp(sys)?;
         self.base1 = self.fetch_vector_zp(sys, self.base1)?;
         // TODO: match and check if sys.rdy to remove &{H+1}
-        self.read(sys, self.base1.no_carry(self.y))?;
+        self.dummy_read(sys, self.base1.no_carry(self.y))?;
         self.lo_byte = self.a & self.x & (self.base1.hi() + 1);
         if self.base1.check_carry(self.y) {
             self.base1 =
@@ -1200,7 +1201,7 @@ impl Nmos {
         self.base1 = self.addr_abs(sys)?;
         self.sp = self.a & self.x;
         // TODO: match and check if sys.rdy to remove &{H+1}
-        self.read(sys, self.base1.no_carry(self.y))?;
+        self.dummy_read(sys, self.base1.no_carry(self.y))?;
         self.lo_byte = self.a & self.x & (self.base1.hi() + 1);
         if self.base1.check_carry(self.y) {
             self.base1 =
@@ -1215,7 +1216,7 @@ impl Nmos {
     fn op_9c<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         self.base1 = self.addr_abs(sys)?;
         // TODO: match and check if sys.rdy
-        self.read(sys, self.base1.no_carry(self.x))?;
+        self.dummy_read(sys, self.base1.no_carry(self.x))?;
         self.lo_byte = self.y & (self.base1.hi() + 1);
         if self.base1.check_carry(self.x) {
             self.base1 =
@@ -1236,7 +1237,7 @@ impl Nmos {
     fn op_9e<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         self.base1 = self.addr_abs(sys)?;
         // TODO: match and check if sys.rdy to remove &{H+1}
-        self.read(sys, self.base1.no_carry(self.y))?;
+        self.dummy_read(sys, self.base1.no_carry(self.y))?;
         self.lo_byte = self.x & (self.base1.hi() + 1);
         if self.base1.check_carry(self.y) {
             self.base1 =
@@ -1251,7 +1252,7 @@ impl Nmos {
     fn op_9f<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         self.base1 = self.addr_abs(sys)?;
         // TODO: match and check if sys.rdy to remove &{H+1}
-        self.read(sys, self.base1.no_carry(self.y))?;
+        self.dummy_read(sys, self.base1.no_carry(self.y))?;
         self.lo_byte = self.a & self.x & (self.base1.hi() + 1);
         if self.base1.check_carry(self.y) {
             self.base1 =
@@ -1983,10 +1984,7 @@ impl Nmos {
 }
 
 impl Nmos {
-    #[cfg_attr(
-        feature = "cargo-clippy",
-        allow(clippy::cyclomatic_complexity)
-    )]
+    #[allow(clippy::cognitive_complexity)]
     pub(crate) fn exec<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         match self.op {
             0x00 => self.op_00(sys)?,
@@ -2245,7 +2243,6 @@ impl Nmos {
             0xfd => self.op_fd(sys)?,
             0xfe => self.op_fe(sys)?,
             0xff => self.op_ff(sys)?,
-            _ => unreachable!(),
         }
         Some(())
     }
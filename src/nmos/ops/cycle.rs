@@ -15,7 +15,7 @@ impl Nmos {
     fn cycle_op_00<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
             if self.do_int {
-                self.read(sys, self.pc)?;
+                self.dummy_read(sys, self.pc)?;
             } else {
                 self.fetch_operand(sys)?;
             }
@@ -23,7 +23,7 @@ impl Nmos {
 
         if self.op_cycle == 2 {
             if self.reset {
-                self.read_stack(sys)?;
+                self.dummy_read(sys, Addr::stack(self.sp))?;
             } else {
                 self.write_stack(sys, self.pc.hi())?;
             }
@@ -32,7 +32,7 @@ impl Nmos {
 
         if self.op_cycle == 3 {
             if self.reset {
-                self.read_stack(sys)?;
+                self.dummy_read(sys, Addr::stack(self.sp))?;
             } else {
                 self.write_stack(sys, self.pc.lo())?;
             }
@@ -42,7 +42,7 @@ impl Nmos {
 
         if self.op_cycle == 4 {
             if self.reset {
-                self.read_stack(sys)?;
+                self.dummy_read(sys, Addr::stack(self.sp))?;
             } else if self.do_int {
                 self.write_stack(sys, self.flags.to_byte() & 0b1110_1111)?;
             } else {
@@ -58,7 +58,7 @@ impl Nmos {
         }
 
         if self.op_cycle == 5 {
-            self.lo_byte = self.read(sys, self.base1)?;
+            self.lo_byte = self.read_vector(sys, self.base1)?;
             if !self.nmi && sys.peek_nmi() && sys.nmi_length() < NmiLength::Two
             {
                 sys.poll_nmi();
@@ -66,7 +66,7 @@ impl Nmos {
         }
 
         // op_cycle == 6
-        self.hi_byte = self.read(sys, self.base1 + 1)?;
+        self.hi_byte = self.read_vector(sys, self.base1 + 1)?;
         self.pc = Addr::from_bytes(self.lo_byte, self.hi_byte);
         self.flags.i = true;
         self.clear_signals();
@@ -150,11 +150,11 @@ impl Nmos {
     // PHP
     fn cycle_op_08<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         // op_cycle == 2
-        self.store(sys, Addr::stack(self.sp), self.flags.to_byte())?;
+        self.push(sys, self.flags.to_byte())?;
         self.sp -= 1;
         Some(())
     }
@@ -367,7 +367,7 @@ impl Nmos {
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
         }
 
         if self.op_cycle == 3 {
@@ -465,16 +465,16 @@ impl Nmos {
     // PLP
     fn cycle_op_28<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
         // op_cycle == 3
-        let p = self.load(sys, Addr::stack(self.sp))?;
+        let p = self.pull(sys)?;
         self.flags.from_byte(p);
         Some(())
     }
@@ -684,11 +684,11 @@ impl Nmos {
     // RTI
     fn cycle_op_40<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
@@ -787,11 +787,11 @@ impl Nmos {
     // PHA
     fn cycle_op_48<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         // op_cycle == 2
-        self.store(sys, Addr::stack(self.sp), self.a)?;
+        self.push(sys, self.a)?;
         self.sp -= 1;
         Some(())
     }
@@ -1002,11 +1002,11 @@ impl Nmos {
     // RTS
     fn cycle_op_60<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
@@ -1022,7 +1022,8 @@ impl Nmos {
         }
 
         // op_cycle == 5
-        self.fetch_operand(sys)?;
+        self.dummy_read(sys, self.pc)?;
+        self.pc += 1;
         Some(())
     }
 
@@ -1103,16 +1104,16 @@ impl Nmos {
     // PLA
     fn cycle_op_68<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         if self.op_cycle == 1 {
-            self.read(sys, self.pc)?;
+            self.dummy_read(sys, self.pc)?;
         }
 
         if self.op_cycle == 2 {
-            self.read_stack(sys)?;
+            self.dummy_read(sys, Addr::stack(self.sp))?;
             self.sp += 1;
         }
 
         // op_cycle == 3
-        self.a = self.load(sys, Addr::stack(self.sp))?;
+        self.a = self.pull(sys)?;
         self.flags.nz(self.a);
         Some(())
     }
@@ -1463,7 +1464,7 @@ impl Nmos {
 
         if self.op_cycle == 4 {
             // TODO: match and check if sys.rdy to remove &{H+1}
-            self.read(sys, self.base1.no_carry(self.y))?;
+            self.dummy_read(sys, self.base1.no_carry(self.y))?;
             self.lo_byte = self.a & self.x & (self.base1.hi() + 1);
             if self.base1.check_carry(self.y) {
                 self.base1 =
@@ -1539,7 +1540,7 @@ impl Nmos {
 
         if self.op_cycle == 3 {
             // TODO: match and check if sys.rdy to remove &{H+1}
-            self.read(sys, self.base1.no_carry(self.y))?;
+            self.dummy_read(sys, self.base1.no_carry(self.y))?;
             self.sp = self.a & self.x;
             self.lo_byte = self.a & self.x & (self.base1.hi() + 1);
             if self.base1.check_carry(self.y) {
@@ -1562,7 +1563,7 @@ impl Nmos {
 
         if self.op_cycle == 3 {
             // TODO: match and check if sys.rdy to remove &{H+1}
-            self.read(sys, self.base1.no_carry(self.x))?;
+            self.dummy_read(sys, self.base1.no_carry(self.x))?;
             self.lo_byte = self.y & (self.base1.hi() + 1);
             if self.base1.check_carry(self.x) {
                 self.base1 =
@@ -1594,7 +1595,7 @@ impl Nmos {
 
         if self.op_cycle == 3 {
             // TODO: match and check if sys.rdy to remove &{H+1}
-            self.read(sys, self.base1.no_carry(self.y))?;
+            self.dummy_read(sys, self.base1.no_carry(self.y))?;
             self.lo_byte = self.x & (self.base1.hi() + 1);
             if self.base1.check_carry(self.y) {
                 self.base1 =
@@ -1616,7 +1617,7 @@ impl Nmos {
 
         if self.op_cycle == 3 {
             // TODO: match and check if sys.rdy to remove &{H+1}
-            self.read(sys, self.base1.no_carry(self.y))?;
+            self.dummy_read(sys, self.base1.no_carry(self.y))?;
             self.lo_byte = self.a & self.x & (self.base1.hi() + 1);
             if self.base1.check_carry(self.y) {
                 self.base1 =
@@ -2481,10 +2482,7 @@ impl Nmos {
 }
 
 impl Nmos {
-    #[cfg_attr(
-        feature = "cargo-clippy",
-        allow(clippy::cyclomatic_complexity)
-    )]
+    #[allow(clippy::cognitive_complexity)]
     pub(crate) fn cycle_exec<S: Sys>(&mut self, sys: &mut S) -> Option<()> {
         match self.op {
             0x00 => self.cycle_op_00(sys)?,
@@ -2743,7 +2741,6 @@ impl Nmos {
             0xfd => self.cycle_op_fd(sys)?,
             0xfe => self.cycle_op_fe(sys)?,
             0xff => self.cycle_op_ff(sys)?,
-            _ => unreachable!(),
         }
         Some(())
     }
@@ -0,0 +1,438 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::io::{self, Write};
+
+use crate::{Apu, Cpu, Device, LoadError, Nmos, Status, Sys};
+
+const NTSC_HZ: u64 = 1_789_773;
+const PAL_HZ: u64 = 1_662_607;
+
+// The usual PLAY rates, in microseconds.
+const NTSC_SPEED: u16 = 16639;
+const PAL_SPEED: u16 = 19997;
+
+// INIT and PLAY are called with this return address (less one) on the
+// stack, and the call ends when the PC reaches it.
+const RETURN: u16 = 0x5ff0;
+
+// A write to a sound register, and the cycle it happened on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RegWrite {
+    pub cycle: u64,
+    pub addr: u16,
+    pub val: u8,
+}
+
+// Writes one write per line, as the decimal cycle, then the address and
+// value in hex.
+pub fn write_reg_log<W: Write>(
+    mut out: W,
+    writes: &[RegWrite],
+) -> io::Result<()> {
+    for w in writes {
+        writeln!(out, "{} {:04x} {:02x}", w.cycle, w.addr, w.val)?;
+    }
+    Ok(())
+}
+
+// An NSF or NSFe file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Nsf {
+    pub songs: u8,
+    // Counting from zero, unlike the NSF header.
+    pub start_song: u8,
+    pub load_addr: u16,
+    pub init_addr: u16,
+    pub play_addr: u16,
+    pub title: String,
+    pub artist: String,
+    pub copyright: String,
+    // The PLAY rates, in microseconds.
+    pub ntsc_speed: u16,
+    pub pal_speed: u16,
+    // The initial banks for $8000-$FFFF, or None if not bankswitched.
+    pub banks: Option<[u8; 8]>,
+    pub pal: bool,
+    // The expansion audio chips.
+    pub chips: u8,
+    pub data: Vec<u8>,
+}
+
+impl Nsf {
+    pub fn parse(file: &[u8]) -> Result<Nsf, LoadError> {
+        if file.starts_with(b"NESM\x1a") {
+            Nsf::parse_nsf(file)
+        } else if file.starts_with(b"NSFE") {
+            Nsf::parse_nsfe(file)
+        } else {
+            Err(LoadError::BadMagic)
+        }
+    }
+
+    fn parse_nsf(file: &[u8]) -> Result<Nsf, LoadError> {
+        if file.len() < 0x80 {
+            return Err(LoadError::Truncated);
+        }
+        let mut data = &file[0x80..];
+        // NSF2 may give the length of the data, with metadata after it.
+        let len = le24(&file[0x7d..]);
+        if file[5] >= 2 && len != 0 {
+            data = data.get(..len).ok_or(LoadError::Truncated)?;
+        }
+        let nsf = Nsf {
+            songs: file[6],
+            start_song: file[7].saturating_sub(1),
+            load_addr: le16(&file[8..]),
+            init_addr: le16(&file[0x0a..]),
+            play_addr: le16(&file[0x0c..]),
+            title: text(&file[0x0e..0x2e]),
+            artist: text(&file[0x2e..0x4e]),
+            copyright: text(&file[0x4e..0x6e]),
+            ntsc_speed: le16(&file[0x6e..]),
+            pal_speed: le16(&file[0x78..]),
+            banks: banks(&file[0x70..0x78]),
+            pal: file[0x7a] & 3 == 1,
+            chips: file[0x7b],
+            data: data.to_vec(),
+        };
+        nsf.check()
+    }
+
+    // NSFe is a list of chunks, each a length, an ID, and the data; IDs
+    // starting with a capital letter must be understood.
+    fn parse_nsfe(file: &[u8]) -> Result<Nsf, LoadError> {
+        let mut nsf = Nsf {
+            ntsc_speed: NTSC_SPEED,
+            pal_speed: PAL_SPEED,
+            ..Default::default()
+        };
+        let (mut info, mut data) = (false, false);
+        let mut rest = &file[4..];
+        loop {
+            if rest.len() < 8 {
+                return Err(LoadError::Truncated);
+            }
+            let len = le32(rest) as usize;
+            let id = &rest[4..8];
+            let chunk = rest.get(8..8 + len).ok_or(LoadError::Truncated)?;
+            rest = &rest[8 + len..];
+            match id {
+                b"INFO" => {
+                    if chunk.len() < 8 {
+                        return Err(LoadError::Truncated);
+                    }
+                    nsf.load_addr = le16(chunk);
+                    nsf.init_addr = le16(&chunk[2..]);
+                    nsf.play_addr = le16(&chunk[4..]);
+                    nsf.pal = chunk[6] & 3 == 1;
+                    nsf.chips = chunk[7];
+                    nsf.songs = chunk.get(8).cloned().unwrap_or(1);
+                    nsf.start_song = chunk.get(9).cloned().unwrap_or(0);
+                    info = true;
+                }
+                b"DATA" => {
+                    nsf.data = chunk.to_vec();
+                    data = true;
+                }
+                b"BANK" => {
+                    let mut init = [0; 8];
+                    let n = chunk.len().min(8);
+                    init[..n].copy_from_slice(&chunk[..n]);
+                    nsf.banks = banks(&init);
+                }
+                b"RATE" => {
+                    if chunk.len() >= 2 {
+                        nsf.ntsc_speed = le16(chunk);
+                    }
+                    if chunk.len() >= 4 {
+                        nsf.pal_speed = le16(&chunk[2..]);
+                    }
+                }
+                b"auth" => {
+                    let mut fields = chunk.split(|&b| b == 0).map(text);
+                    nsf.title = fields.next().unwrap_or_default();
+                    nsf.artist = fields.next().unwrap_or_default();
+                    nsf.copyright = fields.next().unwrap_or_default();
+                }
+                b"NEND" => break,
+                _ if id[0].is_ascii_uppercase() => {
+                    return Err(LoadError::Invalid("unknown NSFe chunk"));
+                }
+                _ => (),
+            }
+        }
+        if !info || !data {
+            return Err(LoadError::Invalid("missing NSFe chunk"));
+        }
+        nsf.check()
+    }
+
+    fn check(self) -> Result<Nsf, LoadError> {
+        if self.banks.is_none() && self.load_addr < 0x8000 {
+            return Err(LoadError::Invalid("load address below $8000"));
+        }
+        if self.songs == 0 || self.start_song >= self.songs {
+            return Err(LoadError::Invalid("bad song count"));
+        }
+        if self.data.is_empty() {
+            return Err(LoadError::Invalid("no data"));
+        }
+        Ok(self)
+    }
+
+    // The cycles between calls to PLAY. A rate of zero is taken to be
+    // the usual one.
+    pub fn frame_cycles(&self) -> u64 {
+        let (speed, usual, hz) = if self.pal {
+            (self.pal_speed, PAL_SPEED, PAL_HZ)
+        } else {
+            (self.ntsc_speed, NTSC_SPEED, NTSC_HZ)
+        };
+        let speed = if speed == 0 { usual } else { speed };
+        u64::from(speed) * hz / 1_000_000
+    }
+}
+
+fn le16(b: &[u8]) -> u16 {
+    u16::from_le_bytes([b[0], b[1]])
+}
+
+fn le24(b: &[u8]) -> usize {
+    usize::from(b[0]) | usize::from(b[1]) << 8 | usize::from(b[2]) << 16
+}
+
+fn le32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn text(b: &[u8]) -> String {
+    let end = b.iter().position(|&c| c == 0).unwrap_or(b.len());
+    String::from_utf8_lossy(&b[..end]).into_owned()
+}
+
+fn banks(b: &[u8]) -> Option<[u8; 8]> {
+    if b.iter().all(|&bank| bank == 0) {
+        return None;
+    }
+    let mut banks = [0; 8];
+    banks.copy_from_slice(b);
+    Some(banks)
+}
+
+// Plays an NSF on an NES CPU, logging every write to $4000-$4017.
+// Expansion audio is ignored.
+//
+// There is no APU unless one is put in the Sys; the log alone is
+// enough to test a driver, or to replay elsewhere.
+pub struct NsfPlayer {
+    pub cpu: Nmos,
+    pub sys: NsfSys,
+    init_addr: u16,
+    play_addr: u16,
+    frame_cycles: u64,
+    pal: bool,
+    // INIT or PLAY hasn't returned yet.
+    busy: bool,
+}
+
+impl NsfPlayer {
+    pub fn new(nsf: &Nsf) -> NsfPlayer {
+        let rom = match nsf.banks {
+            Some(_) => {
+                let pad = (nsf.load_addr & 0x0fff) as usize;
+                let mut rom = vec![0; pad];
+                rom.extend_from_slice(&nsf.data);
+                let len = (rom.len() + 0xfff) & !0xfff;
+                rom.resize(len, 0);
+                rom
+            }
+            None => {
+                let start = (nsf.load_addr - 0x8000) as usize;
+                let mut rom = vec![0; 0x8000];
+                let len = nsf.data.len().min(0x8000 - start);
+                rom[start..start + len].copy_from_slice(&nsf.data[..len]);
+                rom
+            }
+        };
+        NsfPlayer {
+            cpu: Nmos::new_nes(),
+            sys: NsfSys {
+                ram: vec![0; 0x800],
+                wram: vec![0; 0x2000],
+                rom,
+                init_banks: nsf.banks,
+                banks: [0, 1, 2, 3, 4, 5, 6, 7],
+                apu: None,
+                log: Vec::new(),
+                cycles: 0,
+            },
+            init_addr: nsf.init_addr,
+            play_addr: nsf.play_addr,
+            frame_cycles: nsf.frame_cycles(),
+            pal: nsf.pal,
+            busy: false,
+        }
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.sys.cycles
+    }
+
+    pub fn log(&self) -> &[RegWrite] {
+        &self.sys.log
+    }
+
+    pub fn take_log(&mut self) -> Vec<RegWrite> {
+        std::mem::take(&mut self.sys.log)
+    }
+
+    // Starts a song, counting from zero, by clearing memory and the
+    // sound registers, and calling INIT. INIT is given 60 frames; if it
+    // takes longer, it goes on running in place of PLAY.
+    pub fn init(&mut self, song: u8) {
+        for mem in self.sys.ram.iter_mut().chain(self.sys.wram.iter_mut()) {
+            *mem = 0;
+        }
+        if let Some(banks) = self.sys.init_banks {
+            self.sys.banks = banks;
+        }
+        if let Some(apu) = self.sys.apu.as_mut() {
+            for reg in 0..0x14 {
+                apu.write(reg, 0);
+            }
+            apu.write(0x15, 0x0f);
+            apu.write(0x17, 0x40);
+        }
+        self.cpu = Nmos::new_nes();
+        self.cpu.set_sp(0xfd);
+        self.cpu.set_flag(Status::I, true);
+        let x = self.pal as u8;
+        let end = self.sys.cycles + self.frame_cycles * 60;
+        self.call(self.init_addr, song, x);
+        self.run_until(end);
+    }
+
+    // Calls PLAY, then waits for the rest of the frame. A PLAY that
+    // overruns its frame finishes in the next one, which it takes the
+    // place of.
+    pub fn run_frame(&mut self) {
+        let end = self.sys.cycles + self.frame_cycles;
+        if !self.busy {
+            self.call(self.play_addr, 0, 0);
+        }
+        self.run_until(end);
+        while self.sys.cycles < end {
+            self.sys.tick();
+        }
+    }
+
+    pub fn run_frames(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.run_frame();
+        }
+    }
+
+    fn call(&mut self, addr: u16, a: u8, x: u8) {
+        let ret = RETURN - 1;
+        let sp = self.cpu.sp();
+        self.sys.ram[0x100 + sp as usize] = (ret >> 8) as u8;
+        self.sys.ram[0x100 + sp.wrapping_sub(1) as usize] = ret as u8;
+        self.cpu.set_sp(sp.wrapping_sub(2));
+        self.cpu.set_a(a);
+        self.cpu.set_x(x);
+        self.cpu.set_y(0);
+        self.cpu.set_pc(addr);
+        self.busy = true;
+    }
+
+    // Runs the routine being called until it returns, or until the
+    // given cycle.
+    fn run_until(&mut self, end: u64) {
+        while self.busy && self.sys.cycles < end {
+            if self.cpu.halted() {
+                self.sys.tick();
+            } else {
+                self.cpu.run_instruction(&mut self.sys);
+            }
+            self.busy = self.cpu.pc() != RETURN;
+        }
+    }
+}
+
+pub struct NsfSys {
+    pub ram: Vec<u8>,
+    pub wram: Vec<u8>,
+    pub apu: Option<Apu>,
+    rom: Vec<u8>,
+    init_banks: Option<[u8; 8]>,
+    banks: [u8; 8],
+    log: Vec<RegWrite>,
+    cycles: u64,
+}
+
+// The ROM byte at addr, through the bank registers for $8000-$FFFF.
+fn read_rom(rom: &[u8], banks: &[u8; 8], addr: u16) -> u8 {
+    let count = rom.len() / 0x1000;
+    let bank = banks[((addr >> 12) & 7) as usize] as usize % count;
+    rom[bank * 0x1000 + (addr & 0x0fff) as usize]
+}
+
+impl NsfSys {
+    // DMC samples are fetched without stealing cycles.
+    fn tick(&mut self) {
+        if let Some(apu) = self.apu.as_mut() {
+            apu.tick();
+            if let Some(addr) = apu.dmc_request() {
+                apu.dmc_fill(read_rom(&self.rom, &self.banks, addr));
+            }
+        }
+        self.cycles += 1;
+    }
+}
+
+impl Sys for NsfSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let val = match addr {
+            0x0000..=0x1fff => self.ram[(addr & 0x7ff) as usize],
+            0x4015 => self.apu.as_mut().map_or(0, |apu| apu.read(0x15)),
+            0x6000..=0x7fff => self.wram[(addr & 0x1fff) as usize],
+            0x8000..=0xffff => read_rom(&self.rom, &self.banks, addr),
+            _ => 0,
+        };
+        self.tick();
+        Some(val)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        match addr {
+            0x0000..=0x1fff => self.ram[(addr & 0x7ff) as usize] = val,
+            0x4000..=0x4017 => {
+                self.log.push(RegWrite {
+                    cycle: self.cycles,
+                    addr,
+                    val,
+                });
+                if let Some(apu) = self.apu.as_mut() {
+                    apu.write(addr as u8, val);
+                }
+            }
+            0x5ff8..=0x5fff if self.init_banks.is_some() => {
+                self.banks[(addr & 7) as usize] = val;
+            }
+            0x6000..=0x7fff => self.wram[(addr & 0x1fff) as usize] = val,
+            _ => (),
+        }
+        self.tick();
+        Some(())
+    }
+
+    fn irq(&self) -> bool {
+        self.apu.as_ref().is_some_and(|apu| apu.irq())
+    }
+}
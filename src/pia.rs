@@ -0,0 +1,293 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::VecDeque;
+
+use crate::Device;
+
+const CR_IRQ1: u8 = 0x80;
+const CR_IRQ2: u8 = 0x40;
+
+// One side of the PIA.
+#[derive(Clone, Default, Debug)]
+struct Port {
+    or: u8,
+    ddr: u8,
+    cr: u8,
+    input: u8,
+    c1: bool,
+    c2: bool,
+    c2_out: bool,
+    c2_pulse: u8,
+}
+
+impl Port {
+    fn new() -> Port {
+        Port {
+            input: 0xff,
+            c1: true,
+            c2: true,
+            c2_out: true,
+            ..Port::default()
+        }
+    }
+
+    fn pins(&self) -> u8 {
+        (self.or & self.ddr) | (self.input & !self.ddr)
+    }
+
+    fn c2_output(&self) -> bool {
+        self.cr & 0x20 != 0
+    }
+
+    fn c2(&self) -> bool {
+        match (self.cr >> 3) & 7 {
+            0b110 => false,
+            0b111 => true,
+            0b100 | 0b101 => self.c2_out,
+            _ => self.c2,
+        }
+    }
+
+    fn set_c1(&mut self, set: bool) {
+        let positive = self.cr & 0x02 != 0;
+        if set != self.c1 && set == positive {
+            self.cr |= CR_IRQ1;
+            // Handshake mode restores C2 on the active C1 edge.
+            if (self.cr >> 3) & 7 == 0b100 {
+                self.c2_out = true;
+            }
+        }
+        self.c1 = set;
+    }
+
+    fn set_c2(&mut self, set: bool) {
+        let positive = self.cr & 0x10 != 0;
+        if !self.c2_output() && set != self.c2 && set == positive {
+            self.cr |= CR_IRQ2;
+        }
+        self.c2 = set;
+    }
+
+    fn strobe(&mut self) {
+        match (self.cr >> 3) & 7 {
+            0b100 => self.c2_out = false,
+            0b101 => {
+                self.c2_out = false;
+                self.c2_pulse = 2;
+            }
+            _ => (),
+        }
+    }
+
+    fn read_data(&mut self) -> u8 {
+        if self.cr & 0x04 == 0 {
+            return self.ddr;
+        }
+        self.cr &= !(CR_IRQ1 | CR_IRQ2);
+        self.pins()
+    }
+
+    fn write_data(&mut self, val: u8) {
+        if self.cr & 0x04 == 0 {
+            self.ddr = val;
+        } else {
+            self.or = val;
+        }
+    }
+
+    fn write_cr(&mut self, val: u8) {
+        self.cr = (self.cr & 0xc0) | (val & 0x3f);
+        if self.c2_output() {
+            self.cr &= !CR_IRQ2;
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.c2_pulse > 0 {
+            self.c2_pulse -= 1;
+            if self.c2_pulse == 0 {
+                self.c2_out = true;
+            }
+        }
+    }
+
+    fn irq(&self) -> bool {
+        (self.cr & CR_IRQ1 != 0 && self.cr & 0x01 != 0)
+            || (self.cr & CR_IRQ2 != 0
+                && self.cr & 0x08 != 0
+                && !self.c2_output())
+    }
+}
+
+// The 6820/6821 Peripheral Interface Adapter. For keyboards and
+// displays, the host can queue bytes to be strobed into port A with
+// CA1, and, once capture is turned on, collect the bytes written to
+// port B.
+#[derive(Clone, Debug)]
+pub struct Pia {
+    a: Port,
+    b: Port,
+    keys: VecDeque<u8>,
+    capture: bool,
+    output: Vec<u8>,
+}
+
+impl Pia {
+    pub fn new() -> Pia {
+        Pia {
+            a: Port::new(),
+            b: Port::new(),
+            keys: VecDeque::new(),
+            capture: false,
+            output: Vec::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.a = Port::new();
+        self.b = Port::new();
+    }
+
+    pub fn pa(&self) -> u8 {
+        self.a.pins()
+    }
+
+    pub fn pb(&self) -> u8 {
+        self.b.pins()
+    }
+
+    pub fn set_pa(&mut self, val: u8) {
+        self.a.input = val;
+    }
+
+    pub fn set_pb(&mut self, val: u8) {
+        self.b.input = val;
+    }
+
+    pub fn ca2(&self) -> bool {
+        self.a.c2()
+    }
+
+    pub fn cb2(&self) -> bool {
+        self.b.c2()
+    }
+
+    pub fn set_ca1(&mut self, set: bool) {
+        self.a.set_c1(set);
+    }
+
+    pub fn set_ca2(&mut self, set: bool) {
+        self.a.set_c2(set);
+    }
+
+    pub fn set_cb1(&mut self, set: bool) {
+        self.b.set_c1(set);
+    }
+
+    pub fn set_cb2(&mut self, set: bool) {
+        self.b.set_c2(set);
+    }
+
+    pub fn irqa(&self) -> bool {
+        self.a.irq()
+    }
+
+    pub fn irqb(&self) -> bool {
+        self.b.irq()
+    }
+
+    // Puts the byte on port A and pulses CA1 with its active edge.
+    pub fn strobe_a(&mut self, val: u8) {
+        self.a.input = val;
+        let positive = self.a.cr & 0x02 != 0;
+        self.a.set_c1(!positive);
+        self.a.set_c1(positive);
+    }
+
+    // Queues bytes to be strobed into port A, each one once the CPU has
+    // read the previous one.
+    pub fn send_keys(&mut self, keys: &[u8]) {
+        self.keys.extend(keys);
+    }
+
+    pub fn pending_keys(&self) -> usize {
+        self.keys.len()
+    }
+
+    // Keeps the strobed writes to port B for take_output(). They pile
+    // up until taken, so this is off unless something collects them.
+    pub fn set_capture(&mut self, capture: bool) {
+        self.capture = capture;
+        if !capture {
+            self.output.clear();
+        }
+    }
+
+    // Takes the bytes written to port B so far.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+}
+
+impl Default for Pia {
+    fn default() -> Pia {
+        Pia::new()
+    }
+}
+
+impl Device for Pia {
+    fn read(&mut self, reg: u8) -> u8 {
+        match reg & 3 {
+            0 => {
+                let val = self.a.read_data();
+                if self.a.cr & 0x04 != 0 {
+                    self.a.strobe();
+                }
+                val
+            }
+            1 => self.a.cr,
+            2 => self.b.read_data(),
+            3 => self.b.cr,
+            _ => unreachable!(),
+        }
+    }
+
+    // The CB2 strobe comes from writes to port B, rather than reads.
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 3 {
+            0 => self.a.write_data(val),
+            1 => self.a.write_cr(val),
+            2 => {
+                self.b.write_data(val);
+                if self.b.cr & 0x04 != 0 {
+                    self.b.strobe();
+                    if self.capture {
+                        self.output.push(val);
+                    }
+                }
+            }
+            3 => self.b.write_cr(val),
+            _ => unreachable!(),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.a.tick();
+        self.b.tick();
+        if self.a.cr & CR_IRQ1 == 0 {
+            if let Some(key) = self.keys.pop_front() {
+                self.strobe_a(key);
+            }
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irqa() || self.irqb()
+    }
+}
@@ -0,0 +1,457 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{Cia, Cpu, Device, LoadError, Nmos, RegWrite, Status, Sys};
+
+const PAL_HZ: u64 = 985_248;
+const NTSC_HZ: u64 = 1_022_727;
+
+// A PSID or RSID file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Psid {
+    pub rsid: bool,
+    pub version: u16,
+    pub load_addr: u16,
+    pub init_addr: u16,
+    // Zero if the tune installs its own interrupt handler.
+    pub play_addr: u16,
+    pub songs: u16,
+    // Counting from zero, unlike the PSID header.
+    pub start_song: u16,
+    // A bit per song, set if PLAY is driven by the CIA timer instead of
+    // the vertical blank.
+    pub speed: u32,
+    pub name: String,
+    pub author: String,
+    pub released: String,
+    pub ntsc: bool,
+    // The page and length of memory free for the driver, if given.
+    pub start_page: u8,
+    pub page_length: u8,
+    pub data: Vec<u8>,
+}
+
+impl Psid {
+    pub fn parse(file: &[u8]) -> Result<Psid, LoadError> {
+        let rsid = match file.get(..4) {
+            Some(b"PSID") => false,
+            Some(b"RSID") => true,
+            _ => return Err(LoadError::BadMagic),
+        };
+        if file.len() < 0x76 {
+            return Err(LoadError::Truncated);
+        }
+        let version = be16(&file[4..]);
+        let offset = be16(&file[6..]) as usize;
+        if offset < 0x76 || (version >= 2 && offset < 0x7c) {
+            return Err(LoadError::Invalid("bad data offset"));
+        }
+        let mut data = file.get(offset..).ok_or(LoadError::Truncated)?;
+        let mut load_addr = be16(&file[8..]);
+        if load_addr == 0 {
+            if data.len() < 2 {
+                return Err(LoadError::Truncated);
+            }
+            load_addr = u16::from_le_bytes([data[0], data[1]]);
+            data = &data[2..];
+        }
+        if data.is_empty() || load_addr as usize + data.len() > 0x10000 {
+            return Err(LoadError::Invalid("bad load address"));
+        }
+        let flags = if version >= 2 { be16(&file[0x76..]) } else { 0 };
+        if rsid && flags & 2 != 0 {
+            return Err(LoadError::Invalid("BASIC tunes aren't supported"));
+        }
+        let psid = Psid {
+            rsid,
+            version,
+            load_addr,
+            init_addr: match be16(&file[0x0a..]) {
+                0 => load_addr,
+                addr => addr,
+            },
+            play_addr: be16(&file[0x0c..]),
+            songs: be16(&file[0x0e..]),
+            start_song: be16(&file[0x10..]).saturating_sub(1),
+            speed: u32::from_be_bytes([
+                file[0x12], file[0x13], file[0x14], file[0x15],
+            ]),
+            name: text(&file[0x16..0x36]),
+            author: text(&file[0x36..0x56]),
+            released: text(&file[0x56..0x76]),
+            ntsc: flags & 0x0c == 0x08,
+            start_page: if version >= 2 { file[0x78] } else { 0 },
+            page_length: if version >= 2 { file[0x79] } else { 0 },
+            data: data.to_vec(),
+        };
+        if psid.songs == 0 || psid.start_song >= psid.songs {
+            return Err(LoadError::Invalid("bad song count"));
+        }
+        Ok(psid)
+    }
+
+    pub fn clock_hz(&self) -> u64 {
+        if self.ntsc {
+            NTSC_HZ
+        } else {
+            PAL_HZ
+        }
+    }
+
+    fn cia_speed(&self, song: u16) -> bool {
+        !self.rsid && self.speed & (1 << song.min(31)) != 0
+    }
+
+    // The driver goes in the free page given in the header, or else
+    // wherever it won't overwrite the tune.
+    fn driver_addr(&self) -> u16 {
+        if self.start_page != 0 && self.start_page != 0xff {
+            return u16::from(self.start_page) << 8;
+        }
+        let start = self.load_addr as usize;
+        let end = start + self.data.len();
+        if end <= 0x0400 || start >= 0x0420 {
+            0x0400
+        } else {
+            0x033c
+        }
+    }
+
+    // The memory configuration for a PSID, which depends on where INIT
+    // is; RSIDs always start with the usual one.
+    fn bank(&self) -> u8 {
+        match self.init_addr {
+            _ if self.rsid => 0x37,
+            0x0000..=0x9fff => 0x37,
+            0xa000..=0xcfff => 0x36,
+            0xd000..=0xdfff => 0x34,
+            _ => 0x35,
+        }
+    }
+}
+
+fn be16(b: &[u8]) -> u16 {
+    u16::from_be_bytes([b[0], b[1]])
+}
+
+fn text(b: &[u8]) -> String {
+    let end = b.iter().position(|&c| c == 0).unwrap_or(b.len());
+    b[..end].iter().map(|&c| c as char).collect()
+}
+
+// Stand-ins for the parts of the KERNAL that tunes rely on: the IRQ and
+// NMI entry points, the $EA31 and $EA81 exits, and the vectors. The
+// rest is RTS.
+fn kernal() -> Vec<u8> {
+    let mut rom = vec![0x60; 0x2000];
+    let mut put = |addr: u16, code: &[u8]| {
+        let at = (addr - 0xe000) as usize;
+        rom[at..at + code.len()].copy_from_slice(code);
+    };
+    put(0xea31, &[0xad, 0x0d, 0xdc, 0x4c, 0x81, 0xea]);
+    put(0xea81, &[0x68, 0xa8, 0x68, 0xaa, 0x68, 0x40]);
+    put(0xfe43, &[0x6c, 0x18, 0x03]);
+    put(0xfe47, &[0x40]);
+    #[rustfmt::skip]
+    put(0xff48, &[
+        0x48, 0x8a, 0x48, 0x98, 0x48, 0xba, 0xbd, 0x04, 0x01, 0x29, 0x10,
+        0xf0, 0x03, 0x6c, 0x16, 0x03, 0x6c, 0x14, 0x03,
+    ]);
+    put(0xfffa, &[0x43, 0xfe, 0xe2, 0xfc, 0x48, 0xff]);
+    rom
+}
+
+// Just enough of the VIC-II for raster interrupts.
+#[derive(Clone, Debug, Default)]
+struct Raster {
+    lines: u16,
+    line_cycles: u16,
+    line: u16,
+    cycle: u16,
+    compare: u16,
+    flags: u8,
+    enable: u8,
+    regs: Vec<u8>,
+}
+
+impl Raster {
+    fn new(ntsc: bool) -> Raster {
+        let (lines, line_cycles) = if ntsc { (263, 65) } else { (312, 63) };
+        Raster {
+            lines,
+            line_cycles,
+            regs: vec![0; 0x40],
+            ..Default::default()
+        }
+    }
+
+    fn tick(&mut self) {
+        self.cycle += 1;
+        if self.cycle == self.line_cycles {
+            self.cycle = 0;
+            self.line = (self.line + 1) % self.lines;
+            if self.line == self.compare {
+                self.flags |= 1;
+            }
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.flags & self.enable != 0
+    }
+
+    fn read(&self, reg: u8) -> u8 {
+        match reg {
+            0x11 => (self.regs[0x11] & 0x7f) | ((self.line >> 1) as u8 & 0x80),
+            0x12 => self.line as u8,
+            0x19 => self.flags | 0x70 | ((self.irq() as u8) << 7),
+            0x1a => self.enable | 0xf0,
+            _ => self.regs[reg as usize],
+        }
+    }
+
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg {
+            0x11 => {
+                self.compare =
+                    (self.compare & 0xff) | (u16::from(val) & 0x80) << 1
+            }
+            0x12 => self.compare = (self.compare & 0x100) | u16::from(val),
+            0x19 => self.flags &= !val & 0x0f,
+            0x1a => self.enable = val & 0x0f,
+            _ => (),
+        }
+        self.regs[reg as usize] = val;
+    }
+}
+
+// Plays a PSID or RSID tune on a 6510 in a bare C64: RAM, both CIAs,
+// raster interrupts, stand-ins for the ROMs, and a SID whose register
+// writes are logged.
+//
+// A small driver calls INIT with the song in A, then waits with
+// interrupts enabled. PSIDs with a play address have it called from an
+// IRQ, by the CIA 1 timer (set to 60Hz, unless INIT changes it) or the
+// raster interrupt, as the speed bits say. Other tunes are left to the
+// KERNAL's usual 60Hz IRQ, or whatever they set up themselves.
+pub struct PsidPlayer {
+    pub cpu: Nmos,
+    pub sys: PsidSys,
+    psid: Psid,
+}
+
+impl PsidPlayer {
+    pub fn new(psid: &Psid) -> PsidPlayer {
+        PsidPlayer {
+            cpu: Nmos::default(),
+            sys: PsidSys {
+                ram: vec![0; 0x10000],
+                cia1: Cia::new(),
+                cia2: Cia::new(),
+                kernal: kernal(),
+                vic: Raster::new(psid.ntsc),
+                ddr: 0x2f,
+                port: 0x37,
+                log: Vec::new(),
+                cycles: 0,
+            },
+            psid: psid.clone(),
+        }
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.sys.cycles
+    }
+
+    pub fn clock_hz(&self) -> u64 {
+        self.psid.clock_hz()
+    }
+
+    pub fn log(&self) -> &[RegWrite] {
+        &self.sys.log
+    }
+
+    pub fn take_log(&mut self) -> Vec<RegWrite> {
+        std::mem::take(&mut self.sys.log)
+    }
+
+    // Starts a song, counting from zero.
+    pub fn init(&mut self, song: u16) {
+        let psid = &self.psid;
+        let sys = &mut self.sys;
+        for mem in sys.ram.iter_mut() {
+            *mem = 0;
+        }
+        let load = psid.load_addr as usize;
+        sys.ram[load..load + psid.data.len()].copy_from_slice(&psid.data);
+        sys.cia1 = Cia::new();
+        sys.cia2 = Cia::new();
+        sys.vic = Raster::new(psid.ntsc);
+        sys.ddr = 0x2f;
+        sys.port = psid.bank();
+
+        let driver = psid.driver_addr();
+        let irq = driver + 7;
+        let play = driver + 12;
+        let [init_lo, init_hi] = psid.init_addr.to_le_bytes();
+        let [play_lo, play_hi] = psid.play_addr.to_le_bytes();
+        let [loop_lo, loop_hi] = (driver + 4).to_le_bytes();
+        #[rustfmt::skip]
+        let code = [
+            0x20, init_lo, init_hi, // JSR init
+            0x58,                   // CLI
+            0x4c, loop_lo, loop_hi, // JMP *
+            // irq:
+            0x48, 0x8a, 0x48,       // PHA; TXA; PHA
+            0x98, 0x48,             // TYA; PHA
+            // play:
+            0x20, play_lo, play_hi, // JSR play
+            0xad, 0x0d, 0xdc,       // LDA $DC0D
+            0xa9, 0x01,             // LDA #$01
+            0x8d, 0x19, 0xd0,       // STA $D019
+            0x68, 0xa8, 0x68,       // PLA; TAY; PLA
+            0xaa, 0x68, 0x40,       // TAX; PLA; RTI
+        ];
+        let at = driver as usize;
+        sys.ram[at..at + code.len()].copy_from_slice(&code);
+
+        // The KERNAL's vectors, and its 60Hz timer.
+        let own_irq = psid.rsid || psid.play_addr == 0;
+        let vector = if own_irq { 0xea31 } else { play };
+        sys.ram[0x0314..0x0316].copy_from_slice(&vector.to_le_bytes());
+        sys.ram[0x0316..0x0318].copy_from_slice(&0xea81u16.to_le_bytes());
+        sys.ram[0x0318..0x031a].copy_from_slice(&0xfe47u16.to_le_bytes());
+        let latch: u16 = if psid.ntsc { 0x4295 } else { 0x4025 };
+        sys.cia1.write(0x04, latch as u8);
+        sys.cia1.write(0x05, (latch >> 8) as u8);
+        sys.cia1.write(0x0e, 0x11);
+        if own_irq || psid.cia_speed(song) {
+            sys.cia1.write(0x0d, 0x81);
+        } else {
+            sys.vic.write(0x1a, 0x01);
+        }
+        if !own_irq && sys.port & 2 == 0 {
+            sys.ram[0xfffe..].copy_from_slice(&irq.to_le_bytes());
+        }
+
+        self.cpu = Nmos::default();
+        self.cpu.set_pc(driver);
+        self.cpu.set_a(song as u8);
+        self.cpu.set_sp(0xff);
+        self.cpu.set_flag(Status::I, true);
+    }
+
+    // Runs whole instructions until at least the given number of cycles
+    // have passed.
+    pub fn run_cycles(&mut self, cycles: u64) {
+        let end = self.sys.cycles + cycles;
+        while self.sys.cycles < end {
+            if self.cpu.halted() {
+                self.sys.tick();
+            } else {
+                self.cpu.run_instruction(&mut self.sys);
+            }
+        }
+    }
+}
+
+pub struct PsidSys {
+    pub ram: Vec<u8>,
+    pub cia1: Cia,
+    pub cia2: Cia,
+    kernal: Vec<u8>,
+    vic: Raster,
+    ddr: u8,
+    port: u8,
+    log: Vec<RegWrite>,
+    cycles: u64,
+}
+
+impl PsidSys {
+    // The LORAM, HIRAM and CHAREN lines, which are pulled up when not
+    // driven.
+    fn config(&self) -> u8 {
+        (self.port | !self.ddr) & 7
+    }
+
+    fn io(&self) -> bool {
+        self.config() & 3 != 0 && self.config() & 4 != 0
+    }
+
+    fn tick(&mut self) {
+        self.cia1.tick();
+        self.cia2.tick();
+        self.vic.tick();
+        self.cycles += 1;
+    }
+}
+
+// The SID reads as zero, and the character ROM is missing.
+impl Sys for PsidSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let config = self.config();
+        let val = match addr {
+            0x0000 => self.ddr,
+            0x0001 => (self.port & self.ddr) | (!self.ddr & 0xdf),
+            0xa000..=0xbfff if config & 3 == 3 => 0x60,
+            0xd000..=0xdfff if self.io() => match addr {
+                0xd000..=0xd3ff => self.vic.read(addr as u8 & 0x3f),
+                0xd800..=0xdbff => self.ram[addr as usize] | 0xf0,
+                0xdc00..=0xdcff => self.cia1.read(addr as u8),
+                0xdd00..=0xddff => self.cia2.read(addr as u8),
+                _ => 0,
+            },
+            0xd000..=0xdfff if config & 3 != 0 => 0,
+            0xe000..=0xffff if config & 2 != 0 => {
+                self.kernal[(addr - 0xe000) as usize]
+            }
+            _ => self.ram[addr as usize],
+        };
+        self.tick();
+        Some(val)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        match addr {
+            0x0000 => self.ddr = val,
+            0x0001 => self.port = val,
+            0xd000..=0xdfff if self.io() => match addr {
+                0xd000..=0xd3ff => self.vic.write(addr as u8 & 0x3f, val),
+                0xd400..=0xd7ff if addr & 0x1f <= 0x1c => {
+                    self.log.push(RegWrite {
+                        cycle: self.cycles,
+                        addr: 0xd400 | (addr & 0x1f),
+                        val,
+                    })
+                }
+                0xd800..=0xdbff => self.ram[addr as usize] = val & 0x0f,
+                0xdc00..=0xdcff => self.cia1.write(addr as u8, val),
+                0xdd00..=0xddff => self.cia2.write(addr as u8, val),
+                _ => (),
+            },
+            _ => self.ram[addr as usize] = val,
+        }
+        self.tick();
+        Some(())
+    }
+
+    #[inline]
+    fn poll_nmi(&mut self) -> bool {
+        self.cia2.poll_nmi()
+    }
+
+    #[inline]
+    fn peek_nmi(&self) -> bool {
+        self.cia2.peek_nmi()
+    }
+
+    #[inline]
+    fn irq(&self) -> bool {
+        self.cia1.irq() || self.vic.irq()
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::Device;
+
+const FLAG_TIMER: u8 = 0x80;
+const FLAG_PA7: u8 = 0x40;
+
+// The 6532 RAM-I/O-Timer. The registers are selected by A0-A4 with RS
+// high; the RAM, selected by RS low, is left for the Sys to map.
+#[derive(Clone, Debug)]
+pub struct Riot {
+    pub ram: [u8; 128],
+    ora: u8,
+    orb: u8,
+    ddra: u8,
+    ddrb: u8,
+    pa_in: u8,
+    pb_in: u8,
+    timer: u8,
+    interval: u16,
+    prescale: u16,
+    timer_load: bool,
+    timer_irq: bool,
+    pa7_irq: bool,
+    pa7_positive: bool,
+    pa7: bool,
+    flags: u8,
+}
+
+impl Riot {
+    pub fn new() -> Riot {
+        Riot {
+            ram: [0; 128],
+            ora: 0,
+            orb: 0,
+            ddra: 0,
+            ddrb: 0,
+            pa_in: 0xff,
+            pb_in: 0xff,
+            timer: 0,
+            interval: 1024,
+            prescale: 1024,
+            timer_load: false,
+            timer_irq: false,
+            pa7_irq: false,
+            pa7_positive: false,
+            pa7: true,
+            flags: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.ora = 0;
+        self.orb = 0;
+        self.ddra = 0;
+        self.ddrb = 0;
+        self.timer_irq = false;
+        self.pa7_irq = false;
+        self.pa7_positive = false;
+        self.flags = 0;
+        self.update_pa7();
+    }
+
+    pub fn pa(&self) -> u8 {
+        (self.ora & self.ddra) | (self.pa_in & !self.ddra)
+    }
+
+    pub fn pb(&self) -> u8 {
+        (self.orb & self.ddrb) | (self.pb_in & !self.ddrb)
+    }
+
+    pub fn set_pa(&mut self, val: u8) {
+        self.pa_in = val;
+        self.update_pa7();
+    }
+
+    pub fn set_pb(&mut self, val: u8) {
+        self.pb_in = val;
+    }
+
+    fn update_pa7(&mut self) {
+        let pa7 = (self.pa() & 0x80) != 0;
+        if pa7 != self.pa7 && pa7 == self.pa7_positive {
+            self.flags |= FLAG_PA7;
+        }
+        self.pa7 = pa7;
+    }
+}
+
+impl Default for Riot {
+    fn default() -> Riot {
+        Riot::new()
+    }
+}
+
+impl Device for Riot {
+    fn read(&mut self, reg: u8) -> u8 {
+        if reg & 0x04 == 0 {
+            return match reg & 3 {
+                0 => self.pa(),
+                1 => self.ddra,
+                2 => self.pb(),
+                3 => self.ddrb,
+                _ => unreachable!(),
+            };
+        }
+        if reg & 0x01 == 0 {
+            self.timer_irq = reg & 0x08 != 0;
+            self.flags &= !FLAG_TIMER;
+            self.timer
+        } else {
+            let flags = self.flags;
+            self.flags &= !FLAG_PA7;
+            flags
+        }
+    }
+
+    fn write(&mut self, reg: u8, val: u8) {
+        if reg & 0x04 == 0 {
+            match reg & 3 {
+                0 => self.ora = val,
+                1 => self.ddra = val,
+                2 => self.orb = val,
+                3 => self.ddrb = val,
+                _ => unreachable!(),
+            }
+            self.update_pa7();
+        } else if reg & 0x10 != 0 {
+            self.prescale = match reg & 3 {
+                0 => 1,
+                1 => 8,
+                2 => 64,
+                3 => 1024,
+                _ => unreachable!(),
+            };
+            self.timer = val;
+            // The first decrement comes on the cycle after the write.
+            self.interval = 1;
+            self.timer_load = true;
+            self.timer_irq = reg & 0x08 != 0;
+            self.flags &= !FLAG_TIMER;
+        } else {
+            self.pa7_irq = reg & 0x02 != 0;
+            self.pa7_positive = reg & 0x01 != 0;
+        }
+    }
+
+    // After the timer passes zero it counts down every cycle, until it
+    // is written again.
+    fn tick(&mut self) {
+        if self.timer_load {
+            self.timer_load = false;
+            return;
+        }
+        self.interval -= 1;
+        if self.interval != 0 {
+            return;
+        }
+        self.timer = self.timer.wrapping_sub(1);
+        if self.timer == 0xff && self.flags & FLAG_TIMER == 0 {
+            self.flags |= FLAG_TIMER;
+            self.prescale = 1;
+        }
+        self.interval = self.prescale;
+    }
+
+    fn irq(&self) -> bool {
+        (self.timer_irq && self.flags & FLAG_TIMER != 0)
+            || (self.pa7_irq && self.flags & FLAG_PA7 != 0)
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{Cpu, CycleKind, NmiLength, Sys};
+
+pub trait Task {
+    // Runs the CPU for exactly one cycle.
+    fn run_cycle(&mut self);
+
+    fn cycles(&self) -> u64;
+
+    // The clock rate, as a fraction of cycles per unit of time.
+    fn rate(&self) -> (u64, u64);
+}
+
+impl<T: Task + ?Sized> Task for &mut T {
+    fn run_cycle(&mut self) {
+        (**self).run_cycle()
+    }
+
+    fn cycles(&self) -> u64 {
+        (**self).cycles()
+    }
+
+    fn rate(&self) -> (u64, u64) {
+        (**self).rate()
+    }
+}
+
+// A CPU and the bus it is attached to, clocked at num/den cycles per
+// unit of time.
+pub struct Clocked<C: Cpu, S: Sys> {
+    pub cpu: C,
+    pub sys: S,
+    num: u64,
+    den: u64,
+    cycles: u64,
+}
+
+impl<C: Cpu, S: Sys> Clocked<C, S> {
+    pub fn new(cpu: C, sys: S, num: u64, den: u64) -> Clocked<C, S> {
+        assert!(num != 0 && den != 0);
+        Clocked {
+            cpu,
+            sys,
+            num,
+            den,
+            cycles: 0,
+        }
+    }
+}
+
+impl<C: Cpu, S: Sys> Task for Clocked<C, S> {
+    // A cycle where the Sys pauses, or the CPU is halted, still takes
+    // up time, as if RDY had been held low.
+    fn run_cycle(&mut self) {
+        if !self.cpu.halted() {
+            let mut sys = OneCycle {
+                sys: &mut self.sys,
+                done: false,
+            };
+            self.cpu.run_instruction(&mut sys);
+        }
+        self.cycles += 1;
+    }
+
+    fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    fn rate(&self) -> (u64, u64) {
+        (self.num, self.den)
+    }
+}
+
+// Runs several tasks in order of the time of their next cycle, so that
+// accesses to anything they share happen in the order they would on
+// real hardware. Tasks whose cycles fall at the same time run in the
+// order they were added.
+#[derive(Default)]
+pub struct Scheduler<'a> {
+    tasks: Vec<Box<dyn Task + 'a>>,
+}
+
+impl<'a> Scheduler<'a> {
+    pub fn new() -> Scheduler<'a> {
+        Default::default()
+    }
+
+    // Returns the id of the task, for use with run_cycles().
+    pub fn add<T: Task + 'a>(&mut self, task: T) -> usize {
+        self.tasks.push(Box::new(task));
+        self.tasks.len() - 1
+    }
+
+    // Runs one cycle of the task that is furthest behind, and returns
+    // its id.
+    pub fn step(&mut self) -> usize {
+        let next = self.next();
+        self.tasks[next].run_cycle();
+        next
+    }
+
+    // Runs the given task for the given number of cycles, and the other
+    // tasks up to the same point in time.
+    pub fn run_cycles(&mut self, id: usize, cycles: u64) {
+        let end = self.tasks[id].cycles() + cycles;
+        loop {
+            let next = self.next();
+            if self.tasks[id].cycles() >= end
+                && !earlier(&*self.tasks[next], &*self.tasks[id])
+            {
+                break;
+            }
+            self.tasks[next].run_cycle();
+        }
+    }
+
+    fn next(&self) -> usize {
+        assert!(!self.tasks.is_empty(), "no tasks to schedule");
+        let mut next = 0;
+        for id in 1..self.tasks.len() {
+            if earlier(&*self.tasks[id], &*self.tasks[next]) {
+                next = id;
+            }
+        }
+        next
+    }
+}
+
+// Compares cycles/rate for both tasks without rounding.
+fn earlier(a: &dyn Task, b: &dyn Task) -> bool {
+    let (a_num, a_den) = a.rate();
+    let (b_num, b_den) = b.rate();
+    let a_time = a.cycles() as u128 * a_den as u128 * b_num as u128;
+    let b_time = b.cycles() as u128 * b_den as u128 * a_num as u128;
+    a_time < b_time
+}
+
+// Pauses the CPU at the start of its second bus access.
+struct OneCycle<'s, S: Sys> {
+    sys: &'s mut S,
+    done: bool,
+}
+
+impl<'s, S: Sys> Sys for OneCycle<'s, S> {
+    #[inline]
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.read_kind(addr, CycleKind::Data)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.write_kind(addr, val, CycleKind::Data)
+    }
+
+    #[inline]
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        if self.done {
+            return None;
+        }
+        let val = self.sys.read_kind(addr, kind)?;
+        self.done = true;
+        Some(val)
+    }
+
+    #[inline]
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
+        if self.done {
+            return None;
+        }
+        self.sys.write_kind(addr, val, kind)?;
+        self.done = true;
+        Some(())
+    }
+
+    #[inline]
+    fn set_sync(&mut self, set: bool) {
+        self.sys.set_sync(set);
+    }
+
+    #[inline]
+    fn set_vp(&mut self, set: bool) {
+        self.sys.set_vp(set);
+    }
+
+    #[inline]
+    fn set_ml(&mut self, set: bool) {
+        self.sys.set_ml(set);
+    }
+
+    #[inline]
+    fn poll_nmi(&mut self) -> bool {
+        self.sys.poll_nmi()
+    }
+
+    #[inline]
+    fn peek_nmi(&self) -> bool {
+        self.sys.peek_nmi()
+    }
+
+    #[inline]
+    fn nmi_length(&self) -> NmiLength {
+        self.sys.nmi_length()
+    }
+
+    #[inline]
+    fn irq(&self) -> bool {
+        self.sys.irq()
+    }
+}
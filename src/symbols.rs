@@ -0,0 +1,302 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::LoadError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub addr: u16,
+    // Zero if unknown.
+    pub size: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLine {
+    pub file: String,
+    pub line: u32,
+}
+
+// Names for addresses, and the source lines that produced them, read
+// from assembler and emulator symbol files. Several files can be
+// merged; where an address has more than one name, the first one added
+// is the one shown.
+#[derive(Clone, Debug, Default)]
+pub struct Symbols {
+    syms: Vec<Symbol>,
+    by_addr: BTreeMap<u16, Vec<usize>>,
+    by_name: HashMap<String, usize>,
+    lines: BTreeMap<u16, (u16, SourceLine)>,
+}
+
+impl Symbols {
+    pub fn new() -> Symbols {
+        Default::default()
+    }
+
+    pub fn add(&mut self, name: &str, addr: u16, size: u16) {
+        let n = self.syms.len();
+        self.syms.push(Symbol {
+            name: name.to_string(),
+            addr,
+            size,
+        });
+        self.by_addr.entry(addr).or_default().push(n);
+        self.by_name.entry(name.to_string()).or_insert(n);
+    }
+
+    // Marks the size bytes at addr as coming from a source line, unless
+    // the first of them already has one.
+    pub fn add_line(&mut self, addr: u16, size: u16, file: &str, line: u32) {
+        let source = SourceLine {
+            file: file.to_string(),
+            line,
+        };
+        self.lines.entry(addr).or_insert((size.max(1), source));
+    }
+
+    pub fn extend(&mut self, other: Symbols) {
+        for sym in other.syms {
+            self.add(&sym.name, sym.addr, sym.size);
+        }
+        for (addr, (size, source)) in other.lines {
+            self.add_line(addr, size, &source.file, source.line);
+        }
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.syms
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<u16> {
+        self.by_name.get(name).map(|&n| self.syms[n].addr)
+    }
+
+    // The first name given to exactly this address.
+    pub fn name(&self, addr: u16) -> Option<&str> {
+        let n = *self.by_addr.get(&addr)?.first()?;
+        Some(&self.syms[n].name)
+    }
+
+    // The address as a name, or as an offset into the closest symbol
+    // below it whose size covers it, or else as hex.
+    pub fn label(&self, addr: u16) -> String {
+        if let Some(name) = self.name(addr) {
+            return name.to_string();
+        }
+        let inside = self.by_addr.range(..addr).rev().find_map(|(_, syms)| {
+            syms.iter()
+                .map(|&n| &self.syms[n])
+                .find(|sym| u32::from(addr - sym.addr) < u32::from(sym.size))
+        });
+        match inside {
+            Some(sym) => format!("{}+{}", sym.name, addr - sym.addr),
+            None => format!("${:04x}", addr),
+        }
+    }
+
+    pub fn source(&self, addr: u16) -> Option<&SourceLine> {
+        let (&start, (size, source)) = self.lines.range(..=addr).next_back()?;
+        if u32::from(addr - start) < u32::from(*size) {
+            Some(source)
+        } else {
+            None
+        }
+    }
+
+    // A cc65 debug info file, from ld65 --dbgfile: the symbols with
+    // values, and the lines of source each span of bytes came from.
+    // Lines inside macros are skipped in favor of the lines that
+    // invoked them.
+    pub fn parse_dbg(text: &str) -> Result<Symbols, LoadError> {
+        if !text.trim_start().starts_with("version") {
+            return Err(LoadError::BadMagic);
+        }
+        let mut records = Vec::new();
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let (kind, fields) = line
+                .split_once(|c: char| c.is_ascii_whitespace())
+                .ok_or(LoadError::Invalid("bad dbg record"))?;
+            records.push((kind, dbg_fields(fields.trim())?));
+        }
+
+        let mut files = HashMap::new();
+        let mut segs = HashMap::new();
+        let mut spans = HashMap::new();
+        for (kind, fields) in &records {
+            let id = || dbg_num(fields, "id");
+            match *kind {
+                "file" => {
+                    files.insert(id()?, dbg_str(fields, "name")?);
+                }
+                "seg" => {
+                    segs.insert(id()?, dbg_num(fields, "start")?);
+                }
+                "span" => {
+                    let seg = dbg_num(fields, "seg")?;
+                    let start = dbg_num(fields, "start")?;
+                    let size = dbg_num(fields, "size")?;
+                    spans.insert(id()?, (seg, start, size));
+                }
+                _ => (),
+            }
+        }
+
+        let mut syms = Symbols::new();
+        let mut equates = Vec::new();
+        for (kind, fields) in &records {
+            match *kind {
+                "sym" => {
+                    let val = match fields.get("val") {
+                        Some(val) => parse_num(val)?,
+                        None => continue,
+                    };
+                    if val > 0xffff {
+                        continue;
+                    }
+                    let name = dbg_str(fields, "name")?;
+                    let size = dbg_num(fields, "size").unwrap_or(0);
+                    // Labels name addresses before equates do.
+                    if fields.get("type").map(String::as_str) == Some("lab") {
+                        syms.add(name, val as u16, size as u16);
+                    } else {
+                        equates.push((name, val as u16, size as u16));
+                    }
+                }
+                "line" => {
+                    if fields.get("type").map(String::as_str) == Some("2") {
+                        continue;
+                    }
+                    let span_list = match fields.get("span") {
+                        Some(list) => list,
+                        None => continue,
+                    };
+                    let file = files
+                        .get(&dbg_num(fields, "file")?)
+                        .ok_or(LoadError::Invalid("unknown dbg file"))?;
+                    let line = dbg_num(fields, "line")?;
+                    for span in span_list.split('+') {
+                        let span = parse_num(span)?;
+                        let &(seg, start, size) = spans
+                            .get(&span)
+                            .ok_or(LoadError::Invalid("unknown dbg span"))?;
+                        let base = *segs
+                            .get(&seg)
+                            .ok_or(LoadError::Invalid("unknown dbg seg"))?;
+                        let addr = (base + start) as u16;
+                        syms.add_line(addr, size as u16, file, line);
+                    }
+                }
+                _ => (),
+            }
+        }
+        for (name, addr, size) in equates {
+            syms.add(name, addr, size);
+        }
+        Ok(syms)
+    }
+
+    // A VICE monitor label file, as written by its "sl" command or by
+    // ld65 -Ln: "al C:1234 .name" lines.
+    pub fn parse_vice(text: &str) -> Result<Symbols, LoadError> {
+        let mut syms = Symbols::new();
+        for line in text.lines() {
+            let words: Vec<_> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => (),
+                ["al", addr, name] => {
+                    let addr = addr.strip_prefix("C:").unwrap_or(addr);
+                    let addr = u16::from_str_radix(addr, 16)
+                        .map_err(|_| LoadError::Invalid("bad address"))?;
+                    syms.add(name.trim_start_matches('.'), addr, 0);
+                }
+                _ => return Err(LoadError::Invalid("not a VICE label")),
+            }
+        }
+        Ok(syms)
+    }
+
+    // "name = $1234" lines, as many assemblers list them, and as NESASM
+    // writes its .fns files. Addresses may be $hex, 0xhex or decimal;
+    // semicolons start comments.
+    pub fn parse_labels(text: &str) -> Result<Symbols, LoadError> {
+        let mut syms = Symbols::new();
+        for line in text.lines() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, addr) = line
+                .split_once('=')
+                .ok_or(LoadError::Invalid("not a label"))?;
+            let addr = parse_num(addr.trim())?;
+            if addr > 0xffff {
+                return Err(LoadError::Invalid("bad address"));
+            }
+            syms.add(name.trim(), addr as u16, 0);
+        }
+        Ok(syms)
+    }
+}
+
+// The comma-separated key=value fields of a dbg record, with quotes
+// taken off strings.
+fn dbg_fields(text: &str) -> Result<HashMap<&str, String>, LoadError> {
+    let mut fields = HashMap::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (key, after) = rest
+            .split_once('=')
+            .ok_or(LoadError::Invalid("bad dbg record"))?;
+        let (val, after) = if let Some(quoted) = after.strip_prefix('"') {
+            let end = quoted
+                .find('"')
+                .ok_or(LoadError::Invalid("bad dbg record"))?;
+            (quoted[..end].to_string(), &quoted[end + 1..])
+        } else {
+            let end = after.find(',').unwrap_or(after.len());
+            (after[..end].to_string(), &after[end..])
+        };
+        fields.insert(key, val);
+        rest = after.strip_prefix(',').unwrap_or(after);
+    }
+    Ok(fields)
+}
+
+fn dbg_num(
+    fields: &HashMap<&str, String>,
+    key: &str,
+) -> Result<u32, LoadError> {
+    let val = fields
+        .get(key)
+        .ok_or(LoadError::Invalid("missing dbg field"))?;
+    parse_num(val)
+}
+
+fn dbg_str<'a>(
+    fields: &'a HashMap<&str, String>,
+    key: &str,
+) -> Result<&'a str, LoadError> {
+    fields
+        .get(key)
+        .map(String::as_str)
+        .ok_or(LoadError::Invalid("missing dbg field"))
+}
+
+fn parse_num(text: &str) -> Result<u32, LoadError> {
+    let bad = LoadError::Invalid("bad number");
+    if let Some(hex) = text.strip_prefix('$') {
+        u32::from_str_radix(hex, 16).map_err(|_| bad)
+    } else if let Some(hex) = text.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16).map_err(|_| bad)
+    } else {
+        text.parse().map_err(|_| bad)
+    }
+}
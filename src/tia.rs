@@ -0,0 +1,534 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::Device;
+
+const LINE_CLOCKS: u16 = 228;
+const HBLANK: u16 = 68;
+const WIDTH: usize = 160;
+
+// A frame that never sees VSYNC is cut off after this many lines.
+const MAX_LINES: usize = 512;
+
+// Player and missile copies for each NUSIZ setting, as offsets from
+// the first copy, and the stretch applied to players.
+const COPIES: [&[u8]; 8] = [
+    &[0],
+    &[0, 16],
+    &[0, 32],
+    &[0, 16, 32],
+    &[0, 64],
+    &[0],
+    &[0, 32, 64],
+    &[0],
+];
+const STRETCH: [u8; 8] = [1, 1, 1, 1, 1, 2, 1, 4];
+
+// Collision latches, as (register, bit).
+const CX_M0P1: (usize, u8) = (0, 0x80);
+const CX_M0P0: (usize, u8) = (0, 0x40);
+const CX_M1P0: (usize, u8) = (1, 0x80);
+const CX_M1P1: (usize, u8) = (1, 0x40);
+const CX_P0PF: (usize, u8) = (2, 0x80);
+const CX_P0BL: (usize, u8) = (2, 0x40);
+const CX_P1PF: (usize, u8) = (3, 0x80);
+const CX_P1BL: (usize, u8) = (3, 0x40);
+const CX_M0PF: (usize, u8) = (4, 0x80);
+const CX_M0BL: (usize, u8) = (4, 0x40);
+const CX_M1PF: (usize, u8) = (5, 0x80);
+const CX_M1BL: (usize, u8) = (5, 0x40);
+const CX_BLPF: (usize, u8) = (6, 0x80);
+const CX_P0P1: (usize, u8) = (7, 0x80);
+const CX_M0M1: (usize, u8) = (7, 0x40);
+
+// A finished frame, as one TIA color value per pixel.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Frame {
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    // A binary (P6) PPM image, with the NTSC palette.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm =
+            format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for &color in &self.pixels {
+            ppm.extend_from_slice(&ntsc_rgb(color));
+        }
+        ppm
+    }
+}
+
+// An approximation of the NTSC palette: the high nibble picks the hue
+// (0 is grey, from black to white), and bits 1-3 the luminance.
+fn ntsc_rgb(color: u8) -> [u8; 3] {
+    let hue = color >> 4;
+    let lum = f64::from((color >> 1) & 7);
+    let (y, i, q) = if hue == 0 {
+        (lum / 7.0, 0.0, 0.0)
+    } else {
+        let angle = (f64::from(hue - 1) * 24.0 + 180.0).to_radians();
+        (0.1 + lum * 0.12, 0.2 * angle.cos(), 0.2 * angle.sin())
+    };
+    let rgb = [
+        y + 0.956 * i + 0.621 * q,
+        y - 0.272 * i - 0.647 * q,
+        y - 1.106 * i + 1.703 * q,
+    ];
+    let mut out = [0; 3];
+    for (out, val) in out.iter_mut().zip(rgb.iter()) {
+        *out = (val.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+    out
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Object {
+    pos: u8,
+    motion: u8,
+}
+
+impl Object {
+    fn reset(&mut self, clock: u16, offset: u8) {
+        self.pos = if clock < HBLANK {
+            offset - 2
+        } else {
+            ((clock - HBLANK) as u8 + offset) % WIDTH as u8
+        };
+    }
+
+    fn hmove(&mut self) {
+        let motion = (self.motion as i8 >> 4) as i16;
+        let pos = self.pos as i16 - motion;
+        self.pos = pos.rem_euclid(WIDTH as i16) as u8;
+    }
+
+    // How far the pixel is into the object, if it is within `width`
+    // pixels of one of the copies.
+    fn offset(&self, x: u8, copies: &[u8], width: u8) -> Option<u8> {
+        copies.iter().find_map(|&copy| {
+            let start = (self.pos as u16 + copy as u16) % WIDTH as u16;
+            let d = (x as u16 + WIDTH as u16 - start) % WIDTH as u16;
+            if d < width as u16 {
+                Some(d as u8)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+// The Television Interface Adaptor's video and input sections. The
+// audio registers are accepted and ignored.
+//
+// Writes take effect at the color clock where the CPU cycle starts,
+// and each tick() is one CPU cycle, or three color clocks.
+#[derive(Clone, Debug)]
+pub struct Tia {
+    clock: u16,
+    line: usize,
+    vsync: bool,
+    frame_due: bool,
+    vblank: u8,
+    wsync: bool,
+    hmove_blank: bool,
+    nusiz: [u8; 2],
+    colup: [u8; 2],
+    colupf: u8,
+    colubk: u8,
+    ctrlpf: u8,
+    refp: [bool; 2],
+    pf: [u8; 3],
+    grp: [u8; 2],
+    grp_old: [u8; 2],
+    enam: [bool; 2],
+    enabl: bool,
+    enabl_old: bool,
+    vdelp: [bool; 2],
+    vdelbl: bool,
+    resmp: [bool; 2],
+    players: [Object; 2],
+    missiles: [Object; 2],
+    ball: Object,
+    collisions: [u8; 8],
+    fire: [bool; 2],
+    fire_latch: [bool; 2],
+    paddles: [bool; 4],
+    pixels: Vec<u8>,
+    frame: Frame,
+    frames: u64,
+}
+
+impl Tia {
+    pub fn new() -> Tia {
+        Tia {
+            clock: 0,
+            line: 0,
+            vsync: false,
+            frame_due: false,
+            vblank: 0,
+            wsync: false,
+            hmove_blank: false,
+            nusiz: [0; 2],
+            colup: [0; 2],
+            colupf: 0,
+            colubk: 0,
+            ctrlpf: 0,
+            refp: [false; 2],
+            pf: [0; 3],
+            grp: [0; 2],
+            grp_old: [0; 2],
+            enam: [false; 2],
+            enabl: false,
+            enabl_old: false,
+            vdelp: [false; 2],
+            vdelbl: false,
+            resmp: [false; 2],
+            players: [Object::default(); 2],
+            missiles: [Object::default(); 2],
+            ball: Object::default(),
+            collisions: [0; 8],
+            fire: [false; 2],
+            fire_latch: [false; 2],
+            paddles: [false; 4],
+            pixels: vec![0; WIDTH],
+            frame: Frame::default(),
+            frames: 0,
+        }
+    }
+
+    // True from a write to WSYNC until the start of the next line; the
+    // Sys holds RDY low, pausing the CPU on its next read.
+    pub fn wsync(&self) -> bool {
+        self.wsync
+    }
+
+    // The last complete frame, which ends at the start of the line
+    // after VSYNC is turned on.
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frames
+    }
+
+    // The color clock within the line, and the line within the frame.
+    pub fn beam(&self) -> (u16, usize) {
+        (self.clock, self.line)
+    }
+
+    pub fn set_fire(&mut self, player: usize, pressed: bool) {
+        self.fire[player] = pressed;
+        if pressed {
+            self.fire_latch[player] = true;
+        }
+    }
+
+    // Whether each paddle's capacitor has charged past the threshold.
+    pub fn set_paddle(&mut self, paddle: usize, charged: bool) {
+        self.paddles[paddle] = charged;
+    }
+
+    fn player_pixel(&self, n: usize, x: u8) -> bool {
+        let size = self.nusiz[n] & 7;
+        let stretch = STRETCH[size as usize];
+        let obj = &self.players[n];
+        let d = match obj.offset(x, COPIES[size as usize], 8 * stretch) {
+            Some(d) => d / stretch,
+            None => return false,
+        };
+        let grp = if self.vdelp[n] {
+            self.grp_old[n]
+        } else {
+            self.grp[n]
+        };
+        let bit = if self.refp[n] { d } else { 7 - d };
+        grp & (1 << bit) != 0
+    }
+
+    fn missile_pixel(&self, n: usize, x: u8) -> bool {
+        if !self.enam[n] || self.resmp[n] {
+            return false;
+        }
+        let copies = COPIES[(self.nusiz[n] & 7) as usize];
+        let width = 1 << ((self.nusiz[n] >> 4) & 3);
+        self.missiles[n].offset(x, copies, width).is_some()
+    }
+
+    fn ball_pixel(&self, x: u8) -> bool {
+        let enabled = if self.vdelbl {
+            self.enabl_old
+        } else {
+            self.enabl
+        };
+        let width = 1 << ((self.ctrlpf >> 4) & 3);
+        enabled && self.ball.offset(x, &[0], width).is_some()
+    }
+
+    // The 20 playfield bits cover the left half, four pixels each, and
+    // are repeated or reflected on the right.
+    fn playfield_pixel(&self, x: u8) -> bool {
+        let mut bit = (x as usize % 80) / 4;
+        if x >= 80 && self.ctrlpf & 0x01 != 0 {
+            bit = 19 - bit;
+        }
+        match bit {
+            0..=3 => self.pf[0] & (0x10 << bit) != 0,
+            4..=11 => self.pf[1] & (0x80 >> (bit - 4)) != 0,
+            _ => self.pf[2] & (1 << (bit - 12)) != 0,
+        }
+    }
+
+    fn collide(&mut self, (reg, bit): (usize, u8), hit: bool) {
+        if hit {
+            self.collisions[reg] |= bit;
+        }
+    }
+
+    fn draw(&mut self, x: u8) -> u8 {
+        let p0 = self.player_pixel(0, x);
+        let p1 = self.player_pixel(1, x);
+        let m0 = self.missile_pixel(0, x);
+        let m1 = self.missile_pixel(1, x);
+        let bl = self.ball_pixel(x);
+        let pf = self.playfield_pixel(x);
+
+        self.collide(CX_M0P1, m0 && p1);
+        self.collide(CX_M0P0, m0 && p0);
+        self.collide(CX_M1P0, m1 && p0);
+        self.collide(CX_M1P1, m1 && p1);
+        self.collide(CX_P0PF, p0 && pf);
+        self.collide(CX_P0BL, p0 && bl);
+        self.collide(CX_P1PF, p1 && pf);
+        self.collide(CX_P1BL, p1 && bl);
+        self.collide(CX_M0PF, m0 && pf);
+        self.collide(CX_M0BL, m0 && bl);
+        self.collide(CX_M1PF, m1 && pf);
+        self.collide(CX_M1BL, m1 && bl);
+        self.collide(CX_BLPF, bl && pf);
+        self.collide(CX_P0P1, p0 && p1);
+        self.collide(CX_M0M1, m0 && m1);
+
+        if self.vblank & 0x02 != 0 || (self.hmove_blank && x < 8) {
+            return 0;
+        }
+        // In score mode, each half of the playfield takes the color of
+        // the player on that side.
+        let pf_color = if self.ctrlpf & 0x02 != 0 {
+            self.colup[(x >= 80) as usize]
+        } else {
+            self.colupf
+        };
+        let priority = self.ctrlpf & 0x04 != 0;
+        let color = if priority && (pf || bl) {
+            if pf {
+                pf_color
+            } else {
+                self.colupf
+            }
+        } else if p0 || m0 {
+            self.colup[0]
+        } else if p1 || m1 {
+            self.colup[1]
+        } else if bl {
+            self.colupf
+        } else if pf {
+            pf_color
+        } else {
+            self.colubk
+        };
+        color & 0xfe
+    }
+
+    fn clock(&mut self) {
+        if self.clock >= HBLANK {
+            let x = (self.clock - HBLANK) as u8;
+            let color = self.draw(x);
+            let idx = self.line * WIDTH + x as usize;
+            self.pixels[idx] = color;
+        }
+        self.clock += 1;
+        if self.clock == LINE_CLOCKS {
+            self.clock = 0;
+            self.wsync = false;
+            self.hmove_blank = false;
+            self.next_line();
+        }
+    }
+
+    fn next_line(&mut self) {
+        self.line += 1;
+        if self.frame_due || self.line == MAX_LINES {
+            self.frame_due = false;
+            self.pixels.truncate(self.line * WIDTH);
+            self.frame = Frame {
+                width: WIDTH,
+                height: self.line,
+                pixels: std::mem::take(&mut self.pixels),
+            };
+            self.frames += 1;
+            self.line = 0;
+        }
+        self.pixels.resize((self.line + 1) * WIDTH, 0);
+    }
+
+    fn hmove(&mut self) {
+        if self.clock < HBLANK {
+            self.hmove_blank = true;
+        }
+        for obj in self
+            .players
+            .iter_mut()
+            .chain(self.missiles.iter_mut())
+            .chain(std::iter::once(&mut self.ball))
+        {
+            obj.hmove();
+        }
+    }
+
+    // The missile is centered on its player when RESMP is cleared.
+    fn lock_missile(&mut self, n: usize, lock: bool) {
+        if self.resmp[n] && !lock {
+            let center =
+                [3, 3, 3, 3, 3, 6, 3, 10][(self.nusiz[n] & 7) as usize];
+            self.missiles[n].pos = (self.players[n].pos + center) % WIDTH as u8;
+        }
+        self.resmp[n] = lock;
+    }
+}
+
+impl Default for Tia {
+    fn default() -> Tia {
+        Tia::new()
+    }
+}
+
+impl Device for Tia {
+    // Only bits 6 and 7 are driven; the Sys supplies the rest.
+    fn read(&mut self, reg: u8) -> u8 {
+        match reg & 0x0f {
+            reg @ 0x00..=0x07 => self.collisions[reg as usize],
+            reg @ 0x08..=0x0b => {
+                let dumped = self.vblank & 0x80 != 0;
+                if !dumped && self.paddles[(reg - 8) as usize] {
+                    0x80
+                } else {
+                    0x00
+                }
+            }
+            reg @ 0x0c..=0x0d => {
+                let n = (reg - 0x0c) as usize;
+                let pressed = if self.vblank & 0x40 != 0 {
+                    self.fire_latch[n]
+                } else {
+                    self.fire[n]
+                };
+                if pressed {
+                    0x00
+                } else {
+                    0x80
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 0x3f {
+            0x00 => {
+                let vsync = val & 0x02 != 0;
+                if vsync && !self.vsync {
+                    self.frame_due = true;
+                }
+                self.vsync = vsync;
+            }
+            0x01 => {
+                // Enabling the latches resets them.
+                if val & 0x40 != 0 && self.vblank & 0x40 == 0 {
+                    self.fire_latch = self.fire;
+                }
+                self.vblank = val;
+            }
+            0x02 => self.wsync = true,
+            // RSYNC is only used by test carts, and is not emulated.
+            0x03 => (),
+            0x04 => self.nusiz[0] = val,
+            0x05 => self.nusiz[1] = val,
+            0x06 => self.colup[0] = val,
+            0x07 => self.colup[1] = val,
+            0x08 => self.colupf = val,
+            0x09 => self.colubk = val,
+            0x0a => self.ctrlpf = val,
+            0x0b => self.refp[0] = val & 0x08 != 0,
+            0x0c => self.refp[1] = val & 0x08 != 0,
+            0x0d => self.pf[0] = val,
+            0x0e => self.pf[1] = val,
+            0x0f => self.pf[2] = val,
+            0x10 => self.players[0].reset(self.clock, 5),
+            0x11 => self.players[1].reset(self.clock, 5),
+            0x12 => self.missiles[0].reset(self.clock, 4),
+            0x13 => self.missiles[1].reset(self.clock, 4),
+            0x14 => self.ball.reset(self.clock, 4),
+            0x15..=0x1a => (),
+            // Each player's graphics write copies the other's into its
+            // delayed register, and GRP1 does the same for the ball.
+            0x1b => {
+                self.grp[0] = val;
+                self.grp_old[1] = self.grp[1];
+            }
+            0x1c => {
+                self.grp[1] = val;
+                self.grp_old[0] = self.grp[0];
+                self.enabl_old = self.enabl;
+            }
+            0x1d => self.enam[0] = val & 0x02 != 0,
+            0x1e => self.enam[1] = val & 0x02 != 0,
+            0x1f => self.enabl = val & 0x02 != 0,
+            0x20 => self.players[0].motion = val,
+            0x21 => self.players[1].motion = val,
+            0x22 => self.missiles[0].motion = val,
+            0x23 => self.missiles[1].motion = val,
+            0x24 => self.ball.motion = val,
+            0x25 => self.vdelp[0] = val & 0x01 != 0,
+            0x26 => self.vdelp[1] = val & 0x01 != 0,
+            0x27 => self.vdelbl = val & 0x01 != 0,
+            0x28 => self.lock_missile(0, val & 0x02 != 0),
+            0x29 => self.lock_missile(1, val & 0x02 != 0),
+            0x2a => self.hmove(),
+            0x2b => {
+                for obj in self
+                    .players
+                    .iter_mut()
+                    .chain(self.missiles.iter_mut())
+                    .chain(std::iter::once(&mut self.ball))
+                {
+                    obj.motion = 0;
+                }
+            }
+            0x2c => self.collisions = [0; 8],
+            _ => (),
+        }
+    }
+
+    fn tick(&mut self) {
+        for _ in 0..3 {
+            self.clock();
+        }
+    }
+
+    fn irq(&self) -> bool {
+        false
+    }
+}
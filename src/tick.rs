@@ -0,0 +1,203 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::{Cpu, CycleKind, Sys};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Access {
+    Read,
+    Write(u8),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BusRequest {
+    pub addr: u16,
+    pub access: Access,
+    pub kind: CycleKind,
+    pub sync: bool,
+    pub vp: bool,
+    pub ml: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Phase {
+    Phi1,
+    Phi2,
+}
+
+// Runs a Cpu one bus cycle at a time, leaving the caller to perform
+// each access; complete() finishes the pending cycle and moves on to
+// the next one.
+pub struct Ticker<C: Cpu> {
+    pub cpu: C,
+    bus: Capture,
+    phase: Phase,
+}
+
+impl<C: Cpu> Ticker<C> {
+    pub fn new(cpu: C) -> Ticker<C> {
+        Ticker {
+            cpu,
+            bus: Capture::default(),
+            phase: Phase::Phi1,
+        }
+    }
+
+    // Returns None only if the CPU is halted.
+    pub fn next_bus_request(&mut self) -> Option<BusRequest> {
+        if self.bus.req.is_none() && !self.cpu.halted() {
+            let done = self.cpu.run_instruction(&mut self.bus);
+            debug_assert!(done.is_none());
+        }
+        self.bus.req
+    }
+
+    // The data is ignored for write cycles.
+    pub fn complete(&mut self, data: u8) {
+        if self.next_bus_request().is_some() {
+            self.bus.data = Some(data);
+            self.cpu.run_instruction(&mut self.bus);
+            debug_assert!(self.bus.data.is_none());
+        }
+        self.phase = Phase::Phi1;
+    }
+
+    // The phase the next half cycle will run in.
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    // The first half of a cycle, when the address and R/W lines are
+    // set up; the value of a write is not driven until phi2.
+    pub fn phi1(&mut self) -> Option<BusRequest> {
+        let req = self.next_bus_request()?;
+        self.phase = Phase::Phi2;
+        Some(req)
+    }
+
+    // The second half of a cycle, when the data is transferred.
+    // Returns the value on the data bus: the data passed in for a read,
+    // or the value written.
+    pub fn phi2(&mut self, data: u8) -> Option<u8> {
+        debug_assert_eq!(self.phase, Phase::Phi2);
+        let req = self.next_bus_request()?;
+        self.complete(data);
+        match req.access {
+            Access::Read => Some(data),
+            Access::Write(val) => Some(val),
+        }
+    }
+
+    pub fn set_irq(&mut self, set: bool) {
+        self.bus.irq = set;
+    }
+
+    pub fn set_nmi(&mut self, set: bool) {
+        if set && !self.bus.nmi {
+            self.bus.nmi_edge = true;
+        }
+        self.bus.nmi = set;
+    }
+}
+
+#[derive(Default)]
+struct Capture {
+    req: Option<BusRequest>,
+    data: Option<u8>,
+    sync: bool,
+    vp: bool,
+    ml: bool,
+    irq: bool,
+    nmi: bool,
+    nmi_edge: bool,
+}
+
+impl Capture {
+    fn access(
+        &mut self,
+        addr: u16,
+        access: Access,
+        kind: CycleKind,
+    ) -> Option<u8> {
+        // The CPU retries a paused access, so completion data always
+        // belongs to the first access after a pause.
+        if let Some(data) = self.data.take() {
+            debug_assert_eq!(self.req.map(|r| r.addr), Some(addr));
+            self.req = None;
+            return Some(data);
+        }
+        self.req = Some(BusRequest {
+            addr,
+            access,
+            kind,
+            sync: self.sync,
+            vp: self.vp,
+            ml: self.ml,
+        });
+        None
+    }
+}
+
+impl Sys for Capture {
+    #[inline]
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.read_kind(addr, CycleKind::Data)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.write_kind(addr, val, CycleKind::Data)
+    }
+
+    #[inline]
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        self.access(addr, Access::Read, kind)
+    }
+
+    #[inline]
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
+        self.access(addr, Access::Write(val), kind).map(|_| ())
+    }
+
+    #[inline]
+    fn set_sync(&mut self, set: bool) {
+        self.sync = set;
+    }
+
+    #[inline]
+    fn set_vp(&mut self, set: bool) {
+        self.vp = set;
+    }
+
+    #[inline]
+    fn set_ml(&mut self, set: bool) {
+        self.ml = set;
+    }
+
+    #[inline]
+    fn poll_nmi(&mut self) -> bool {
+        let edge = self.nmi_edge;
+        self.nmi_edge = false;
+        edge
+    }
+
+    #[inline]
+    fn peek_nmi(&self) -> bool {
+        self.nmi_edge
+    }
+
+    #[inline]
+    fn irq(&self) -> bool {
+        self.irq
+    }
+}
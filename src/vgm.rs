@@ -0,0 +1,295 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::io::{self, Write};
+use std::mem;
+
+use crate::{CycleKind, NmiLength, Sys};
+
+const VGM_RATE: u64 = 44_100;
+
+// The sound chips a SoundTap can watch for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SoundChip {
+    // The 2A03 APU at $4000-$4017.
+    Apu,
+    // A SID at the given address, usually $D400.
+    Sid(u16),
+    // A POKEY at the given address, usually $D200.
+    Pokey(u16),
+    // A Mockingboard in the given slot: two AY-3-8910s, each behind a
+    // 6522 at $Cn00 and $Cn80.
+    Mockingboard(u8),
+}
+
+impl SoundChip {
+    fn vgm_command(self) -> Option<u8> {
+        match self {
+            SoundChip::Apu => Some(0xb4),
+            SoundChip::Pokey(_) => Some(0xbb),
+            SoundChip::Mockingboard(_) => Some(0xa0),
+            SoundChip::Sid(_) => None,
+        }
+    }
+
+    // Where the chip's clock goes in the VGM header.
+    fn vgm_clock(self) -> usize {
+        match self {
+            SoundChip::Apu => 0x84,
+            SoundChip::Pokey(_) => 0xb0,
+            _ => 0x74,
+        }
+    }
+}
+
+// A write to a sound chip register. The unit tells apart chips of the
+// same kind, like the two AYs on a Mockingboard.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ChipWrite {
+    pub cycle: u64,
+    pub chip: SoundChip,
+    pub unit: u8,
+    pub reg: u8,
+    pub val: u8,
+}
+
+// Wraps a Sys, counting cycles, and recording the writes to the sound
+// chips it has been told about.
+//
+// The Mockingboard's AYs are written through their 6522s' ports, so a
+// write is recorded when port B gives the AY its write command, using
+// the register last latched and the value on port A.
+pub struct SoundTap<S: Sys> {
+    pub sys: S,
+    chips: Vec<SoundChip>,
+    // Port A, and the latched register, for each AY.
+    ay: [(u8, u8); 2],
+    writes: Vec<ChipWrite>,
+    cycles: u64,
+}
+
+impl<S: Sys> SoundTap<S> {
+    pub fn new(sys: S) -> SoundTap<S> {
+        SoundTap {
+            sys,
+            chips: Vec::new(),
+            ay: [(0, 0); 2],
+            writes: Vec::new(),
+            cycles: 0,
+        }
+    }
+
+    pub fn add(&mut self, chip: SoundChip) {
+        self.chips.push(chip);
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn writes(&self) -> &[ChipWrite] {
+        &self.writes
+    }
+
+    pub fn take_writes(&mut self) -> Vec<ChipWrite> {
+        std::mem::take(&mut self.writes)
+    }
+
+    fn tap(&mut self, addr: u16, val: u8) {
+        for i in 0..self.chips.len() {
+            let chip = self.chips[i];
+            let reg = match chip {
+                SoundChip::Apu => match addr {
+                    0x4000..=0x4013 | 0x4015 | 0x4017 => addr as u8,
+                    _ => continue,
+                },
+                SoundChip::Sid(base) => match addr.wrapping_sub(base) {
+                    reg @ 0x00..=0x1c => reg as u8,
+                    _ => continue,
+                },
+                SoundChip::Pokey(base) => match addr.wrapping_sub(base) {
+                    reg @ 0x00..=0x0f => reg as u8,
+                    _ => continue,
+                },
+                SoundChip::Mockingboard(slot) => {
+                    if addr >> 8 != 0xc0 | u16::from(slot) {
+                        continue;
+                    }
+                    let unit = (addr >> 7) as usize & 1;
+                    let ay = &mut self.ay[unit];
+                    match (addr & 0x0f, val & 7) {
+                        (1, _) => ay.0 = val,
+                        (0, 7) => ay.1 = ay.0 & 0x0f,
+                        (0, 6) => {
+                            let (val, reg) = *ay;
+                            self.record(chip, unit as u8, reg, val);
+                        }
+                        _ => (),
+                    }
+                    continue;
+                }
+            };
+            // A second chip of the same kind is the next unit.
+            let kind = mem::discriminant(&chip);
+            let unit = self.chips[..i]
+                .iter()
+                .filter(|&c| mem::discriminant(c) == kind)
+                .count();
+            self.record(chip, unit as u8, reg, val);
+        }
+    }
+
+    fn record(&mut self, chip: SoundChip, unit: u8, reg: u8, val: u8) {
+        self.writes.push(ChipWrite {
+            cycle: self.cycles,
+            chip,
+            unit,
+            reg,
+            val,
+        });
+    }
+}
+
+impl<S: Sys> Sys for SoundTap<S> {
+    #[inline]
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.read_kind(addr, CycleKind::Data)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.write_kind(addr, val, CycleKind::Data)
+    }
+
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        let val = self.sys.read_kind(addr, kind)?;
+        self.cycles += 1;
+        Some(val)
+    }
+
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
+        self.sys.write_kind(addr, val, kind)?;
+        self.tap(addr, val);
+        self.cycles += 1;
+        Some(())
+    }
+
+    #[inline]
+    fn set_sync(&mut self, set: bool) {
+        self.sys.set_sync(set);
+    }
+
+    #[inline]
+    fn set_vp(&mut self, set: bool) {
+        self.sys.set_vp(set);
+    }
+
+    #[inline]
+    fn set_ml(&mut self, set: bool) {
+        self.sys.set_ml(set);
+    }
+
+    #[inline]
+    fn poll_nmi(&mut self) -> bool {
+        self.sys.poll_nmi()
+    }
+
+    #[inline]
+    fn peek_nmi(&self) -> bool {
+        self.sys.peek_nmi()
+    }
+
+    #[inline]
+    fn nmi_length(&self) -> NmiLength {
+        self.sys.nmi_length()
+    }
+
+    #[inline]
+    fn irq(&self) -> bool {
+        self.sys.irq()
+    }
+}
+
+// Writes a VGM 1.71 file of the writes, which must be in order, lasting
+// until end_cycle. Each chip is clocked at the CPU rate, as the APU,
+// POKEY and Mockingboard are; VGM has no SID commands, so SID writes
+// are an error, as are more than two chips of a kind. DMC samples
+// aren't included.
+pub fn write_vgm<W: Write>(
+    mut out: W,
+    cpu_hz: u32,
+    end_cycle: u64,
+    writes: &[ChipWrite],
+) -> io::Result<()> {
+    let invalid = |msg| Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+    let mut header = vec![0u8; 0x100];
+    let mut data = Vec::new();
+    let mut samples = 0;
+    for w in writes {
+        let cmd = match w.chip.vgm_command() {
+            Some(cmd) => cmd,
+            None => return invalid("VGM has no SID commands"),
+        };
+        if w.unit > 1 {
+            return invalid("VGM allows only two chips of a kind");
+        }
+        let clock = w.chip.vgm_clock();
+        let dual = u32::from(w.unit) << 30;
+        let old = u32::from_le_bytes([
+            header[clock],
+            header[clock + 1],
+            header[clock + 2],
+            header[clock + 3],
+        ]);
+        let clock_val = old | cpu_hz | dual;
+        header[clock..clock + 4].copy_from_slice(&clock_val.to_le_bytes());
+        if let SoundChip::Mockingboard(_) = w.chip {
+            // A plain AY-3-8910, with the default output flags.
+            header[0x78] = 0x00;
+            header[0x79] = 0x01;
+        }
+
+        let at = w.cycle * VGM_RATE / u64::from(cpu_hz);
+        wait(&mut data, at - samples);
+        samples = at;
+        data.extend_from_slice(&[cmd, w.reg | (w.unit << 7), w.val]);
+    }
+    let end = (end_cycle * VGM_RATE / u64::from(cpu_hz)).max(samples);
+    wait(&mut data, end - samples);
+    data.push(0x66);
+
+    header[0..4].copy_from_slice(b"Vgm ");
+    let eof = (header.len() + data.len() - 4) as u32;
+    header[0x04..0x08].copy_from_slice(&eof.to_le_bytes());
+    header[0x08..0x0c].copy_from_slice(&0x171u32.to_le_bytes());
+    header[0x18..0x1c].copy_from_slice(&(end as u32).to_le_bytes());
+    header[0x34..0x38].copy_from_slice(&(0x100u32 - 0x34).to_le_bytes());
+    out.write_all(&header)?;
+    out.write_all(&data)
+}
+
+fn wait(data: &mut Vec<u8>, mut samples: u64) {
+    while samples > 0 {
+        let n = samples.min(0xffff);
+        match n {
+            1..=16 => data.push(0x70 + n as u8 - 1),
+            735 => data.push(0x62),
+            882 => data.push(0x63),
+            _ => {
+                data.push(0x61);
+                data.extend_from_slice(&(n as u16).to_le_bytes());
+            }
+        }
+        samples -= n;
+    }
+}
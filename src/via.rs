@@ -0,0 +1,502 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::Device;
+
+const IF_CA2: u8 = 0x01;
+const IF_CA1: u8 = 0x02;
+const IF_SR: u8 = 0x04;
+const IF_CB2: u8 = 0x08;
+const IF_CB1: u8 = 0x10;
+const IF_T2: u8 = 0x20;
+const IF_T1: u8 = 0x40;
+
+// The 6522 Versatile Interface Adapter.
+#[derive(Clone, Debug)]
+pub struct Via {
+    ora: u8,
+    orb: u8,
+    ddra: u8,
+    ddrb: u8,
+    pa_in: u8,
+    pb_in: u8,
+    ira: u8,
+    irb: u8,
+    t1c: u16,
+    t1l: u16,
+    t1_reload: bool,
+    t1_armed: bool,
+    pb7: bool,
+    t2c: u16,
+    t2l: u8,
+    t2_load: bool,
+    t2_reload: bool,
+    t2_armed: bool,
+    sr: u8,
+    sr_count: u8,
+    sr_out: bool,
+    acr: u8,
+    pcr: u8,
+    ifr: u8,
+    ier: u8,
+    ca1: bool,
+    ca2: bool,
+    cb1: bool,
+    cb2: bool,
+    ca2_out: bool,
+    cb1_out: bool,
+    cb2_out: bool,
+    ca2_pulse: u8,
+    cb2_pulse: u8,
+}
+
+impl Via {
+    pub fn new() -> Via {
+        Via {
+            ora: 0,
+            orb: 0,
+            ddra: 0,
+            ddrb: 0,
+            pa_in: 0xff,
+            pb_in: 0xff,
+            ira: 0,
+            irb: 0,
+            t1c: 0xffff,
+            t1l: 0xffff,
+            t1_reload: false,
+            t1_armed: false,
+            pb7: true,
+            t2c: 0xffff,
+            t2l: 0xff,
+            t2_load: false,
+            t2_reload: false,
+            t2_armed: false,
+            sr: 0,
+            sr_count: 8,
+            sr_out: true,
+            acr: 0,
+            pcr: 0,
+            ifr: 0,
+            ier: 0,
+            ca1: true,
+            ca2: true,
+            cb1: true,
+            cb2: true,
+            ca2_out: true,
+            cb1_out: true,
+            cb2_out: true,
+            ca2_pulse: 0,
+            cb2_pulse: 0,
+        }
+    }
+
+    // Clears the I/O, control, and interrupt registers; the timers,
+    // latches, and shift register are not affected.
+    pub fn reset(&mut self) {
+        self.ora = 0;
+        self.orb = 0;
+        self.ddra = 0;
+        self.ddrb = 0;
+        self.acr = 0;
+        self.pcr = 0;
+        self.ifr = 0;
+        self.ier = 0;
+        self.t1_armed = false;
+        self.t2_armed = false;
+        self.ca2_out = true;
+        self.cb2_out = true;
+    }
+
+    // The levels of the port pins, as driven by either side.
+    pub fn pa(&self) -> u8 {
+        (self.ora & self.ddra) | (self.pa_in & !self.ddra)
+    }
+
+    pub fn pb(&self) -> u8 {
+        let pb = (self.orb & self.ddrb) | (self.pb_in & !self.ddrb);
+        if self.acr & 0x80 != 0 {
+            (pb & 0x7f) | ((self.pb7 as u8) << 7)
+        } else {
+            pb
+        }
+    }
+
+    // Sets the levels that outside devices drive on the input pins.
+    pub fn set_pa(&mut self, val: u8) {
+        self.pa_in = val;
+    }
+
+    pub fn set_pb(&mut self, val: u8) {
+        // T2 counts falling edges on PB6 in pulse counting mode.
+        let fall = (self.pb_in & !val & 0x40) != 0;
+        self.pb_in = val;
+        if fall && self.acr & 0x20 != 0 {
+            self.t2c = self.t2c.wrapping_sub(1);
+            if self.t2c == 0xffff && self.t2_armed {
+                self.t2_armed = false;
+                self.ifr |= IF_T2;
+            }
+        }
+    }
+
+    pub fn ca2(&self) -> bool {
+        match (self.pcr >> 1) & 7 {
+            0b100 | 0b101 => self.ca2_out,
+            0b110 => false,
+            0b111 => true,
+            _ => self.ca2,
+        }
+    }
+
+    pub fn cb1(&self) -> bool {
+        match self.sr_mode() {
+            0 | 3 | 7 => self.cb1,
+            _ => self.cb1_out,
+        }
+    }
+
+    pub fn cb2(&self) -> bool {
+        if self.acr & 0x10 != 0 {
+            return self.sr_out;
+        }
+        match (self.pcr >> 5) & 7 {
+            0b100 | 0b101 => self.cb2_out,
+            0b110 => false,
+            0b111 => true,
+            _ => self.cb2,
+        }
+    }
+
+    pub fn set_ca1(&mut self, set: bool) {
+        if self.active_edge(self.ca1, set, self.pcr & 0x01) {
+            self.ifr |= IF_CA1;
+            if self.acr & 0x01 != 0 {
+                self.ira = self.pa();
+            }
+            if (self.pcr >> 1) & 7 == 0b100 {
+                self.ca2_out = true;
+            }
+        }
+        self.ca1 = set;
+    }
+
+    pub fn set_ca2(&mut self, set: bool) {
+        if self.pcr & 0x08 == 0
+            && self.active_edge(self.ca2, set, self.pcr & 0x04)
+        {
+            self.ifr |= IF_CA2;
+        }
+        self.ca2 = set;
+    }
+
+    pub fn set_cb1(&mut self, set: bool) {
+        if self.active_edge(self.cb1, set, self.pcr & 0x10) {
+            self.ifr |= IF_CB1;
+            if self.acr & 0x02 != 0 {
+                self.irb = self.pb();
+            }
+            if (self.pcr >> 5) & 7 == 0b100 {
+                self.cb2_out = true;
+            }
+        }
+        // Shift in on rising edges, out on falling edges.
+        match self.sr_mode() {
+            3 if set && !self.cb1 => self.shift(),
+            7 if !set && self.cb1 => self.shift(),
+            _ => (),
+        }
+        self.cb1 = set;
+    }
+
+    pub fn set_cb2(&mut self, set: bool) {
+        if self.pcr & 0x80 == 0
+            && self.active_edge(self.cb2, set, self.pcr & 0x40)
+        {
+            self.ifr |= IF_CB2;
+        }
+        self.cb2 = set;
+    }
+
+    fn active_edge(&self, old: bool, new: bool, positive: u8) -> bool {
+        if positive != 0 {
+            !old && new
+        } else {
+            old && !new
+        }
+    }
+
+    fn sr_mode(&self) -> u8 {
+        (self.acr >> 2) & 7
+    }
+
+    fn shift(&mut self) {
+        if self.acr & 0x10 != 0 {
+            self.sr_out = (self.sr & 0x80) != 0;
+            self.sr = self.sr.rotate_left(1);
+        } else {
+            self.sr = (self.sr << 1) | (self.cb2 as u8);
+        }
+        // Mode 4 shifts out continuously without interrupting.
+        if self.sr_count < 8 && self.sr_mode() != 4 {
+            self.sr_count += 1;
+            if self.sr_count == 8 {
+                self.ifr |= IF_SR;
+            }
+        }
+    }
+
+    // T2 runs as an 8-bit shift clock in modes 1, 4 and 5; every two
+    // timeouts make one CB1 pulse.
+    fn tick_sr_clock(&mut self) {
+        if self.t2_reload {
+            self.t2_reload = false;
+            self.t2c = (self.t2c & 0xff00) | u16::from(self.t2l);
+            return;
+        }
+        let lo = (self.t2c as u8).wrapping_sub(1);
+        self.t2c = (self.t2c & 0xff00) | u16::from(lo);
+        if lo == 0xff {
+            self.t2_reload = true;
+            let mode = self.sr_mode();
+            if mode == 4 || self.sr_count < 8 {
+                self.cb1_out = !self.cb1_out;
+                if self.cb1_out {
+                    self.shift();
+                }
+            }
+        }
+    }
+
+    fn tick_t1(&mut self) {
+        if self.t1_reload {
+            self.t1_reload = false;
+            self.t1c = self.t1l;
+            return;
+        }
+        self.t1c = self.t1c.wrapping_sub(1);
+        if self.t1c == 0xffff {
+            let free_run = self.acr & 0x40 != 0;
+            if free_run {
+                self.ifr |= IF_T1;
+                self.pb7 = !self.pb7;
+                self.t1_reload = true;
+            } else if self.t1_armed {
+                self.t1_armed = false;
+                self.ifr |= IF_T1;
+                self.pb7 = true;
+            }
+        }
+    }
+
+    fn tick_t2(&mut self) {
+        if self.t2_load {
+            self.t2_load = false;
+            return;
+        }
+        match self.sr_mode() {
+            1 | 4 | 5 => self.tick_sr_clock(),
+            _ if self.acr & 0x20 != 0 => (),
+            _ => {
+                self.t2c = self.t2c.wrapping_sub(1);
+                if self.t2c == 0xffff && self.t2_armed {
+                    self.t2_armed = false;
+                    self.ifr |= IF_T2;
+                }
+            }
+        }
+    }
+
+    fn port_a_access(&mut self) {
+        self.ifr &= !IF_CA1;
+        // The independent interrupt modes leave the CA2 flag alone.
+        let ca2 = (self.pcr >> 1) & 7;
+        if ca2 != 0b001 && ca2 != 0b011 {
+            self.ifr &= !IF_CA2;
+        }
+        match ca2 {
+            0b100 => self.ca2_out = false,
+            0b101 => {
+                self.ca2_out = false;
+                self.ca2_pulse = 2;
+            }
+            _ => (),
+        }
+    }
+
+    fn port_b_access(&mut self, write: bool) {
+        self.ifr &= !IF_CB1;
+        let cb2 = (self.pcr >> 5) & 7;
+        if cb2 != 0b001 && cb2 != 0b011 {
+            self.ifr &= !IF_CB2;
+        }
+        // CB2 handshaking only happens on writes.
+        match cb2 {
+            0b100 if write => self.cb2_out = false,
+            0b101 if write => {
+                self.cb2_out = false;
+                self.cb2_pulse = 2;
+            }
+            _ => (),
+        }
+    }
+
+    fn sr_access(&mut self) {
+        self.ifr &= !IF_SR;
+        if self.sr_mode() != 0 {
+            self.sr_count = 0;
+        }
+    }
+}
+
+impl Default for Via {
+    fn default() -> Via {
+        Via::new()
+    }
+}
+
+impl Device for Via {
+    fn read(&mut self, reg: u8) -> u8 {
+        match reg & 0x0f {
+            0x0 => {
+                self.port_b_access(false);
+                let pins = if self.acr & 0x02 != 0 {
+                    self.irb
+                } else {
+                    self.pb()
+                };
+                // Output pins read back the output register.
+                (self.orb & self.ddrb) | (pins & !self.ddrb)
+            }
+            0x1 => {
+                self.port_a_access();
+                self.read(0xf)
+            }
+            0x2 => self.ddrb,
+            0x3 => self.ddra,
+            0x4 => {
+                self.ifr &= !IF_T1;
+                self.t1c as u8
+            }
+            0x5 => (self.t1c >> 8) as u8,
+            0x6 => self.t1l as u8,
+            0x7 => (self.t1l >> 8) as u8,
+            0x8 => {
+                self.ifr &= !IF_T2;
+                self.t2c as u8
+            }
+            0x9 => (self.t2c >> 8) as u8,
+            0xa => {
+                self.sr_access();
+                self.sr
+            }
+            0xb => self.acr,
+            0xc => self.pcr,
+            0xd => {
+                let irq = (self.irq() as u8) << 7;
+                self.ifr | irq
+            }
+            0xe => self.ier | 0x80,
+            0xf => {
+                if self.acr & 0x01 != 0 {
+                    self.ira
+                } else {
+                    self.pa()
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn write(&mut self, reg: u8, val: u8) {
+        match reg & 0x0f {
+            0x0 => {
+                self.port_b_access(true);
+                self.orb = val;
+            }
+            0x1 => {
+                self.port_a_access();
+                self.ora = val;
+            }
+            0x2 => self.ddrb = val,
+            0x3 => self.ddra = val,
+            0x4 | 0x6 => self.t1l = (self.t1l & 0xff00) | u16::from(val),
+            0x5 => {
+                self.t1l = (self.t1l & 0x00ff) | u16::from(val) << 8;
+                self.ifr &= !IF_T1;
+                self.t1c = self.t1l;
+                self.t1_reload = true;
+                self.t1_armed = true;
+                if self.acr & 0x80 != 0 {
+                    self.pb7 = false;
+                }
+            }
+            0x7 => {
+                self.t1l = (self.t1l & 0x00ff) | u16::from(val) << 8;
+                self.ifr &= !IF_T1;
+            }
+            0x8 => self.t2l = val,
+            0x9 => {
+                self.ifr &= !IF_T2;
+                self.t2c = u16::from(val) << 8 | u16::from(self.t2l);
+                self.t2_load = true;
+                self.t2_reload = false;
+                self.t2_armed = true;
+            }
+            0xa => {
+                self.sr_access();
+                self.sr = val;
+            }
+            0xb => {
+                if self.acr & 0x80 == 0 && val & 0x80 != 0 {
+                    self.pb7 = true;
+                }
+                self.acr = val;
+            }
+            0xc => self.pcr = val,
+            0xd => self.ifr &= !val & 0x7f,
+            0xe => {
+                if val & 0x80 != 0 {
+                    self.ier |= val & 0x7f;
+                } else {
+                    self.ier &= !val;
+                }
+            }
+            0xf => self.ora = val,
+            _ => unreachable!(),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.tick_t1();
+        self.tick_t2();
+        // Shift under phi2 moves one bit per cycle.
+        match self.sr_mode() {
+            2 | 6 if self.sr_count < 8 => {
+                self.cb1_out = true;
+                self.shift();
+            }
+            _ => (),
+        }
+        if self.ca2_pulse > 0 {
+            self.ca2_pulse -= 1;
+            if self.ca2_pulse == 0 {
+                self.ca2_out = true;
+            }
+        }
+        if self.cb2_pulse > 0 {
+            self.cb2_pulse -= 1;
+            if self.cb2_pulse == 0 {
+                self.cb2_out = true;
+            }
+        }
+    }
+
+    fn irq(&self) -> bool {
+        (self.ifr & self.ier & 0x7f) != 0
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Acia, Cmos, Cpu, Device, Sys};
+
+const DATA: u8 = 0;
+const STATUS: u8 = 1;
+const COMMAND: u8 = 2;
+const CONTROL: u8 = 3;
+
+fn setup(mut acia: Acia, command: u8) -> Acia {
+    // 9600 baud, 8N1
+    acia.write(CONTROL, 0x1e);
+    acia.write(COMMAND, command);
+    acia
+}
+
+#[test]
+fn acia_frame() {
+    let mut acia = setup(Acia::new(1_000_000), 0x0b);
+    assert_eq!(acia.frame_cycles(), 1042);
+    // 7 bits, even parity, 2 stop bits, 300 baud.
+    acia.write(CONTROL, 0xb6);
+    acia.write(COMMAND, 0x6b);
+    assert_eq!(acia.frame_cycles(), 36667);
+}
+
+#[test]
+fn acia_tx() {
+    let mut acia = setup(Acia::new(1_000_000), 0x07);
+    assert_eq!(acia.read(STATUS), 0x10);
+    acia.write(DATA, b'A');
+    assert_eq!(acia.read(STATUS), 0x00);
+    acia.tick();
+    // The byte moved to the shift register, and the IRQ is enabled.
+    assert_eq!(acia.read(STATUS), 0x90);
+    assert!(!acia.irq());
+    acia.write(DATA, b'B');
+    for _ in 0..1041 {
+        acia.tick();
+    }
+    assert_eq!(acia.take_output(), b"");
+    acia.tick();
+    assert_eq!(acia.take_output(), b"A");
+    assert!(acia.irq());
+    for _ in 0..1042 {
+        acia.tick();
+    }
+    assert_eq!(acia.take_output(), b"B");
+}
+
+#[test]
+fn acia_rx() {
+    let mut acia = setup(Acia::new(1_000_000), 0x09);
+    acia.send(b"xy");
+    for _ in 0..1043 {
+        acia.tick();
+    }
+    assert!(acia.irq());
+    assert_eq!(acia.read(STATUS), 0x98);
+    assert!(!acia.irq());
+    assert_eq!(acia.read(DATA), b'x');
+    assert_eq!(acia.read(STATUS), 0x10);
+
+    // Overrun keeps the first byte.
+    acia.send(b"z");
+    for _ in 0..3000 {
+        acia.tick();
+    }
+    assert_eq!(acia.read(STATUS), 0x9c);
+    assert_eq!(acia.read(DATA), b'y');
+    assert_eq!(acia.read(STATUS), 0x10);
+    assert_eq!(acia.pending_input(), 0);
+}
+
+#[test]
+fn acia_rx_disabled() {
+    let mut acia = setup(Acia::new(1_000_000), 0x00);
+    acia.send(b"x");
+    for _ in 0..3000 {
+        acia.tick();
+    }
+    assert_eq!(acia.read(STATUS), 0x10);
+    assert_eq!(acia.pending_input(), 1);
+}
+
+#[test]
+fn acia_wdc() {
+    let mut acia = setup(Acia::wdc(1_000_000), 0x07);
+    acia.write(DATA, b'A');
+    assert_eq!(acia.read(STATUS), 0x10);
+    acia.tick();
+    acia.write(DATA, b'B');
+    assert_eq!(acia.read(STATUS), 0x10);
+    for _ in 0..3000 {
+        acia.tick();
+    }
+    assert_eq!(acia.take_output(), b"B");
+    assert!(!acia.irq());
+}
+
+#[test]
+fn acia_cpu_echo() {
+    #[rustfmt::skip]
+    let code = [
+        0xa9, 0x1e,       // LDA #$1e
+        0x8d, 0x03, 0x50, // STA $5003
+        0xa9, 0x0b,       // LDA #$0b
+        0x8d, 0x02, 0x50, // STA $5002
+        0xad, 0x01, 0x50, // LDA $5001
+        0x29, 0x08,       // AND #$08
+        0xf0, 0xf9,       // BEQ $020a
+        0xad, 0x00, 0x50, // LDA $5000
+        0xa8,             // TAY
+        0xad, 0x01, 0x50, // LDA $5001
+        0x29, 0x10,       // AND #$10
+        0xf0, 0xf9,       // BEQ $0215
+        0x8c, 0x00, 0x50, // STY $5000
+        0x4c, 0x0a, 0x02, // JMP $020a
+    ];
+    let mut sys = AciaSys::new(&code);
+    sys.acia.send(b"HELLO");
+    let mut cpu = Cmos::new();
+    cpu.set_pc(0x0200);
+    while sys.cycle < 8 * 1042 {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    assert_eq!(sys.acia.take_output(), b"HELLO");
+}
+
+// RAM, with an ACIA at $5000.
+struct AciaSys {
+    mem: Vec<u8>,
+    acia: Acia,
+    cycle: u64,
+}
+
+impl AciaSys {
+    fn new(code: &[u8]) -> AciaSys {
+        let mut mem = vec![0u8; 0x10000];
+        mem[0x0200..(0x0200 + code.len())].copy_from_slice(code);
+        AciaSys {
+            mem,
+            acia: Acia::new(1_000_000),
+            cycle: 0,
+        }
+    }
+}
+
+impl Sys for AciaSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let val = match addr {
+            0x5000..=0x5003 => self.acia.read(addr as u8),
+            _ => self.mem[addr as usize],
+        };
+        self.acia.tick();
+        self.cycle += 1;
+        Some(val)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        match addr {
+            0x5000..=0x5003 => self.acia.write(addr as u8, val),
+            _ => self.mem[addr as usize] = val,
+        }
+        self.acia.tick();
+        self.cycle += 1;
+        Some(())
+    }
+
+    fn irq(&self) -> bool {
+        self.acia.irq()
+    }
+}
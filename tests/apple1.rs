@@ -0,0 +1,92 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use robo6502::{Apple1, Cpu, LoadError, Sys};
+
+// Echoes keys to the display, the way the Woz Monitor does.
+#[rustfmt::skip]
+const ECHO: [u8; 32] = [
+    0xa9, 0x7f,       // LDA #$7f
+    0x8d, 0x12, 0xd0, // STA $d012
+    0xa9, 0xa7,       // LDA #$a7
+    0x8d, 0x11, 0xd0, // STA $d011
+    0x8d, 0x13, 0xd0, // STA $d013
+    0xad, 0x11, 0xd0, // LDA $d011
+    0x10, 0xfb,       // BPL $ff0d
+    0xad, 0x10, 0xd0, // LDA $d010
+    0x2c, 0x12, 0xd0, // BIT $d012
+    0x30, 0xfb,       // BMI $ff15
+    0x8d, 0x12, 0xd0, // STA $d012
+    0x4c, 0x0d, 0xff, // JMP $ff0d
+];
+
+fn rom() -> Vec<u8> {
+    let mut rom = vec![0u8; 256];
+    rom[..ECHO.len()].copy_from_slice(&ECHO);
+    rom[0xfc] = 0x00;
+    rom[0xfd] = 0xff;
+    rom
+}
+
+#[test]
+fn apple1_headless() {
+    let mut apple1 = Apple1::new(4, &rom());
+    apple1.send_keys(b"hello\n");
+    apple1.run_cycles(2000);
+    assert_eq!(apple1.take_output(), b"HELLO\n");
+    assert_eq!(apple1.take_output(), b"");
+}
+
+#[test]
+fn apple1_streams() {
+    let display = Shared::default();
+    let mut apple1 = Apple1::new(8, &rom());
+    apple1.set_input(Box::new(&b"abc\n"[..]));
+    apple1.set_output(Box::new(display.clone()));
+    apple1.run_cycles(2000);
+    assert_eq!(&display.0.borrow()[..], b"ABC\n");
+    assert_eq!(apple1.take_output(), b"");
+}
+
+#[test]
+fn apple1_memory() {
+    let mut apple1 = Apple1::new(4, &rom());
+    apple1.load(0x0ffe, &[0x12, 0x34]).unwrap();
+    assert_eq!(
+        apple1.load(0x0fff, &[0x12, 0x34]),
+        Err(LoadError::Invalid("data past end of RAM"))
+    );
+    assert_eq!(apple1.sys.read(0x0fff), Some(0x34));
+    // Nothing above the top of RAM.
+    apple1.sys.write(0x1000, 0x56);
+    assert_eq!(apple1.sys.read(0x1000), Some(0x00));
+    assert_eq!(apple1.sys.read(0xfffd), Some(0xff));
+
+    apple1.run_instruction();
+    assert_eq!(apple1.cpu.pc(), 0xff00);
+    // Four accesses above, and seven for the reset.
+    assert_eq!(apple1.cycles(), 4 + 7);
+}
+
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{write_wav, Apu, Cpu, Device, Nmos, Rp2a03, Sys};
+
+use self::common::VecSys;
+
+mod common;
+
+fn ticks(apu: &mut Apu, n: usize) {
+    for _ in 0..n {
+        apu.tick();
+    }
+}
+
+#[test]
+fn apu_frame_irq() {
+    let mut apu = Apu::new(44_100);
+    apu.write(0x17, 0x00);
+    ticks(&mut apu, 29_830);
+    assert!(!apu.irq());
+    apu.tick();
+    assert!(apu.irq());
+    assert_eq!(apu.read(0x15), 0x40);
+    assert!(!apu.irq());
+
+    // The flag is set again for two more cycles.
+    apu.tick();
+    assert!(apu.irq());
+    apu.read(0x15);
+    ticks(&mut apu, 2);
+    apu.read(0x15);
+    ticks(&mut apu, 1000);
+    assert!(!apu.irq());
+
+    // Setting the inhibit bit clears the flag, and keeps it clear.
+    ticks(&mut apu, 30_000);
+    assert!(apu.irq());
+    apu.write(0x17, 0x40);
+    assert!(!apu.irq());
+    ticks(&mut apu, 60_000);
+    assert!(!apu.irq());
+}
+
+#[test]
+fn apu_length_counter() {
+    let mut apu = Apu::new(44_100);
+    // Loading a length counter while the channel is disabled does
+    // nothing.
+    apu.write(0x03, 0x00);
+    assert_eq!(apu.read(0x15), 0x00);
+
+    apu.write(0x15, 0x0f);
+    apu.write(0x00, 0x10);
+    apu.write(0x03, 0x00);
+    apu.write(0x0c, 0x30);
+    apu.write(0x0f, 0x00);
+    assert_eq!(apu.read(0x15), 0x09);
+
+    // Entering the five-step mode clocks the length counters; the
+    // noise channel is halted.
+    for _ in 0..9 {
+        apu.write(0x17, 0x80);
+        ticks(&mut apu, 4);
+    }
+    assert_eq!(apu.read(0x15), 0x09);
+    apu.write(0x17, 0x80);
+    ticks(&mut apu, 4);
+    assert_eq!(apu.read(0x15), 0x08);
+
+    apu.write(0x15, 0x00);
+    assert_eq!(apu.read(0x15), 0x00);
+}
+
+#[test]
+fn apu_pulse_tone() {
+    let mut apu = Apu::new(44_100);
+    apu.write(0x15, 0x01);
+    // 50% duty, halted length counter, constant volume 15, 440Hz.
+    apu.write(0x00, 0xbf);
+    apu.write(0x02, 253);
+    apu.write(0x03, 0x00);
+    ticks(&mut apu, 1_789_773);
+
+    let samples = apu.take_samples();
+    assert!((samples.len() as i32 - 44_100).abs() <= 1);
+    assert!(apu.take_samples().is_empty());
+    let crossings = samples
+        .windows(2)
+        .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+        .count();
+    assert!((438..=442).contains(&crossings), "{}", crossings);
+    let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    assert!(peak > 0.05 && peak < 1.0);
+}
+
+#[test]
+fn apu_silence() {
+    let mut apu = Apu::new(48_000);
+    ticks(&mut apu, 100_000);
+    // The triangle's DC offset is filtered out.
+    let samples = apu.take_samples_i16();
+    assert_eq!(samples.len(), 2681);
+    assert!(samples[1000..].iter().all(|&s| s == 0));
+}
+
+#[test]
+fn apu_dmc() {
+    let mut apu = Apu::new(44_100);
+    // IRQ enabled, one byte at $C040.
+    apu.write(0x10, 0x8f);
+    apu.write(0x12, 0x01);
+    apu.write(0x13, 0x00);
+    assert_eq!(apu.dmc_request(), None);
+    apu.write(0x15, 0x10);
+    assert_eq!(apu.read(0x15), 0x10);
+    assert_eq!(apu.dmc_request(), Some(0xc040));
+
+    apu.dmc_fill(0xff);
+    assert_eq!(apu.dmc_request(), None);
+    assert!(apu.dmc_irq());
+    assert!(apu.irq());
+    assert_eq!(apu.read(0x15), 0x80);
+    apu.write(0x15, 0x00);
+    assert!(!apu.irq());
+
+    // Looping restarts the sample instead.
+    apu.write(0x10, 0x4f);
+    apu.write(0x15, 0x10);
+    apu.dmc_fill(0xff);
+    ticks(&mut apu, 428 * 8);
+    assert_eq!(apu.dmc_request(), Some(0xc040));
+    assert!(!apu.irq());
+}
+
+#[test]
+fn apu_wav() {
+    let mut wav = Vec::new();
+    write_wav(&mut wav, 22_050, &[0, 1, -1]).unwrap();
+    assert_eq!(wav.len(), 44 + 6);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[4..8], &42u32.to_le_bytes());
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(&wav[24..28], &22_050u32.to_le_bytes());
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(&wav[44..], &[0x00, 0x00, 0x01, 0x00, 0xff, 0xff]);
+}
+
+#[test]
+fn rp2a03_frame_irq() {
+    #[rustfmt::skip]
+    let code = [
+        0xa9, 0x00,             // LDA #$00
+        0x8d, 0x17, 0x40,       // STA $4017
+        0x8d, 0x16, 0x40,       // STA $4016
+        0x58,                   // CLI
+        0x4c, 0x09, 0x02,       // JMP *
+    ];
+    let mut sys = Rp2a03::new(VecSys::with_code(&code), 44_100);
+    // irq: LDA $4015; STA $10; INC $11; RTI
+    sys.sys.mem[0x0300..0x0308]
+        .copy_from_slice(&[0xad, 0x15, 0x40, 0x85, 0x10, 0xe6, 0x11, 0x40]);
+    sys.sys.mem[0x4015] = 0xaa;
+    sys.sys.mem[0x4016] = 0xff;
+    sys.sys.mem[0x4017] = 0xff;
+    sys.sys.mem[0xfffe] = 0x00;
+    sys.sys.mem[0xffff] = 0x03;
+
+    let mut cpu = Nmos::nes();
+    cpu.set_pc(0x0200);
+    for _ in 0..12_000 {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    // $4016 still goes to the system, but $4015 doesn't.
+    assert_eq!(sys.sys.mem[0x4016], 0x00);
+    assert_eq!(sys.sys.mem[0x4017], 0xff);
+    assert_eq!(sys.sys.mem[0x10], 0x40);
+    assert_eq!(sys.sys.mem[0x11], 0x01);
+    assert!(!sys.irq());
+}
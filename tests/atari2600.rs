@@ -0,0 +1,286 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Atari2600, Bankswitch, Cpu, Device, Nmos, Sys, Tia};
+
+// A 262-line kernel: a player at x=21 over a playfield, and a
+// background color that counts down the visible lines.
+#[rustfmt::skip]
+const KERNEL: [u8; 0x62] = [
+    0x78,                   // SEI
+    0xd8,                   // CLD
+    0xa2, 0xff,             // LDX #$ff
+    0x9a,                   // TXS
+    // frame:
+    0xa9, 0x02,             // LDA #$02
+    0x85, 0x00,             // STA VSYNC
+    0x85, 0x02,             // STA WSYNC
+    0x85, 0x02,             // STA WSYNC
+    0x85, 0x02,             // STA WSYNC
+    0xa9, 0x00,             // LDA #$00
+    0x85, 0x00,             // STA VSYNC
+    0xa9, 0x02,             // LDA #$02
+    0x85, 0x01,             // STA VBLANK
+    0xa2, 0x25,             // LDX #37
+    // vblank:
+    0x85, 0x02,             // STA WSYNC
+    0xca,                   // DEX
+    0xd0, 0xfb,             // BNE vblank
+    0xa9, 0x00,             // LDA #$00
+    0x85, 0x01,             // STA VBLANK
+    0xa9, 0xf0,             // LDA #$f0
+    0x85, 0x0d,             // STA PF0
+    0xa9, 0xff,             // LDA #$ff
+    0x85, 0x0e,             // STA PF1
+    0x85, 0x1b,             // STA GRP0
+    0xa9, 0x1e,             // LDA #$1e
+    0x85, 0x08,             // STA COLUPF
+    0xa9, 0x44,             // LDA #$44
+    0x85, 0x06,             // STA COLUP0
+    0x85, 0x2c,             // STA CXCLR
+    0x85, 0x02,             // STA WSYNC
+    0xea, 0xea, 0xea, 0xea, // NOP x 13
+    0xea, 0xea, 0xea, 0xea,
+    0xea, 0xea, 0xea, 0xea,
+    0xea,
+    0x85, 0x10,             // STA RESP0
+    0xa2, 0xc0,             // LDX #192
+    // visible:
+    0x86, 0x09,             // STX COLUBK
+    0x85, 0x02,             // STA WSYNC
+    0xca,                   // DEX
+    0xd0, 0xf9,             // BNE visible
+    0xa5, 0x02,             // LDA CXP0FB
+    0x85, 0x80,             // STA $80
+    0xa9, 0x02,             // LDA #$02
+    0x85, 0x01,             // STA VBLANK
+    0xa2, 0x1d,             // LDX #29
+    // overscan:
+    0x85, 0x02,             // STA WSYNC
+    0xca,                   // DEX
+    0xd0, 0xfb,             // BNE overscan
+    0x4c, 0x05, 0xf0,       // JMP frame
+];
+
+fn rom(code: &[u8], size: usize) -> Vec<u8> {
+    let mut rom = vec![0; size];
+    rom[(size - 0x1000)..(size - 0x1000 + code.len())].copy_from_slice(code);
+    rom[size - 4] = 0x00;
+    rom[size - 3] = 0xf0;
+    rom
+}
+
+#[test]
+fn atari2600_frame() {
+    let mut vcs = Atari2600::new(&rom(&KERNEL, 0x1000));
+    vcs.run_frame();
+    let frame = vcs.run_frame().clone();
+    assert_eq!((frame.width, frame.height), (160, 262));
+    assert_eq!(vcs.sys.tia.frame_count(), 2);
+
+    // VBLANK lines are black.
+    assert!(frame.pixels[..(39 * 160)].iter().all(|&p| p == 0));
+    // The background counts down from 191 on line 41.
+    assert_eq!(frame.pixel(159, 41), 0xbe);
+    assert_eq!(frame.pixel(159, 100), (232 - 100) & 0xfe);
+    // Playfield on both halves, with the player drawn over it.
+    assert_eq!(frame.pixel(0, 100), 0x1e);
+    assert_eq!(frame.pixel(20, 100), 0x1e);
+    assert_eq!(frame.pixel(21, 100), 0x44);
+    assert_eq!(frame.pixel(28, 100), 0x44);
+    assert_eq!(frame.pixel(29, 100), 0x1e);
+    assert_eq!(frame.pixel(48, 100), (232 - 100) & 0xfe);
+    assert_eq!(frame.pixel(80, 100), 0x1e);
+    assert_eq!(frame.pixel(101, 100), 0x1e);
+    // The overscan is black again.
+    assert!(frame.pixels[(232 * 160)..].iter().all(|&p| p == 0));
+
+    // The player hit the playfield.
+    assert_eq!(vcs.sys.riot.ram[0], 0x80 | 0x02);
+}
+
+#[test]
+fn atari2600_ppm() {
+    let mut vcs = Atari2600::new(&rom(&KERNEL, 0x1000));
+    // The first frame is cut short by the first VSYNC.
+    assert_eq!(vcs.run_frame().height, 1);
+    let ppm = vcs.run_frame().to_ppm();
+    let header = b"P6\n160 262\n255\n";
+    assert_eq!(&ppm[..header.len()], header);
+    assert_eq!(ppm.len(), header.len() + 160 * 262 * 3);
+    assert_eq!(&ppm[header.len()..(header.len() + 3)], &[0, 0, 0]);
+}
+
+#[test]
+fn atari2600_wsync() {
+    let mut vcs = Atari2600::new(&rom(&KERNEL, 0x1000));
+    // Run through the first two WSYNCs.
+    while vcs.cpu.pc() != 0xf00d {
+        vcs.run_instruction();
+    }
+    // The STA started at the beginning of the line.
+    assert_eq!(vcs.sys.tia.beam(), (9, 0));
+    let cycles = vcs.cycles();
+    vcs.run_instruction();
+    assert_eq!(vcs.cycles() - cycles, 76);
+    assert_eq!(vcs.sys.tia.beam(), (9, 1));
+}
+
+#[test]
+fn atari2600_memory_map() {
+    let mut vcs = Atari2600::new(&rom(&KERNEL, 0x1000));
+    // RIOT RAM is mirrored at $0180 (and everywhere else A9 is low).
+    vcs.sys.write(0x00ff, 0x12);
+    assert_eq!(vcs.sys.read(0x01ff), Some(0x12));
+    // SWCHB, with the default switches.
+    assert_eq!(vcs.sys.read(0x0282), Some(0x0b));
+    vcs.set_fire(0, true);
+    assert_eq!(vcs.sys.read(0x000c).unwrap() & 0x80, 0x00);
+    assert_eq!(vcs.sys.read(0x000d).unwrap() & 0x80, 0x80);
+    // The cart repeats through the 8K address space.
+    assert_eq!(vcs.sys.read(0x1000), Some(0x78));
+    assert_eq!(vcs.sys.read(0x7000), Some(0x78));
+}
+
+#[test]
+fn atari2600_6507() {
+    // A 6507 sees only 8K, and has no interrupt pins.
+    struct Mem([u8; 0x2000], bool);
+    impl Sys for Mem {
+        fn read(&mut self, addr: u16) -> Option<u8> {
+            Some(self.0[addr as usize])
+        }
+        fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+            self.0[addr as usize] = val;
+            Some(())
+        }
+        fn irq(&self) -> bool {
+            self.1
+        }
+    }
+    let mut mem = Mem([0xea; 0x2000], true);
+    // JMP $F000, to $1000.
+    mem.0[0x1000..0x1003].copy_from_slice(&[0x4c, 0x00, 0xf0]);
+    mem.0[0x1ffc] = 0x00;
+    mem.0[0x1ffd] = 0xf0;
+    let mut cpu = Nmos::m6507();
+    cpu.reset();
+    for _ in 0..10 {
+        cpu.run_instruction(&mut mem);
+        assert_eq!(cpu.pc() & 0x1fff, 0x1000);
+    }
+}
+
+fn bank_rom(size: usize) -> Vec<u8> {
+    let mut rom: Vec<u8> = (0..size).map(|i| (i / 0x400) as u8).collect();
+    let len = rom.len();
+    rom[len - 4] = 0x00;
+    rom[len - 3] = 0xf0;
+    rom
+}
+
+#[test]
+fn atari2600_bankswitch() {
+    for &(size, scheme, first) in &[
+        (0x2000, Bankswitch::F8, 0x1ff8),
+        (0x4000, Bankswitch::F6, 0x1ff6),
+        (0x8000, Bankswitch::F4, 0x1ff4),
+    ] {
+        let mut vcs = Atari2600::new(&bank_rom(size));
+        assert_eq!(vcs.sys.cart.scheme(), scheme);
+        let banks = size / 0x1000;
+        // Starts in the last bank.
+        assert_eq!(vcs.sys.read(0x1000), Some((banks * 4 - 4) as u8));
+        for bank in 0..banks {
+            vcs.sys.read(first + bank as u16);
+            assert_eq!(vcs.sys.read(0x1000), Some((bank * 4) as u8));
+            assert_eq!(vcs.sys.read(0x1c00), Some((bank * 4 + 3) as u8));
+        }
+        // Writes switch banks too.
+        vcs.sys.write(first, 0);
+        assert_eq!(vcs.sys.read(0x1400), Some(1));
+    }
+}
+
+#[test]
+fn atari2600_e0() {
+    let mut vcs = Atari2600::with_bankswitch(&bank_rom(0x2000), Bankswitch::E0);
+    assert_eq!(vcs.sys.read(0x1000), Some(4));
+    assert_eq!(vcs.sys.read(0x1400), Some(5));
+    assert_eq!(vcs.sys.read(0x1800), Some(6));
+    assert_eq!(vcs.sys.read(0x1c00), Some(7));
+    vcs.sys.read(0x1fe3);
+    vcs.sys.read(0x1fe9);
+    vcs.sys.read(0x1ff0);
+    assert_eq!(vcs.sys.read(0x1000), Some(3));
+    assert_eq!(vcs.sys.read(0x1400), Some(1));
+    assert_eq!(vcs.sys.read(0x1800), Some(0));
+    assert_eq!(vcs.sys.read(0x1c00), Some(7));
+}
+
+#[test]
+fn atari2600_tigervision() {
+    let mut vcs =
+        Atari2600::with_bankswitch(&bank_rom(0x2000), Bankswitch::Tigervision);
+    assert_eq!(vcs.sys.read(0x1000), Some(0));
+    assert_eq!(vcs.sys.read(0x1800), Some(6));
+    // Writes to $3F select the bank, but above $3F do not.
+    vcs.sys.write(0x003f, 2);
+    assert_eq!(vcs.sys.read(0x1000), Some(4));
+    assert_eq!(vcs.sys.read(0x1400), Some(5));
+    vcs.sys.write(0x0040, 1);
+    assert_eq!(vcs.sys.read(0x1000), Some(4));
+    assert_eq!(vcs.sys.read(0x1c00), Some(7));
+}
+
+// Runs the writes at the start of a line, then the rest of the line.
+fn tia_line(tia: &mut Tia, writes: &[(u8, u8)]) {
+    for &(reg, val) in writes {
+        tia.write(reg, val);
+    }
+    tia.tick();
+    while tia.beam().0 != 0 {
+        tia.tick();
+    }
+}
+
+#[test]
+fn tia_hmove() {
+    let mut tia = Tia::new();
+    // Reset the ball and missile 0 during HBLANK, to x=2.
+    tia_line(&mut tia, &[(0x14, 0), (0x12, 0)]);
+    // Enable them, and set the ball to move right and the missile left.
+    tia_line(
+        &mut tia,
+        &[
+            (0x09, 0x0e),
+            (0x08, 0x44),
+            (0x06, 0x88),
+            (0x1f, 0x02),
+            (0x1d, 0x02),
+            (0x24, 0xf0),
+            (0x22, 0x10),
+        ],
+    );
+    tia_line(&mut tia, &[(0x2a, 0)]);
+    tia_line(&mut tia, &[]);
+    tia_line(&mut tia, &[(0x00, 0x02)]);
+    assert_eq!(tia.frame_count(), 1);
+
+    let frame = tia.frame();
+    assert_eq!(frame.height, 5);
+    assert_eq!(frame.pixel(2, 1), 0x88);
+    // The HMOVE line starts with eight black pixels.
+    assert_eq!(frame.pixel(1, 2), 0x00);
+    assert_eq!(frame.pixel(8, 2), 0x0e);
+    assert_eq!(frame.pixel(1, 3), 0x88);
+    assert_eq!(frame.pixel(2, 3), 0x0e);
+    assert_eq!(frame.pixel(3, 3), 0x44);
+    // They overlapped before the move.
+    assert_eq!(tia.read(0x04), 0x40);
+}
@@ -66,9 +66,9 @@ fn bus<C: Cpu>(cpu: C) {
     }
 
     // Branch ops
-    for op in 0..256 {
+    for (op, mode) in ADDR_MODES.iter().enumerate() {
         use self::common::CpuAddrMode::*;
-        let addr_mode = match ADDR_MODES[op] {
+        let addr_mode = match *mode {
             S(mode) => mode,
             D(nmode, cmode) => {
                 if !cpu.is_nmos() {
@@ -107,7 +107,7 @@ fn bus<C: Cpu>(cpu: C) {
         test_nop4(cpu.clone(), 0xfc, &mut tested);
     }
 
-    for (i, t) in (&tested).iter().enumerate() {
+    for (i, t) in tested.iter().enumerate() {
         if !t {
             println!("MISSED: {:02x}", i);
         }
@@ -349,11 +349,7 @@ fn bus_zpi(op: u8, is_nmos: bool) -> Vec<AddrTest> {
 }
 
 fn bus_abi(op: u8, action: MemAction, is_nmos: bool) -> Vec<AddrTest> {
-    let w = match action {
-        MemAction::Load => false,
-        MemAction::Decimal => false,
-        _ => true,
-    };
+    let w = !matches!(action, MemAction::Load | MemAction::Decimal);
 
     let h = [0x9b, 0x9c, 0x9e, 0x9f].contains(&op) && is_nmos;
 
@@ -508,11 +504,7 @@ fn bus_izx(op: u8, is_nmos: bool) -> Vec<AddrTest> {
 }
 
 fn bus_izy(op: u8, action: MemAction, is_nmos: bool) -> Vec<AddrTest> {
-    let w = match action {
-        MemAction::Load => false,
-        MemAction::Decimal => false,
-        _ => true,
-    };
+    let w = !matches!(action, MemAction::Load | MemAction::Decimal);
 
     let h = (op == 0x93) && is_nmos;
 
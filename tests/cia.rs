@@ -0,0 +1,212 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cia, Device};
+
+const ICR: u8 = 0xd;
+const CRA: u8 = 0xe;
+const CRB: u8 = 0xf;
+
+#[test]
+fn cia_timer_a() {
+    let mut cia = Cia::new();
+    cia.write(ICR, 0x81);
+    cia.write(0x4, 0x03);
+    cia.write(0x5, 0x00);
+    cia.write(CRA, 0x01);
+    // Two cycles of start delay, N + 1 cycles to the underflow, and one
+    // cycle for the interrupt output.
+    assert_eq!(ticks_until_irq(&mut cia), 7);
+    assert_eq!(cia.read(ICR), 0x81);
+    assert!(!cia.irq());
+    assert_eq!(ticks_until_irq(&mut cia), 4);
+    assert_eq!(cia.read(ICR), 0x81);
+}
+
+#[test]
+fn cia_one_shot() {
+    let mut cia = Cia::new();
+    cia.write(ICR, 0x81);
+    cia.write(CRA, 0x08);
+    cia.write(0x4, 0x02);
+    cia.write(0x5, 0x00);
+    assert_eq!(cia.read(CRA) & 0x01, 0x01);
+    assert_eq!(ticks_until_irq(&mut cia), 6);
+    assert_eq!(cia.read(CRA) & 0x01, 0x00);
+    cia.read(ICR);
+    assert!(ticks_until_irq(&mut cia) > 100);
+    assert_eq!(cia.read(0x4), 0x02);
+}
+
+#[test]
+fn cia_icr_read_race() {
+    let mut cia = Cia::new();
+    cia.write(ICR, 0x81);
+    cia.write(0x4, 0x03);
+    cia.write(0x5, 0x00);
+    cia.write(CRA, 0x01);
+    for _ in 0..5 {
+        cia.tick();
+    }
+    // The underflow happens in the same cycle as this read.
+    assert_eq!(cia.read(ICR), 0x00);
+    cia.tick();
+    for _ in 0..3 {
+        cia.tick();
+        assert!(!cia.irq());
+    }
+    assert_eq!(cia.read(ICR), 0x01);
+}
+
+#[test]
+fn cia_timer_b_cascade() {
+    let mut cia = Cia::new();
+    cia.write(ICR, 0x82);
+    cia.write(0x4, 0x01);
+    cia.write(0x5, 0x00);
+    cia.write(0x6, 0x02);
+    cia.write(0x7, 0x00);
+    cia.write(CRB, 0x41);
+    cia.write(CRA, 0x01);
+    // Timer A underflows every two cycles, and B needs three of them.
+    assert_eq!(ticks_until_irq(&mut cia), 2 + 2 * 3 + 1);
+    assert_eq!(cia.read(ICR), 0x83);
+}
+
+#[test]
+fn cia_pb6() {
+    let mut cia = Cia::new();
+    cia.write(0x4, 0x01);
+    cia.write(0x5, 0x00);
+    // Toggle PB6 on underflow.
+    cia.write(CRA, 0x07);
+    assert_eq!(cia.pb() & 0x40, 0x40);
+    let mut pins = Vec::new();
+    for _ in 0..8 {
+        cia.tick();
+        pins.push(cia.pb() >> 6 & 1);
+    }
+    assert_eq!(pins, [1, 1, 1, 0, 0, 1, 1, 0]);
+}
+
+#[test]
+fn cia_tod() {
+    let mut cia = Cia::new();
+    cia.write(ICR, 0x84);
+    // 11:59:59.9 AM
+    cia.write(0xb, 0x11);
+    cia.write(0xa, 0x59);
+    cia.write(0x9, 0x59);
+    cia.write(0x8, 0x09);
+    // Alarm at 12:00:00.1 PM.
+    cia.write(CRB, 0x80);
+    cia.write(0xb, 0x92);
+    cia.write(0xa, 0x00);
+    cia.write(0x9, 0x00);
+    cia.write(0x8, 0x01);
+    cia.write(CRB, 0x00);
+
+    pulse(&mut cia, 6);
+    assert_eq!(cia.read(0xb), 0x92);
+    // Latched until the tenths are read.
+    pulse(&mut cia, 6);
+    assert_eq!(cia.read(0x9), 0x00);
+    assert_eq!(cia.read(0x8), 0x00);
+    assert_eq!(cia.read(0x8), 0x01);
+    cia.tick();
+    assert!(cia.irq());
+    assert_eq!(cia.read(ICR), 0x84);
+
+    // 50Hz input.
+    cia.write(CRA, 0x80);
+    pulse(&mut cia, 5);
+    assert_eq!(cia.read(0x8), 0x02);
+
+    // 12:59:59.9 PM goes to 1 PM.
+    cia.write(0xb, 0x92);
+    cia.write(0xa, 0x59);
+    cia.write(0x9, 0x59);
+    pulse(&mut cia, 5);
+    assert_eq!(cia.read(0x8), 0x02);
+    cia.write(0x8, 0x09);
+    pulse(&mut cia, 5);
+    assert_eq!(cia.read(0xb), 0x81);
+    cia.read(0x8);
+}
+
+#[test]
+fn cia_serial_out() {
+    let mut cia = Cia::new();
+    cia.write(ICR, 0x88);
+    cia.write(0x4, 0x00);
+    cia.write(0x5, 0x00);
+    cia.write(CRA, 0x41);
+    cia.write(0xc, 0xa5);
+    let mut bits = Vec::new();
+    let mut cnt = cia.cnt();
+    while !cia.irq() {
+        cia.tick();
+        if cia.cnt() && !cnt {
+            bits.push(cia.sp() as u8);
+        }
+        cnt = cia.cnt();
+        assert!(bits.len() <= 8);
+    }
+    assert_eq!(bits, [1, 0, 1, 0, 0, 1, 0, 1]);
+    assert_eq!(cia.read(ICR), 0x89);
+}
+
+#[test]
+fn cia_serial_in() {
+    let mut cia = Cia::new();
+    for i in 0..8 {
+        cia.set_sp((0x3c << i) & 0x80 != 0);
+        cia.set_cnt(false);
+        cia.set_cnt(true);
+    }
+    assert_eq!(cia.read(0xc), 0x3c);
+    assert_eq!(cia.read(ICR), 0x08);
+}
+
+#[test]
+fn cia_nmi_edge() {
+    let mut cia = Cia::new();
+    cia.write(ICR, 0x90);
+    cia.set_flag(false);
+    cia.tick();
+    assert!(cia.peek_nmi());
+    assert!(cia.poll_nmi());
+    assert!(!cia.poll_nmi());
+
+    // No new edge while the output is still asserted.
+    cia.set_flag(true);
+    cia.set_flag(false);
+    cia.tick();
+    assert!(!cia.peek_nmi());
+    cia.read(ICR);
+    cia.tick();
+    cia.set_flag(true);
+    cia.set_flag(false);
+    cia.tick();
+    assert!(cia.poll_nmi());
+}
+
+fn ticks_until_irq(cia: &mut Cia) -> usize {
+    let mut ticks = 0;
+    while !cia.irq() && ticks <= 1000 {
+        cia.tick();
+        ticks += 1;
+    }
+    ticks
+}
+
+fn pulse(cia: &mut Cia, count: usize) {
+    for _ in 0..count {
+        cia.pulse_tod();
+    }
+}
@@ -1,8 +1,8 @@
-#![allow(dead_code)]
+#![allow(dead_code, clippy::upper_case_acronyms)]
 
 use machine_int::MachineInt;
 
-use robo6502::{Cpu, NmiLength, Status, Sys};
+use robo6502::{Cpu, CycleKind, NmiLength, Status, Sys};
 
 pub trait TestSys: Sys {
     fn run_instruction<C: Cpu>(&mut self, cpu: &mut C);
@@ -13,7 +13,7 @@ pub trait MemSys: Sys {
 }
 
 pub struct VecSys {
-    mem: Vec<u8>,
+    pub mem: Vec<u8>,
 }
 
 impl VecSys {
@@ -21,6 +21,13 @@ impl VecSys {
         assert!(mem.len() >= 0xffff);
         VecSys { mem }
     }
+
+    // 64K of RAM, with the code at $0200.
+    pub fn with_code(code: &[u8]) -> VecSys {
+        let mut mem = vec![0u8; 0x10000];
+        mem[0x0200..(0x0200 + code.len())].copy_from_slice(code);
+        VecSys { mem }
+    }
 }
 
 impl MemSys for VecSys {
@@ -113,6 +120,16 @@ impl<T: Sys> TestSys for StepFullSys<T> {
 impl<T: Sys> Sys for StepFullSys<T> {
     #[inline]
     fn read(&mut self, addr: u16) -> Option<u8> {
+        self.read_kind(addr, CycleKind::Data)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.write_kind(addr, val, CycleKind::Data)
+    }
+
+    #[inline]
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
         if self.sync {
             self.do_stop = true;
         } else {
@@ -121,14 +138,19 @@ impl<T: Sys> Sys for StepFullSys<T> {
                 return None;
             }
         }
-        self.sys.read(addr)
+        self.sys.read_kind(addr, kind)
     }
 
     #[inline]
-    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
         // No instruction writes immediately after opcode fetch, so we
         // don't have to check do_stop here.
-        self.sys.write(addr, val)
+        self.sys.write_kind(addr, val, kind)
     }
 
     #[inline]
@@ -137,6 +159,16 @@ impl<T: Sys> Sys for StepFullSys<T> {
         self.sys.set_sync(set);
     }
 
+    #[inline]
+    fn set_vp(&mut self, set: bool) {
+        self.sys.set_vp(set);
+    }
+
+    #[inline]
+    fn set_ml(&mut self, set: bool) {
+        self.sys.set_ml(set);
+    }
+
     #[inline]
     fn poll_nmi(&mut self) -> bool {
         self.sys.poll_nmi()
@@ -196,20 +228,35 @@ impl<T: Sys> TestSys for StepSys<T> {
 impl<T: Sys> Sys for StepSys<T> {
     #[inline]
     fn read(&mut self, addr: u16) -> Option<u8> {
+        self.read_kind(addr, CycleKind::Data)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.write_kind(addr, val, CycleKind::Data)
+    }
+
+    #[inline]
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
         self.do_stop = !self.do_stop;
         if !self.do_stop {
             return None;
         }
-        self.sys.read(addr)
+        self.sys.read_kind(addr, kind)
     }
 
     #[inline]
-    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
         self.do_stop = !self.do_stop;
         if !self.do_stop {
             return None;
         }
-        self.sys.write(addr, val)
+        self.sys.write_kind(addr, val, kind)
     }
 
     #[inline]
@@ -218,6 +265,16 @@ impl<T: Sys> Sys for StepSys<T> {
         self.sys.set_sync(set);
     }
 
+    #[inline]
+    fn set_vp(&mut self, set: bool) {
+        self.sys.set_vp(set);
+    }
+
+    #[inline]
+    fn set_ml(&mut self, set: bool) {
+        self.sys.set_ml(set);
+    }
+
     #[inline]
     fn irq(&self) -> bool {
         self.sys.irq()
@@ -278,7 +335,7 @@ pub enum CpuAddrMode {
 use self::AddrMode::*;
 use self::CpuAddrMode::*;
 use self::MemAction::*;
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 pub static ADDR_MODES: [CpuAddrMode; 256] = [
     S(MISC),
     S(IZX(Load)),
@@ -566,3 +623,67 @@ pub fn branch_flag(op: u8) -> (Status, bool) {
         _ => unreachable!(),
     }
 }
+
+// The opcodes that don't halt the CPU.
+pub fn running_ops<C: Cpu>(cpu: &C) -> impl Iterator<Item = u8> {
+    let nmos = cpu.is_nmos();
+    let not_kil = [0x82, 0xa2, 0xc2, 0xe2];
+    (0..=255u8).filter(move |&op| {
+        let kil = (op & 0x0f) == 0x02 && !not_kil.contains(&op);
+        !(nmos && kil)
+    })
+}
+
+// PC at $0200, the stack empty, and X and Y $20, for running the code
+// of a LogSys.
+pub fn setup_cpu<C: Cpu>(mut cpu: C) -> C {
+    cpu.set_pc(0x0200);
+    cpu.set_sp(0xff);
+    cpu.set_x(0x20);
+    cpu.set_y(0x20);
+    cpu
+}
+
+// 64K of RAM, with the code at $0200, logging the address, direction
+// and kind of each access.
+pub struct LogSys {
+    pub mem: Vec<u8>,
+    pub log: Vec<(u16, bool, CycleKind)>,
+}
+
+impl LogSys {
+    pub fn new(code: &[u8]) -> LogSys {
+        let mut mem = vec![0u8; 0x10000];
+        mem[0x0200..(0x0200 + code.len())].copy_from_slice(code);
+        LogSys {
+            mem,
+            log: Vec::new(),
+        }
+    }
+}
+
+impl Sys for LogSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        Some(self.mem[addr as usize])
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.mem[addr as usize] = val;
+        Some(())
+    }
+
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        self.log.push((addr, false, kind));
+        self.read(addr)
+    }
+
+    fn write_kind(
+        &mut self,
+        addr: u16,
+        val: u8,
+        kind: CycleKind,
+    ) -> Option<()> {
+        self.log.push((addr, true, kind));
+        self.write(addr, val)
+    }
+}
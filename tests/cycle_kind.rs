@@ -0,0 +1,177 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cmos, Cpu, CycleKind, Events, Nmos, Status};
+
+use self::common::{running_ops, setup_cpu, LogSys, StepSys, TestSys};
+
+use self::CycleKind::*;
+
+mod common;
+
+#[test]
+fn kind_abs_indexed() {
+    // LDA $12f0,X
+    let code = [0xbd, 0xf0, 0x12];
+    let exp = [
+        (0x0200, Opcode),
+        (0x0201, Operand),
+        (0x0202, Operand),
+        (0x1210, Dummy),
+        (0x1310, Data),
+    ];
+    assert_eq!(reads(Nmos::standard(), &code), exp);
+    let exp = [
+        (0x0200, Opcode),
+        (0x0201, Operand),
+        (0x0202, Operand),
+        (0x0202, Dummy),
+        (0x1310, Data),
+    ];
+    assert_eq!(reads(Cmos::new(), &code), exp);
+}
+
+#[test]
+fn kind_rmw() {
+    // INC $10
+    let code = [0xe6, 0x10];
+    let log = run(Nmos::standard(), &code);
+    let exp = [
+        (0x0200, false, Opcode),
+        (0x0201, false, Operand),
+        (0x0010, false, Data),
+        (0x0010, true, Dummy),
+        (0x0010, true, Data),
+    ];
+    assert_eq!(log, exp);
+    let log = run(Cmos::new(), &code);
+    let exp = [
+        (0x0200, false, Opcode),
+        (0x0201, false, Operand),
+        (0x0010, false, Data),
+        (0x0010, false, Dummy),
+        (0x0010, true, Data),
+    ];
+    assert_eq!(log, exp);
+}
+
+#[test]
+fn kind_stack() {
+    // JSR $0300
+    let code = [0x20, 0x00, 0x03];
+    let exp = [
+        (0x0200, false, Opcode),
+        (0x0201, false, Operand),
+        (0x01ff, false, Dummy),
+        (0x01ff, true, Stack),
+        (0x01fe, true, Stack),
+        (0x0202, false, Operand),
+    ];
+    assert_eq!(run(Nmos::standard(), &code), exp);
+    assert_eq!(run(Cmos::new(), &code), exp);
+
+    // RTS
+    let code = [0x60];
+    let exp = [
+        (0x0200, false, Opcode),
+        (0x0201, false, Dummy),
+        (0x01ff, false, Dummy),
+        (0x0100, false, Stack),
+        (0x0101, false, Stack),
+        (0x0000, false, Dummy),
+    ];
+    assert_eq!(run(Nmos::standard(), &code), exp);
+    assert_eq!(run(Cmos::new(), &code), exp);
+}
+
+#[test]
+fn kind_vector() {
+    // BRK
+    let code = [0x00];
+    let exp = [
+        (0x0200, false, Opcode),
+        (0x0201, false, Operand),
+        (0x01ff, true, Stack),
+        (0x01fe, true, Stack),
+        (0x01fd, true, Stack),
+        (0xfffe, false, Vector),
+        (0xffff, false, Vector),
+    ];
+    assert_eq!(run(Nmos::standard(), &code), exp);
+    assert_eq!(run(Cmos::new(), &code), exp);
+}
+
+#[test]
+fn kind_interrupt() {
+    fn check<C: Cpu>(cpu: C, nmi: bool) -> Vec<(u16, bool, CycleKind)> {
+        // NOP
+        let mut sys = Events::new(LogSys::new(&[0xea]));
+        let mut cpu = setup_cpu(cpu);
+        cpu.set_flag(Status::I, false);
+        if nmi {
+            sys.pulse_nmi(100);
+        } else {
+            sys.set_irq(true);
+        }
+        cpu.run_instruction(&mut sys);
+        sys.sys.log.clear();
+        cpu.run_instruction(&mut sys);
+        sys.sys.log.clone()
+    }
+
+    for &nmi in &[false, true] {
+        let vector = if nmi { 0xfffa } else { 0xfffe };
+        let exp = [
+            (0x0201, false, Dummy),
+            (0x0201, false, Dummy),
+            (0x01ff, true, Stack),
+            (0x01fe, true, Stack),
+            (0x01fd, true, Stack),
+            (vector, false, Vector),
+            (vector + 1, false, Vector),
+        ];
+        assert_eq!(check(Nmos::standard(), nmi), exp);
+        assert_eq!(check(Cmos::new(), nmi), exp);
+    }
+}
+
+#[test]
+fn kind_step() {
+    // The kind of each cycle must not depend on whether the
+    // instruction ran all at once or was paused between cycles.
+    fn check<C: Cpu>(cpu: C) {
+        for op in running_ops(&cpu) {
+            let code = [op, 0x10, 0x12];
+            let full = run(cpu.clone(), &code);
+
+            let mut sys = StepSys::new(LogSys::new(&code));
+            let mut step = setup_cpu(cpu.clone());
+            sys.run_instruction(&mut step);
+            // The step sys may also have fetched the next opcode.
+            let log = &sys.sys.log;
+            assert!(log[full.len()..].iter().all(|e| e.2 == Opcode));
+            assert_eq!(full[..], log[..full.len()], "op {:02x}", op);
+        }
+    }
+
+    check(Nmos::standard());
+    check(Cmos::new());
+}
+
+fn reads<C: Cpu>(cpu: C, code: &[u8]) -> Vec<(u16, CycleKind)> {
+    run(cpu, code)
+        .into_iter()
+        .map(|(addr, _, kind)| (addr, kind))
+        .collect()
+}
+
+fn run<C: Cpu>(cpu: C, code: &[u8]) -> Vec<(u16, bool, CycleKind)> {
+    let mut sys = LogSys::new(code);
+    setup_cpu(cpu).run_instruction(&mut sys);
+    sys.log
+}
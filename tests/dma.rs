@@ -0,0 +1,179 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cpu, CycleKind, Device, Nmos, Rp2a03, Sys};
+
+fn new_nes() -> Rp2a03<NesSys> {
+    let mut sys = NesSys {
+        mem: vec![0u8; 0x10000],
+        oam: Vec::new(),
+        reads: Vec::new(),
+        fetches: Vec::new(),
+        pauses: 0,
+    };
+    for i in 0..0x100 {
+        sys.mem[0x0300 + i] = i as u8;
+    }
+    sys.mem[0xc000] = 0x55;
+    Rp2a03::new(sys, 44_100)
+}
+
+// Returns the number of cycles stolen from a read.
+fn stolen(nes: &mut Rp2a03<NesSys>, addr: u16) -> u64 {
+    let start = nes.cycles();
+    while nes.read(addr).is_none() {}
+    nes.cycles() - start - 1
+}
+
+#[test]
+fn dma_oam() {
+    for &(skip, expected) in &[(0, 514), (1, 513)] {
+        let mut nes = new_nes();
+        for _ in 0..skip {
+            nes.read(0x0000);
+        }
+        nes.write(0x4014, 0x03);
+        // DMA waits for a read cycle.
+        nes.write(0x0000, 0x00);
+        assert_eq!(nes.sys.oam.len(), 0);
+        assert_eq!(stolen(&mut nes, 0x0010), expected);
+        assert_eq!(nes.sys.oam, (0..=0xff).collect::<Vec<u8>>());
+        assert_eq!(stolen(&mut nes, 0x0010), 0);
+    }
+}
+
+#[test]
+fn dma_oam_halt_read() {
+    let mut nes = new_nes();
+    nes.write(0x4014, 0x03);
+    nes.write(0x0000, 0x00);
+    nes.sys.reads.clear();
+    stolen(&mut nes, 0x2002);
+    // The CPU's read is repeated on the halt cycle, and the alignment
+    // cycle if there is one.
+    assert_eq!(&nes.sys.reads[..3], &[0x2002, 0x2002, 0x0300]);
+    assert_eq!(nes.sys.reads.last(), Some(&0x2002));
+}
+
+#[test]
+fn dma_dmc() {
+    for &(skip, expected) in &[(0, 4), (1, 3)] {
+        let mut nes = new_nes();
+        for _ in 0..skip {
+            nes.read(0x0000);
+        }
+        // A one-byte sample at $C000.
+        nes.write(0x4012, 0x00);
+        nes.write(0x4013, 0x00);
+        nes.write(0x4015, 0x10);
+        nes.sys.reads.clear();
+        assert_eq!(stolen(&mut nes, 0x0010), expected);
+        assert_eq!(nes.sys.reads[expected as usize - 1], 0xc000);
+        assert_eq!(nes.read(0x4015), Some(0x00));
+    }
+}
+
+#[test]
+fn dma_dmc_paused() {
+    let mut nes = new_nes();
+    nes.write(0x4012, 0x00);
+    nes.write(0x4013, 0x00);
+    nes.write(0x4015, 0x10);
+    // The sample read is paused twice, then retried.
+    nes.sys.pauses = 2;
+    stolen(&mut nes, 0x0010);
+    assert_eq!(nes.sys.fetches, [CycleKind::Data; 3]);
+    assert_eq!(nes.apu.dmc_request(), None);
+    assert_eq!(nes.read(0x4015), Some(0x00));
+}
+
+#[test]
+fn dma_dmc_during_oam() {
+    let mut nes = new_nes();
+    nes.write(0x4014, 0x03);
+    stolen(&mut nes, 0x0000);
+    let oam_cycles = nes.cycles();
+
+    let mut nes = new_nes();
+    nes.write(0x4014, 0x03);
+    for _ in 0..100 {
+        nes.read(0x0000);
+    }
+    nes.apu.write(0x12, 0x00);
+    nes.apu.write(0x13, 0x00);
+    nes.apu.write(0x15, 0x10);
+    stolen(&mut nes, 0x0000);
+    // The DMC fetch only takes two cycles from the OAM transfer.
+    assert_eq!(nes.cycles(), oam_cycles + 2);
+    assert_eq!(nes.sys.oam, (0..=0xff).collect::<Vec<u8>>());
+    assert!(nes.sys.reads.contains(&0xc000));
+}
+
+#[test]
+fn dma_cpu() {
+    #[rustfmt::skip]
+    let code = [
+        0xa9, 0x03,             // LDA #$03
+        0x8d, 0x14, 0x40,       // STA $4014
+        0xea,                   // NOP
+    ];
+    let mut nes = new_nes();
+    nes.sys.mem[0x0200..0x0206].copy_from_slice(&code);
+    let mut cpu = Nmos::nes();
+    cpu.set_pc(0x0200);
+    cpu.run_instruction(&mut nes).unwrap();
+    cpu.run_instruction(&mut nes).unwrap();
+    assert_eq!(nes.cycles(), 6);
+
+    // The NOP's opcode fetch is paused until the DMA is done.
+    let mut pauses = 0;
+    while cpu.run_instruction(&mut nes).is_none() {
+        pauses += 1;
+    }
+    assert_eq!(pauses, 514);
+    assert_eq!(nes.cycles(), 6 + 514 + 2);
+    assert_eq!(cpu.pc(), 0x0206);
+    assert_eq!(nes.sys.oam.len(), 256);
+}
+
+// 64K of RAM, with OAM at $2004, remembering the addresses read. The
+// kinds of the reads from the sample at $C000 are kept too, and those
+// reads can be paused.
+struct NesSys {
+    mem: Vec<u8>,
+    oam: Vec<u8>,
+    reads: Vec<u16>,
+    fetches: Vec<CycleKind>,
+    pauses: u32,
+}
+
+impl Sys for NesSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.reads.push(addr);
+        Some(self.mem[addr as usize])
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        if addr == 0x2004 {
+            self.oam.push(val);
+        }
+        self.mem[addr as usize] = val;
+        Some(())
+    }
+
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        if addr == 0xc000 {
+            self.fetches.push(kind);
+            if self.pauses > 0 {
+                self.pauses -= 1;
+                return None;
+            }
+        }
+        self.read(addr)
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{BenEater, Cpu, Hd44780, Sys};
+
+// The "hello world" program from the breadboard computer videos,
+// which polls the busy flag between LCD writes.
+#[rustfmt::skip]
+const HELLO: [u8; 0x8f] = [
+    0xa2, 0xff,             // LDX #$ff
+    0x9a,                   // TXS
+    0xa9, 0xff,             // LDA #$ff
+    0x8d, 0x02, 0x60,       // STA DDRB
+    0xa9, 0xe0,             // LDA #$e0
+    0x8d, 0x03, 0x60,       // STA DDRA
+    0xa9, 0x38,             // LDA #$38
+    0x20, 0x55, 0x80,       // JSR lcd_instruction
+    0xa9, 0x0e,             // LDA #$0e
+    0x20, 0x55, 0x80,       // JSR lcd_instruction
+    0xa9, 0x06,             // LDA #$06
+    0x20, 0x55, 0x80,       // JSR lcd_instruction
+    0xa9, 0x01,             // LDA #$01
+    0x20, 0x55, 0x80,       // JSR lcd_instruction
+    0xa2, 0x00,             // LDX #$00
+    // print:
+    0xbd, 0x81, 0x80,       // LDA message,X
+    0xf0, 0x07,             // BEQ loop
+    0x20, 0x6b, 0x80,       // JSR print_char
+    0xe8,                   // INX
+    0x4c, 0x23, 0x80,       // JMP print
+    // loop:
+    0x4c, 0x2f, 0x80,       // JMP loop
+    // lcd_wait:
+    0x48,                   // PHA
+    0xa9, 0x00,             // LDA #$00
+    0x8d, 0x02, 0x60,       // STA DDRB
+    // lcd_busy:
+    0xa9, 0x40,             // LDA #RW
+    0x8d, 0x01, 0x60,       // STA PORTA
+    0xa9, 0xc0,             // LDA #(RW | E)
+    0x8d, 0x01, 0x60,       // STA PORTA
+    0xad, 0x00, 0x60,       // LDA PORTB
+    0x29, 0x80,             // AND #$80
+    0xd0, 0xef,             // BNE lcd_busy
+    0xa9, 0x40,             // LDA #RW
+    0x8d, 0x01, 0x60,       // STA PORTA
+    0xa9, 0xff,             // LDA #$ff
+    0x8d, 0x02, 0x60,       // STA DDRB
+    0x68,                   // PLA
+    0x60,                   // RTS
+    // lcd_instruction:
+    0x20, 0x32, 0x80,       // JSR lcd_wait
+    0x8d, 0x00, 0x60,       // STA PORTB
+    0xa9, 0x00,             // LDA #$00
+    0x8d, 0x01, 0x60,       // STA PORTA
+    0xa9, 0x80,             // LDA #E
+    0x8d, 0x01, 0x60,       // STA PORTA
+    0xa9, 0x00,             // LDA #$00
+    0x8d, 0x01, 0x60,       // STA PORTA
+    0x60,                   // RTS
+    // print_char:
+    0x20, 0x32, 0x80,       // JSR lcd_wait
+    0x8d, 0x00, 0x60,       // STA PORTB
+    0xa9, 0x20,             // LDA #RS
+    0x8d, 0x01, 0x60,       // STA PORTA
+    0xa9, 0xa0,             // LDA #(RS | E)
+    0x8d, 0x01, 0x60,       // STA PORTA
+    0xa9, 0x20,             // LDA #RS
+    0x8d, 0x01, 0x60,       // STA PORTA
+    0x60,                   // RTS
+    // message:
+    b'H', b'e', b'l', b'l', b'o', b',', b' ',
+    b'w', b'o', b'r', b'l', b'd', b'!', 0x00,
+];
+
+fn rom(code: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0xea; 0x8000];
+    rom[..code.len()].copy_from_slice(code);
+    rom[0x7ffc] = 0x00;
+    rom[0x7ffd] = 0x80;
+    rom
+}
+
+#[test]
+fn eater_hello_world() {
+    let mut sbc = BenEater::new(&rom(&HELLO), 1_000_000);
+    sbc.run_cycles(20_000);
+    assert_eq!(sbc.cpu.pc(), 0x802f);
+    assert!(sbc.lcd().display_on());
+    assert_eq!(sbc.lcd_text(), "Hello, world!   \n                ");
+    assert_eq!(sbc.lcd().cursor(), Some((0, 13)));
+}
+
+#[test]
+fn eater_memory_map() {
+    let mut sbc = BenEater::new(&rom(&[]), 1_000_000);
+    sbc.sys.write(0x3fff, 0x12);
+    assert_eq!(sbc.sys.read(0x3fff), Some(0x12));
+    sbc.sys.write(0x8000, 0x34);
+    assert_eq!(sbc.sys.read(0x8000), Some(0xea));
+    assert_eq!(sbc.sys.read(0xfffd), Some(0x80));
+
+    // VIA DDRB at $6002, ACIA data at $5000.
+    sbc.sys.write(0x6002, 0xa5);
+    assert_eq!(sbc.sys.read(0x6002), Some(0xa5));
+    sbc.sys.write(0x5002, 0x0b);
+    sbc.sys.write(0x5000, b'A');
+    sbc.run_cycles(10_000);
+    assert_eq!(sbc.sys.acia.take_output(), b"A");
+}
+
+// Writes one byte as two nibbles, the way a 4-bit interface does.
+fn write4(lcd: &mut Hd44780, rs: bool, val: u8) {
+    for &nibble in &[val & 0xf0, val << 4] {
+        lcd.bus(true, false, rs, nibble);
+        lcd.bus(false, false, rs, nibble);
+    }
+}
+
+#[test]
+fn lcd_four_bit() {
+    let mut lcd = Hd44780::new(16, 2, 1_000_000);
+    // Function set to 4-bit is a single 8-bit write.
+    lcd.bus(true, false, false, 0x20);
+    lcd.bus(false, false, false, 0x20);
+    write4(&mut lcd, false, 0x28);
+    write4(&mut lcd, false, 0x0c);
+    write4(&mut lcd, false, 0xc0);
+    for &ch in b"line 2" {
+        write4(&mut lcd, true, ch);
+    }
+    assert_eq!(lcd.text(), format!("{:16}\n{:16}", "", "line 2"));
+    assert_eq!(lcd.cursor(), None);
+
+    // The busy flag and address come back as two nibbles.
+    assert!(lcd.busy());
+    for _ in 0..43 {
+        lcd.tick();
+    }
+    assert_eq!(lcd.bus(true, true, false, 0), Some(0x40));
+    lcd.bus(false, true, false, 0);
+    assert_eq!(lcd.bus(true, true, false, 0), Some(0x60));
+    lcd.bus(false, true, false, 0);
+}
+
+#[test]
+fn lcd_shift() {
+    let mut lcd = Hd44780::new(16, 2, 1_000_000);
+    let mut write = |rs, val| {
+        lcd.bus(true, false, rs, val);
+        lcd.bus(false, false, rs, val);
+    };
+    write(false, 0x38);
+    write(false, 0x0e);
+    for &ch in b"ABC" {
+        write(true, ch);
+    }
+    // Shift the display right, then move the cursor left.
+    write(false, 0x1c);
+    write(false, 0x10);
+    assert_eq!(lcd.lines()[0], " ABC            ");
+    assert_eq!(lcd.cursor(), Some((0, 3)));
+}
@@ -0,0 +1,144 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cmos, Cpu, Events, NmiLength, Nmos, Status, Sys};
+
+use self::common::VecSys;
+
+mod common;
+
+#[test]
+fn events_order() {
+    let mut sys = Events::new(VecSys::with_code(&[]));
+    sys.schedule(2, |ev| ev.sys.mem[0x10] += 1);
+    sys.schedule(1, |ev| ev.sys.mem[0x10] *= 3);
+    sys.schedule(1, |ev| ev.sys.mem[0x10] += 2);
+    assert_eq!(sys.read(0x10), Some(0x00));
+    assert_eq!(sys.cycle(), 1);
+    assert_eq!(sys.pending(), 1);
+    assert_eq!(sys.read(0x10), Some(0x02));
+    assert_eq!(sys.read(0x10), Some(0x03));
+    assert_eq!(sys.pending(), 0);
+}
+
+#[test]
+fn events_nmi_length() {
+    let mut sys = Events::new(VecSys::with_code(&[]));
+    sys.pulse_nmi(1);
+    assert!(sys.peek_nmi());
+    assert_eq!(sys.nmi_length(), NmiLength::One);
+    assert!(sys.poll_nmi());
+    assert!(!sys.peek_nmi());
+    sys.read(0);
+    sys.pulse_nmi(2);
+    assert_eq!(sys.nmi_length(), NmiLength::Two);
+    sys.read(0);
+    sys.read(0);
+    sys.pulse_nmi(100);
+    assert_eq!(sys.nmi_length(), NmiLength::Plenty);
+}
+
+#[test]
+fn events_nmi_release() {
+    let mut sys = Events::new(VecSys::with_code(&[]));
+    sys.pulse_nmi(2);
+    sys.read(0);
+    assert!(sys.nmi_line());
+    sys.read(0);
+    assert!(!sys.nmi_line());
+    assert_eq!(sys.pending(), 0);
+    // The edge outlasts the pulse.
+    assert!(sys.peek_nmi());
+    assert!(sys.poll_nmi());
+    assert!(!sys.peek_nmi());
+
+    // A new pulse outlasts the end of an older one, without a second
+    // edge.
+    sys.pulse_nmi(2);
+    sys.read(0);
+    assert!(sys.poll_nmi());
+    sys.pulse_nmi(4);
+    assert!(!sys.peek_nmi());
+    sys.read(0);
+    sys.read(0);
+    assert!(sys.nmi_line());
+    sys.read(0);
+    sys.read(0);
+    assert!(!sys.nmi_line());
+}
+
+#[test]
+fn events_nmi_short_pulse() {
+    // INC $1000,X; loop: JMP loop
+    let code = [0xfe, 0x00, 0x10, 0x4c, 0x03, 0x02];
+    // nmi: INC $10; RTI
+    let mut sys = Events::new(VecSys::with_code(&code));
+    sys.sys.mem[0x0300..0x0303].copy_from_slice(&[0xe6, 0x10, 0x40]);
+    sys.sys.mem[0xfffa] = 0x00;
+    sys.sys.mem[0xfffb] = 0x03;
+    // Over before the INC is done.
+    sys.schedule(1, |ev| ev.pulse_nmi(2));
+
+    let mut cpu = Nmos::standard();
+    cpu.set_pc(0x0200);
+    while sys.cycle() < 40 {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    assert_eq!(sys.sys.mem[0x10], 1);
+}
+
+#[test]
+fn events_vblank() {
+    // loop: JMP loop
+    let code = [0x4c, 0x00, 0x02];
+    // nmi: INC $10; RTI
+    let mut sys = Events::new(VecSys::with_code(&code));
+    sys.sys.mem[0x0300..0x0303].copy_from_slice(&[0xe6, 0x10, 0x40]);
+    sys.sys.mem[0xfffa] = 0x00;
+    sys.sys.mem[0xfffb] = 0x03;
+    sys.schedule(100, vblank);
+
+    let mut cpu = Nmos::standard();
+    cpu.set_pc(0x0200);
+    while sys.cycle() < 1000 {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    assert_eq!(sys.sys.mem[0x10], 9);
+}
+
+fn vblank(ev: &mut Events<VecSys>) {
+    ev.pulse_nmi(20);
+    let next = ev.cycle() + 100;
+    ev.schedule(next, vblank);
+}
+
+#[test]
+fn events_timer_irq() {
+    // CLI; loop: JMP loop
+    let code = [0x58, 0x4c, 0x01, 0x02];
+    // irq: STA $10
+    let mut sys = Events::new(VecSys::with_code(&code));
+    sys.sys.mem[0x0300..0x0302].copy_from_slice(&[0x85, 0x10]);
+    sys.sys.mem[0xfffe] = 0x00;
+    sys.sys.mem[0xffff] = 0x03;
+    sys.schedule(21, |ev| ev.set_irq(true));
+
+    let mut cpu = Cmos::new();
+    cpu.set_pc(0x0200);
+    cpu.set_flag(Status::I, true);
+    let mut starts = Vec::new();
+    while cpu.pc() != 0x0300 {
+        starts.push(sys.cycle());
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    // The IRQ is seen before the last cycle of the JMP that ends at
+    // cycle 23, and the interrupt sequence takes seven cycles.
+    assert_eq!(starts[starts.len() - 2], 20);
+    assert_eq!(sys.cycle(), 30);
+    assert!(cpu.flag(Status::I));
+}
@@ -0,0 +1,377 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::io::Read;
+
+use robo6502::{Cpu, Ines, LoadError, Mirroring, NesCart, NesSys, Nmos, Sys};
+
+// An iNES file with each 8K of PRG ROM filled with its bank number.
+fn nes_file(mapper: u8, prg_16k: u8, chr_8k: u8) -> Vec<u8> {
+    let mut file = vec![0; 16];
+    file[0..4].copy_from_slice(b"NES\x1a");
+    file[4] = prg_16k;
+    file[5] = chr_8k;
+    file[6] = mapper << 4 | 0x01;
+    file[7] = mapper & 0xf0;
+    for bank in 0..usize::from(prg_16k) * 2 {
+        file.extend_from_slice(&[bank as u8; 0x2000]);
+    }
+    file.resize(file.len() + usize::from(chr_8k) * 0x2000, 0);
+    file
+}
+
+fn nes_sys(mapper: u8, prg_16k: u8, chr_8k: u8) -> NesSys {
+    let ines = Ines::parse(&nes_file(mapper, prg_16k, chr_8k)).unwrap();
+    NesSys::new(NesCart::new(&ines).unwrap())
+}
+
+// The bank number seen in each 8K window.
+fn banks(sys: &mut NesSys) -> [u8; 4] {
+    let mut banks = [0; 4];
+    for (n, bank) in banks.iter_mut().enumerate() {
+        *bank = sys.read(0x8000 + n as u16 * 0x2000).unwrap();
+    }
+    banks
+}
+
+// Writes a value through the MMC1's serial port, a bit at a time.
+fn mmc1_write(sys: &mut NesSys, addr: u16, val: u8) {
+    for bit in 0..5 {
+        sys.write(addr, val >> bit).unwrap();
+        sys.tick();
+    }
+}
+
+fn run_until<F: Fn(&NesSys) -> bool>(sys: &mut NesSys, done: F) -> u64 {
+    let start = sys.cycles();
+    while !done(sys) {
+        sys.tick();
+    }
+    sys.cycles() - start
+}
+
+#[test]
+fn ines_header() {
+    let mut file = nes_file(4, 2, 1);
+    file[6] |= 0x02;
+    let ines = Ines::parse(&file).unwrap();
+    assert!(!ines.nes2);
+    assert_eq!(ines.mapper, 4);
+    assert_eq!(ines.mirroring, Mirroring::Vertical);
+    assert!(ines.battery);
+    assert_eq!(ines.trainer, None);
+    assert_eq!(ines.prg_ram, 0x2000);
+    assert_eq!(ines.chr_ram, 0);
+    assert_eq!(ines.prg.len(), 0x8000);
+    assert_eq!(ines.prg[0x6000], 3);
+    assert_eq!(ines.chr.len(), 0x2000);
+
+    // Junk at the end of the header hides the high nibble of the mapper.
+    let mut file = nes_file(0x41, 1, 0);
+    assert_eq!(Ines::parse(&file).unwrap().mapper, 0x41);
+    file[12..16].copy_from_slice(b"Dude");
+    let ines = Ines::parse(&file).unwrap();
+    assert_eq!(ines.mapper, 1);
+    assert_eq!(ines.chr_ram, 0x2000);
+
+    assert_eq!(Ines::parse(b"NES\x1b"), Err(LoadError::BadMagic));
+    assert_eq!(Ines::parse(b"NES\x1a\x01"), Err(LoadError::Truncated));
+    let file = nes_file(0, 2, 1);
+    assert_eq!(Ines::parse(&file[..0x8000]), Err(LoadError::Truncated));
+    let file = nes_file(0, 0, 1);
+    assert_eq!(Ines::parse(&file), Err(LoadError::Invalid("no PRG ROM")));
+}
+
+#[test]
+fn ines_nes2() {
+    let mut file = nes_file(4, 2, 0);
+    file[6] |= 0x08;
+    file[7] |= 0x08;
+    // Mapper $104, submapper 1.
+    file[8] = 0x11;
+    // 32K of PRG RAM, 8K of it battery-backed; 8K of CHR RAM.
+    file[10] = 0x79;
+    file[11] = 0x07;
+    file[12] = 0x01;
+    let ines = Ines::parse(&file).unwrap();
+    assert!(ines.nes2);
+    assert_eq!(ines.mapper, 0x104);
+    assert_eq!(ines.submapper, 1);
+    assert_eq!(ines.mirroring, Mirroring::FourScreen);
+    assert_eq!(ines.prg_ram, 0x8000 + 0x2000);
+    assert_eq!(ines.chr_ram, 0x2000);
+    assert!(ines.pal);
+    assert_eq!(
+        NesCart::new(&ines).err(),
+        Some(LoadError::Invalid("unsupported mapper"))
+    );
+
+    // An exponent and multiplier: 2^13 * 3 bytes of PRG ROM.
+    let mut file = nes_file(0, 0, 0);
+    file[4] = 13 << 2 | 1;
+    file[7] = 0x08;
+    file[9] = 0x0f;
+    file.resize(16 + 0x6000, 0xea);
+    let ines = Ines::parse(&file).unwrap();
+    assert_eq!(ines.prg.len(), 0x6000);
+    assert_eq!(ines.prg_ram, 0);
+
+    // Sizes too big to count, alone or together.
+    file[4] = 0xff;
+    assert_eq!(
+        Ines::parse(&file).err(),
+        Some(LoadError::Invalid("bad ROM size"))
+    );
+    file[4] = 62 << 2 | 1;
+    file[5] = 62 << 2 | 1;
+    file[9] = 0xff;
+    assert_eq!(
+        Ines::parse(&file).err(),
+        Some(LoadError::Invalid("bad ROM size"))
+    );
+
+    // 8K of PRG ROM, 2^13 bytes, fills every window.
+    for &mapper in &[1, 2, 4] {
+        let mut file = nes_file(mapper, 0, 0);
+        file[4] = 13 << 2;
+        file[7] |= 0x08;
+        file[9] = 0x0f;
+        file.resize(16 + 0x2000, 0xea);
+        let ines = Ines::parse(&file).unwrap();
+        let cart = NesCart::new(&ines).unwrap();
+        assert_eq!(cart.read(0x8000), Some(0xea));
+        assert_eq!(cart.read(0xfffc), Some(0xea));
+    }
+}
+
+#[test]
+fn ines_trainer() {
+    let mut file = nes_file(0, 1, 1);
+    file[6] |= 0x04;
+    let prg = file.split_off(16);
+    file.extend_from_slice(&[0x42; 0x200]);
+    file.extend_from_slice(&prg);
+    let ines = Ines::parse(&file).unwrap();
+    assert_eq!(ines.trainer.as_ref().map(|t| t.len()), Some(0x200));
+    assert_eq!(ines.prg[0], 0);
+
+    let mut sys = NesSys::new(NesCart::new(&ines).unwrap());
+    assert_eq!(sys.read(0x6fff), Some(0x00));
+    assert_eq!(sys.read(0x7000), Some(0x42));
+    assert_eq!(sys.read(0x71ff), Some(0x42));
+}
+
+#[test]
+fn ines_nestest() {
+    let mut file = Vec::new();
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("nestest.nes");
+    std::fs::File::open(path)
+        .unwrap()
+        .read_to_end(&mut file)
+        .unwrap();
+    let ines = Ines::parse(&file).unwrap();
+    let mut sys = NesSys::new(NesCart::new(&ines).unwrap());
+
+    // The automated mode starts at $C000 and ends at $C66E.
+    let mut cpu = Nmos::nes();
+    cpu.set_pc(0xc000);
+    cpu.set_sp(0xfd);
+    for _ in 0..8990 {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    assert_eq!(cpu.pc(), 0xc66e);
+    assert_eq!((sys.ram[0x10], sys.ram[0x11]), (0, 0));
+}
+
+#[test]
+fn ines_nrom() {
+    let mut sys = nes_sys(0, 1, 1);
+    assert_eq!(banks(&mut sys), [0, 1, 0, 1]);
+    let mut sys = nes_sys(0, 2, 1);
+    assert_eq!(banks(&mut sys), [0, 1, 2, 3]);
+
+    sys.write(0x6123, 0x55).unwrap();
+    assert_eq!(sys.cart.prg_ram[0x0123], 0x55);
+    assert_eq!(sys.read(0x0800 + 0x10), Some(0));
+    sys.write(0x1810, 0x66).unwrap();
+    assert_eq!(sys.ram[0x10], 0x66);
+    // Nothing drives the APU registers, so they read as open bus.
+    assert_eq!(sys.read(0x4000), Some(0x66));
+}
+
+#[test]
+fn ines_mmc1() {
+    let mut sys = nes_sys(1, 8, 0);
+    // The last bank starts fixed at $C000.
+    assert_eq!(banks(&mut sys), [0, 1, 14, 15]);
+    mmc1_write(&mut sys, 0xe000, 3);
+    assert_eq!(banks(&mut sys), [6, 7, 14, 15]);
+
+    // Fix the first bank at $8000, and switch $C000.
+    mmc1_write(&mut sys, 0x8000, 0x08 | 0x02);
+    assert_eq!(sys.cart.mirroring(), Mirroring::Vertical);
+    assert_eq!(banks(&mut sys), [0, 1, 6, 7]);
+
+    // 32K mode ignores the low bit of the bank.
+    mmc1_write(&mut sys, 0x8000, 0x03);
+    assert_eq!(sys.cart.mirroring(), Mirroring::Horizontal);
+    assert_eq!(banks(&mut sys), [4, 5, 6, 7]);
+
+    // Writing bit 7 resets the shift register and the PRG mode.
+    sys.write(0x8000, 0x01).unwrap();
+    sys.tick();
+    sys.write(0x8000, 0x80).unwrap();
+    sys.tick();
+    assert_eq!(banks(&mut sys), [6, 7, 14, 15]);
+
+    // Of two writes on consecutive cycles, the second is ignored.
+    for val in &[0, 1, 0, 0] {
+        sys.write(0xe000, *val).unwrap();
+        sys.tick();
+    }
+    sys.write(0xe000, 0).unwrap();
+    sys.write(0xe000, 0).unwrap();
+    assert_eq!(banks(&mut sys), [4, 5, 14, 15]);
+
+    // Bit 4 of the PRG register disables the PRG RAM.
+    sys.write(0x6000, 0x77).unwrap();
+    mmc1_write(&mut sys, 0xe000, 0x10);
+    assert_eq!(sys.read(0x6000), Some(0x01));
+    mmc1_write(&mut sys, 0xe000, 0x00);
+    assert_eq!(sys.read(0x6000), Some(0x77));
+}
+
+#[test]
+fn ines_mmc1_chr() {
+    let mut sys = nes_sys(1, 2, 4);
+    mmc1_write(&mut sys, 0xa000, 3);
+    mmc1_write(&mut sys, 0xc000, 5);
+    // 8K mode.
+    assert_eq!(sys.cart.chr_window(0), 2 * 0x1000);
+    assert_eq!(sys.cart.chr_window(7), 2 * 0x1000 + 0x1c00);
+    mmc1_write(&mut sys, 0x8000, 0x1c);
+    assert_eq!(sys.cart.chr_window(0), 3 * 0x1000);
+    assert_eq!(sys.cart.chr_window(4), 5 * 0x1000);
+}
+
+#[test]
+fn ines_uxrom_cnrom() {
+    let mut sys = nes_sys(2, 8, 0);
+    assert_eq!(banks(&mut sys), [0, 1, 14, 15]);
+    sys.write(0x8000, 5).unwrap();
+    assert_eq!(banks(&mut sys), [10, 11, 14, 15]);
+    sys.write(0xffff, 9).unwrap();
+    assert_eq!(banks(&mut sys), [2, 3, 14, 15]);
+
+    let mut sys = nes_sys(3, 2, 4);
+    sys.write(0x8000, 2).unwrap();
+    assert_eq!(banks(&mut sys), [0, 1, 2, 3]);
+    assert_eq!(sys.cart.chr_window(0), 0x4000);
+    assert_eq!(sys.cart.chr_window(3), 0x4c00);
+}
+
+#[test]
+fn ines_mmc3() {
+    let mut sys = nes_sys(4, 8, 8);
+    assert_eq!(banks(&mut sys), [0, 1, 14, 15]);
+    sys.write(0x8000, 6).unwrap();
+    sys.write(0x8001, 3).unwrap();
+    sys.write(0x8000, 7).unwrap();
+    sys.write(0x8001, 9).unwrap();
+    assert_eq!(banks(&mut sys), [3, 9, 14, 15]);
+    // Swap $8000 and $C000.
+    sys.write(0x8000, 0x40).unwrap();
+    assert_eq!(banks(&mut sys), [14, 9, 3, 15]);
+
+    sys.write(0x8000, 0x80).unwrap();
+    sys.write(0x8001, 0x09).unwrap();
+    sys.write(0x8000, 0x82).unwrap();
+    sys.write(0x8001, 0x21).unwrap();
+    assert_eq!(sys.cart.chr_window(0), 0x21 * 0x400);
+    assert_eq!(sys.cart.chr_window(4), 8 * 0x400);
+    assert_eq!(sys.cart.chr_window(5), 9 * 0x400);
+
+    sys.write(0xa000, 1).unwrap();
+    assert_eq!(sys.cart.mirroring(), Mirroring::Horizontal);
+    // Write-protect the PRG RAM, then disable it.
+    sys.write(0x6000, 0x12).unwrap();
+    sys.write(0xa001, 0xc0).unwrap();
+    sys.write(0x6000, 0x34).unwrap();
+    assert_eq!(sys.read(0x6000), Some(0x12));
+    sys.write(0xa001, 0x00).unwrap();
+    assert_eq!(sys.read(0x7fff), Some(0x00));
+}
+
+#[test]
+fn ines_mmc3_irq() {
+    let mut sys = nes_sys(4, 2, 1);
+    sys.write(0xc000, 3).unwrap();
+    sys.write(0xc001, 0).unwrap();
+    sys.write(0xe001, 0).unwrap();
+    // Reload, then count down to zero.
+    for _ in 0..3 {
+        sys.cart.clock_scanline();
+        assert!(!sys.irq());
+    }
+    sys.cart.clock_scanline();
+    assert!(sys.irq());
+    sys.write(0xe000, 0).unwrap();
+    assert!(!sys.irq());
+
+    // With rendering on, the counter is clocked once a line.
+    let mut sys = nes_sys(4, 2, 1);
+    sys.write(0xc000, 9).unwrap();
+    sys.write(0xc001, 0).unwrap();
+    sys.write(0xe001, 0).unwrap();
+    run_until(&mut sys, |sys| sys.scanline() == 100);
+    assert!(!sys.irq());
+    sys.write(0x2001, 0x18).unwrap();
+    let cycles = run_until(&mut sys, |sys| sys.irq());
+    // The first line reloads the counter, which reaches zero nine lines
+    // later.
+    let line = 341.0 / 3.0;
+    assert!(cycles as f64 > 9.0 * line && (cycles as f64) < 10.0 * line);
+    assert_eq!(sys.scanline(), 109);
+
+    // No lines are counted in vblank.
+    sys.write(0xe000, 0).unwrap();
+    run_until(&mut sys, |sys| sys.scanline() == 235);
+    sys.write(0xc000, 20).unwrap();
+    sys.write(0xc001, 0).unwrap();
+    sys.write(0xe001, 0).unwrap();
+    run_until(&mut sys, |sys| sys.irq());
+    assert_eq!(sys.scanline(), 14);
+}
+
+#[test]
+fn ines_vblank() {
+    let mut sys = nes_sys(0, 1, 1);
+    // Vblank starts on the second dot of line 241, 82182 dots in.
+    let cycles = run_until(&mut sys, |sys| sys.scanline() == 241);
+    assert_eq!(cycles, 27_394);
+    assert_eq!(sys.read(0x2002), Some(0x80));
+    assert_eq!(sys.read(0x3ffa), Some(0x00));
+    assert!(!sys.peek_nmi());
+
+    // Reading $2002 cleared the flag, so there's no NMI until the next
+    // vblank.
+    sys.write(0x2000, 0x80).unwrap();
+    assert!(!sys.poll_nmi());
+    run_until(&mut sys, |sys| sys.scanline() == 0);
+    run_until(&mut sys, |sys| sys.scanline() == 241);
+    sys.tick();
+    assert!(sys.poll_nmi());
+    assert!(!sys.poll_nmi());
+
+    // Turning NMI on in vblank gives one at once.
+    sys.write(0x2000, 0x00).unwrap();
+    sys.write(0x2000, 0x80).unwrap();
+    assert!(sys.poll_nmi());
+}
@@ -200,9 +200,9 @@ fn polling_impl<C: Cpu>(cpu: C) {
     }
 
     // Branch ops
-    for op in 0..256 {
+    for (op, mode) in ADDR_MODES.iter().enumerate() {
         use self::common::CpuAddrMode::*;
-        let addr_mode = match ADDR_MODES[op] {
+        let addr_mode = match *mode {
             S(mode) => mode,
             D(nmode, cmode) => {
                 if cpu.is_nmos() {
@@ -744,7 +744,7 @@ struct IntSys {
 
 impl IntSys {
     fn new(test: IntTest) -> IntSys {
-        #[cfg_attr(rustfmt, rustfmt_skip)]
+        #[rustfmt::skip]
         let mut sys = IntSys {
             desc: test.desc, exp_pc: test.exp_pc,
             nmi_cycle: test.nmi_on, irq_cycle: test.irq_on,
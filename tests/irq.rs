@@ -0,0 +1,115 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cmos, Cpu, IrqController, Nmos, Status, Sys};
+
+use self::common::VecSys;
+
+mod common;
+
+#[test]
+fn irq_wired_or() {
+    let mut sys = IrqController::new(ram());
+    let via = sys.add_source("via");
+    let acia = sys.add_source("acia");
+    assert_eq!(sys.source("acia"), Some(acia));
+    assert_eq!(sys.name(via), "via");
+    assert!(!sys.irq());
+    sys.set(via, true);
+    sys.set(acia, true);
+    assert!(sys.irq());
+    sys.set(via, false);
+    assert!(sys.irq());
+    sys.set(acia, false);
+    assert!(!sys.irq());
+    assert_eq!(sys.stats(via).asserts, 1);
+}
+
+#[test]
+fn irq_latency() {
+    let mut sys = IrqController::new(ram());
+    let timer = sys.add_source("timer");
+    let mut cpu = Nmos::standard();
+    cpu.set_pc(0x0200);
+    cpu.set_flag(Status::I, false);
+
+    // Asserted at cycle 3, before the second JMP; the interrupt
+    // sequence starts at cycle 6 and reads the vector on cycle 11.
+    cpu.run_instruction(&mut sys).unwrap();
+    sys.set(timer, true);
+    cpu.run_instruction(&mut sys).unwrap();
+    cpu.run_instruction(&mut sys).unwrap();
+    assert_eq!(cpu.pc(), 0x0300);
+    let stats = sys.stats(timer);
+    assert_eq!(stats.asserted_at, Some(3));
+    assert_eq!(stats.taken, 1);
+    assert_eq!(stats.last_latency, Some(8));
+
+    // Still asserted, but this is not a new assertion.
+    cpu.set_flag(Status::I, false);
+    cpu.run_instruction(&mut sys).unwrap();
+    assert_eq!(sys.stats(timer).taken, 1);
+}
+
+#[test]
+fn irq_reset_stats() {
+    let mut sys = IrqController::new(ram());
+    let timer = sys.add_source("timer");
+    let mut cpu = Nmos::standard();
+    cpu.set_pc(0x0200);
+    cpu.set_flag(Status::I, false);
+
+    cpu.run_instruction(&mut sys).unwrap();
+    sys.set(timer, true);
+    sys.reset_stats();
+    cpu.run_instruction(&mut sys).unwrap();
+    cpu.run_instruction(&mut sys).unwrap();
+    assert_eq!(cpu.pc(), 0x0300);
+    let stats = sys.stats(timer);
+    assert_eq!(stats.asserts, 0);
+    assert_eq!(stats.taken, 1);
+    assert_eq!(stats.last_latency, Some(8));
+
+    sys.set(timer, false);
+    sys.reset_stats();
+    assert_eq!(sys.stats(timer).asserted_at, None);
+}
+
+#[test]
+fn irq_priority() {
+    let mut sys = IrqController::new(ram());
+    let high = sys.add_source("high");
+    let low = sys.add_source("low");
+    let plain = sys.add_source("plain");
+    sys.set_vector(high, Some(0x0400));
+    sys.set_vector(low, Some(0x0500));
+
+    let take = |sys: &mut IrqController<VecSys>| {
+        let mut cpu = Cmos::new();
+        cpu.set_pc(0x0200);
+        cpu.set_flag(Status::I, false);
+        cpu.run_instruction(sys).unwrap();
+        cpu.run_instruction(sys).unwrap();
+        cpu.pc()
+    };
+
+    sys.set(plain, true);
+    assert_eq!(take(&mut sys), 0x0300);
+    sys.set(low, true);
+    assert_eq!(take(&mut sys), 0x0500);
+    sys.set(high, true);
+    assert_eq!(take(&mut sys), 0x0400);
+}
+
+// JMP $0200 at $0200, and the IRQ vector set to $0300.
+fn ram() -> VecSys {
+    let mut sys = VecSys::with_code(&[0x4c, 0x00, 0x02]);
+    sys.mem[0xfffe] = 0x00;
+    sys.mem[0xffff] = 0x03;
+    sys
+}
@@ -0,0 +1,193 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cpu, Kim1, LoadError, Sys};
+
+// Scans "123456" onto the LEDs the way the monitor does, reading the
+// second row of the keypad into $00 after each scan.
+#[rustfmt::skip]
+const SCAN: [u8; 0x46] = [
+    0xa9, 0x7f,             // LDA #$7f
+    0x8d, 0x41, 0x17,       // STA PADD
+    0xa9, 0x1e,             // LDA #$1e
+    0x8d, 0x43, 0x17,       // STA PBDD
+    // scan:
+    0xa2, 0x09,             // LDX #$09
+    0xa0, 0x00,             // LDY #$00
+    // digit:
+    0xb9, 0x40, 0x1c,       // LDA table,Y
+    0x8e, 0x42, 0x17,       // STX SBD
+    0x8d, 0x40, 0x17,       // STA SAD
+    0xa9, 0x20,             // LDA #$20
+    // delay:
+    0x38,                   // SEC
+    0xe9, 0x01,             // SBC #$01
+    0xd0, 0xfb,             // BNE delay
+    0x8d, 0x40, 0x17,       // STA SAD
+    0xe8,                   // INX
+    0xe8,                   // INX
+    0xc8,                   // INY
+    0xc0, 0x06,             // CPY #$06
+    0xd0, 0xe6,             // BNE digit
+    0xa9, 0x00,             // LDA #$00
+    0x8d, 0x41, 0x17,       // STA PADD
+    0xa9, 0x03,             // LDA #$03
+    0x8d, 0x42, 0x17,       // STA SBD
+    0xad, 0x40, 0x17,       // LDA SAD
+    0x85, 0x00,             // STA $00
+    0xa9, 0x7f,             // LDA #$7f
+    0x8d, 0x41, 0x17,       // STA PADD
+    0x4c, 0x0a, 0x1c,       // JMP scan
+    0xea,                   // NOP
+    // table:
+    0x06, 0x5b, 0x4f, 0x66, 0x6d, 0x7d,
+];
+
+// Echoes bytes at 2400 baud, bit-banged with delay loops.
+#[rustfmt::skip]
+const ECHO: [u8; 0x46] = [
+    0xa9, 0x01,             // LDA #$01
+    0x8d, 0x42, 0x17,       // STA SBD
+    0x8d, 0x43, 0x17,       // STA PBDD
+    // wait:
+    0x2c, 0x40, 0x17,       // BIT SAD
+    0x30, 0xfb,             // BMI wait
+    0xa0, 0x28,             // LDY #$28
+    // half:
+    0x88,                   // DEY
+    0xd0, 0xfd,             // BNE half
+    0xa2, 0x08,             // LDX #$08
+    // in_bit:
+    0xa0, 0x50,             // LDY #$50
+    // in_delay:
+    0x88,                   // DEY
+    0xd0, 0xfd,             // BNE in_delay
+    0xad, 0x40, 0x17,       // LDA SAD
+    0x0a,                   // ASL A
+    0x66, 0x00,             // ROR $00
+    0xca,                   // DEX
+    0xd0, 0xf2,             // BNE in_bit
+    0xa9, 0x00,             // LDA #$00
+    0x8d, 0x42, 0x17,       // STA SBD
+    0xa2, 0x08,             // LDX #$08
+    // out_bit:
+    0xa0, 0x50,             // LDY #$50
+    // out_delay:
+    0x88,                   // DEY
+    0xd0, 0xfd,             // BNE out_delay
+    0xa9, 0x00,             // LDA #$00
+    0x46, 0x00,             // LSR $00
+    0x2a,                   // ROL A
+    0x8d, 0x42, 0x17,       // STA SBD
+    0xca,                   // DEX
+    0xd0, 0xf0,             // BNE out_bit
+    0xa0, 0x50,             // LDY #$50
+    // stop_delay:
+    0x88,                   // DEY
+    0xd0, 0xfd,             // BNE stop_delay
+    0xa9, 0x01,             // LDA #$01
+    0x8d, 0x42, 0x17,       // STA SBD
+    0x4c, 0x08, 0x1c,       // JMP wait
+];
+
+// The code goes at $1C00, with an NMI handler that loops at $1C50.
+fn rom(code: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0xea; 0x800];
+    rom[0x400..(0x400 + code.len())].copy_from_slice(code);
+    rom[0x450..0x453].copy_from_slice(&[0x4c, 0x50, 0x1c]);
+    rom[0x7fa] = 0x50;
+    rom[0x7fb] = 0x1c;
+    rom[0x7fc] = 0x00;
+    rom[0x7fd] = 0x1c;
+    rom
+}
+
+#[test]
+fn kim1_keypad() {
+    let mut kim = Kim1::keypad(&rom(&SCAN));
+    kim.run_cycles(10_000);
+    assert_eq!(kim.led_text(), "123456");
+    assert_eq!(kim.leds()[0], 0x06);
+    assert_eq!(kim.sys.read(0x0000), Some(0xff));
+
+    // The A key is on the second row, at PA3.
+    kim.press_key(0x0a);
+    kim.run_cycles(5_000);
+    assert_eq!(kim.sys.read(0x0000), Some(0xf7));
+    kim.release_key();
+    kim.run_cycles(5_000);
+    assert_eq!(kim.sys.read(0x0000), Some(0xff));
+}
+
+#[test]
+fn kim1_tty() {
+    let mut kim = Kim1::tty(&rom(&ECHO), 2400);
+    kim.send(b"KIM");
+    kim.run_cycles(40_000);
+    assert_eq!(kim.pending_input(), 0);
+    assert_eq!(kim.take_output(), b"KIM");
+}
+
+#[test]
+fn kim1_tty_jumper() {
+    for &tty in &[false, true] {
+        let mut kim = if tty {
+            Kim1::tty(&rom(&[]), 300)
+        } else {
+            Kim1::keypad(&rom(&[]))
+        };
+        // Select the jumper's output of the decoder.
+        kim.sys.write(0x1743, 0x1e);
+        kim.sys.write(0x1742, 0x07);
+        assert_eq!(kim.sys.read(0x1740), Some(if tty { 0xfe } else { 0xff }));
+    }
+}
+
+#[test]
+fn kim1_memory_map() {
+    let mut kim = Kim1::keypad(&rom(&[]));
+    // The vectors come from the top of the ROM.
+    assert_eq!(kim.sys.read(0xfffc), Some(0x00));
+    assert_eq!(kim.sys.read(0xfffd), Some(0x1c));
+    kim.sys.write(0x03ff, 0x12);
+    assert_eq!(kim.sys.read(0x23ff), Some(0x12));
+    assert_eq!(
+        kim.load(0x03ff, &[0x12, 0x34]),
+        Err(LoadError::Invalid("data past end of RAM"))
+    );
+    // The NMI vector at $17FA is in the 6530-002.
+    kim.sys.write(0x17fa, 0x34);
+    assert_eq!(kim.sys.riot002.ram[0x3a], 0x34);
+    kim.sys.write(0x17b0, 0x56);
+    assert_eq!(kim.sys.riot003.ram[0x30], 0x56);
+
+    // The timer is written with A2 set, and read back at $1706.
+    kim.sys.write(0x1704, 0x10);
+    kim.sys.read(0x0000);
+    assert_eq!(kim.sys.read(0x1706), Some(0x0f));
+}
+
+#[test]
+fn kim1_st_and_sst() {
+    let mut kim = Kim1::keypad(&rom(&[0x4c, 0x00, 0x1c]));
+    kim.run_cycles(10);
+    kim.press_st();
+    kim.run_cycles(20);
+    assert_eq!(kim.cpu.pc(), 0x1c50);
+
+    // Single-stepping code in RAM, but not the monitor.
+    kim.load(0x0200, &[0xea, 0xea, 0xea]).unwrap();
+    kim.cpu.set_pc(0x0200);
+    kim.set_sst(true);
+    kim.run_instruction();
+    assert_eq!(kim.cpu.pc(), 0x0201);
+    kim.run_instruction();
+    assert_eq!(kim.cpu.pc(), 0x1c50);
+    kim.run_cycles(20);
+    assert_eq!(kim.cpu.pc(), 0x1c50);
+}
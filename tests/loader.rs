@@ -0,0 +1,286 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{LoadError, Program, Segment, Sys};
+
+const CODE: [u8; 6] = [0xa9, 0x01, 0x8d, 0x00, 0x02, 0x60];
+
+fn segment(addr: u16, data: &[u8]) -> Segment {
+    Segment {
+        addr,
+        data: data.to_vec(),
+    }
+}
+
+#[test]
+fn loader_prg() {
+    // 10 SYS 2064
+    #[rustfmt::skip]
+    let file = [
+        0x01, 0x08,
+        0x0c, 0x08, 0x0a, 0x00, 0x9e, b' ', b'2', b'0', b'6', b'4', 0x00,
+        0x00, 0x00,
+    ];
+    let prog = Program::from_prg(&file).unwrap();
+    assert_eq!(prog.entry, Some(2064));
+    assert_eq!(prog.ranges(), [0x0801..=0x080d]);
+
+    let mut file = vec![0x00, 0xc0];
+    file.extend_from_slice(&CODE);
+    let prog = Program::from_prg(&file).unwrap();
+    assert_eq!(prog.entry, None);
+    assert_eq!(prog.segments, [segment(0xc000, &CODE)]);
+    let mut sys = RamSys::new();
+    assert_eq!(prog.load(&mut sys), Some(()));
+    assert_eq!(&sys.mem[0xc000..0xc006], &CODE[..]);
+    assert_eq!(segment(0xc000, &[]).range(), None);
+
+    // Ending at $FFFF, with the reset and IRQ vectors.
+    let prog = Program::from_prg(&[0xfc, 0xff, 0, 0x80, 0, 0x80]).unwrap();
+    assert_eq!(prog.load(&mut sys), Some(()));
+    assert_eq!(&sys.mem[0xfffc..], &[0, 0x80, 0, 0x80]);
+
+    assert_eq!(Program::from_prg(&[0x01]), Err(LoadError::Truncated));
+    let mut file = vec![0xff, 0xff];
+    file.extend_from_slice(&CODE);
+    assert_eq!(
+        Program::from_prg(&file),
+        Err(LoadError::Invalid("data past $FFFF"))
+    );
+}
+
+#[test]
+fn loader_apple_dos() {
+    let mut file = vec![0x00, 0x03, 0x06, 0x00];
+    file.extend_from_slice(&CODE);
+    // Padding to the end of the sector.
+    file.extend_from_slice(&[0; 8]);
+    let prog = Program::from_apple_dos(&file).unwrap();
+    assert_eq!(prog.entry, Some(0x0300));
+    assert_eq!(prog.segments, [segment(0x0300, &CODE)]);
+
+    assert_eq!(
+        Program::from_apple_dos(&file[..9]),
+        Err(LoadError::Truncated)
+    );
+}
+
+#[test]
+fn loader_xex() {
+    #[rustfmt::skip]
+    let file = [
+        0xff, 0xff,
+        0x00, 0x20, 0x05, 0x20,
+        0xa9, 0x01, 0x8d, 0x00, 0x02, 0x60,
+        // INITAD
+        0xe2, 0x02, 0xe3, 0x02, 0x00, 0x20,
+        0xff, 0xff,
+        0x00, 0x30, 0x00, 0x30, 0x60,
+        // RUNAD
+        0xe0, 0x02, 0xe1, 0x02, 0x00, 0x30,
+    ];
+    let prog = Program::from_xex(&file).unwrap();
+    assert_eq!(
+        prog.ranges(),
+        [
+            0x2000..=0x2005,
+            0x02e2..=0x02e3,
+            0x3000..=0x3000,
+            0x02e0..=0x02e1
+        ]
+    );
+    assert_eq!(prog.inits, [(2, 0x2000)]);
+    assert_eq!(prog.entry, Some(0x3000));
+    let mut sys = RamSys::new();
+    sys.pause = true;
+    assert_eq!(prog.load(&mut sys), None);
+    assert_eq!(prog.load(&mut sys), Some(()));
+    assert_eq!(&sys.mem[0x02e0..0x02e4], &[0x00, 0x30, 0x00, 0x20]);
+
+    assert_eq!(Program::from_xex(&file[2..]), Err(LoadError::BadMagic));
+    assert_eq!(
+        Program::from_xex(&file[..file.len() - 1]),
+        Err(LoadError::Truncated)
+    );
+    assert!(Program::from_xex(&[0xff, 0xff, 0x01, 0x20, 0x00, 0x20]).is_err());
+}
+
+#[test]
+fn loader_ihex() {
+    let text = "\
+        :020000040000FA\n\
+        :05100000A9018D0002B2\n\
+        :01100500608A\n\
+        :01200000429D\n\
+        :0400000500001000E7\n\
+        :00000001FF\n";
+    let prog = Program::from_ihex(text).unwrap();
+    assert_eq!(
+        prog.segments,
+        [segment(0x1000, &CODE), segment(0x2000, &[0x42])]
+    );
+    assert_eq!(prog.entry, Some(0x1000));
+
+    assert_eq!(Program::from_ihex("S9031000EC"), Err(LoadError::BadMagic));
+    assert_eq!(
+        Program::from_ihex(":01100500608A\n"),
+        Err(LoadError::Truncated)
+    );
+    assert_eq!(
+        Program::from_ihex(":01100500608B\n:00000001FF\n"),
+        Err(LoadError::Invalid("bad checksum"))
+    );
+    assert_eq!(
+        Program::from_ihex(":020000040001F9\n:01200000429D\n:00000001FF\n"),
+        Err(LoadError::Invalid("data past $FFFF"))
+    );
+    for entry in &[":04000003F000000009\n", ":0400000500010000F6\n"] {
+        assert_eq!(
+            Program::from_ihex(entry),
+            Err(LoadError::Invalid("entry past $FFFF"))
+        );
+    }
+}
+
+#[test]
+fn loader_srec() {
+    let text = "\
+        S00600004844521B\n\
+        S1081000A9018D0002AE\n\
+        S10410056086\n\
+        S2050020004298\n\
+        S5030002FA\n\
+        S9031000EC\n";
+    let prog = Program::from_srec(text).unwrap();
+    assert_eq!(
+        prog.segments,
+        [segment(0x1000, &CODE), segment(0x2000, &[0x42])]
+    );
+    assert_eq!(prog.entry, Some(0x1000));
+
+    assert_eq!(Program::from_srec(":00000001FF"), Err(LoadError::BadMagic));
+    assert_eq!(
+        Program::from_srec("S10410056087\n"),
+        Err(LoadError::Invalid("bad checksum"))
+    );
+    assert_eq!(
+        Program::from_srec("S3060001000042B6\n"),
+        Err(LoadError::Invalid("data past $FFFF"))
+    );
+    assert_eq!(
+        Program::from_srec("S1041005608\n"),
+        Err(LoadError::Invalid("bad hex digit"))
+    );
+}
+
+#[test]
+fn loader_o65() {
+    #[rustfmt::skip]
+    let mut file = vec![
+        0x01, 0x00, b'o', b'6', b'5', 0x00,
+        // Mode, then the text at $1000, the data at $2000, and the BSS
+        // after it.
+        0x00, 0x00,
+        0x00, 0x10, 0x0a, 0x00,
+        0x00, 0x20, 0x02, 0x00,
+        0x02, 0x20, 0x10, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+        // A filename option.
+        0x04, 0x00, b'a', 0x00,
+        0x00,
+    ];
+    #[rustfmt::skip]
+    let text = [
+        0xad, 0x00, 0x20,       // LDA data
+        0x4c, 0x00, 0x10,       // JMP text
+        0xa9, 0x02,             // LDA #<bss
+        0xa2, 0x20,             // LDX #>bss
+    ];
+    file.extend_from_slice(&text);
+    file.extend_from_slice(&[0x00, 0x10]);
+    // No undefined references.
+    file.extend_from_slice(&[0x00, 0x00]);
+    #[rustfmt::skip]
+    file.extend_from_slice(&[
+        0x02, 0x83,
+        0x03, 0x82,
+        0x03, 0x24,
+        0x02, 0x44, 0x02,
+        0x00,
+    ]);
+    file.extend_from_slice(&[0x01, 0x82, 0x00]);
+    // No exported globals.
+    file.extend_from_slice(&[0x00, 0x00]);
+
+    let prog = Program::from_o65(&file, 0x3000).unwrap();
+    assert_eq!(prog.entry, Some(0x3000));
+    #[rustfmt::skip]
+    let relocated = [
+        0xad, 0x0a, 0x30,
+        0x4c, 0x00, 0x30,
+        0xa9, 0x0c,
+        0xa2, 0x30,
+    ];
+    assert_eq!(
+        prog.segments,
+        [segment(0x3000, &relocated), segment(0x300a, &[0x00, 0x30])]
+    );
+    // The data always follows the text.
+    let prog = Program::from_o65(&file, 0x1000).unwrap();
+    assert_eq!(prog.ranges(), [0x1000..=0x1009, 0x100a..=0x100b]);
+    assert_eq!(
+        prog.segments[0].data[..6],
+        [0xad, 0x0a, 0x10, 0x4c, 0x00, 0x10]
+    );
+
+    assert_eq!(
+        Program::from_o65(&file[1..], 0x3000),
+        Err(LoadError::BadMagic)
+    );
+    assert_eq!(
+        Program::from_o65(&file[..40], 0x3000),
+        Err(LoadError::Truncated)
+    );
+    let mut undefined = file.clone();
+    undefined[43] = 1;
+    assert_eq!(
+        Program::from_o65(&undefined, 0x3000),
+        Err(LoadError::Invalid("undefined o65 references"))
+    );
+}
+
+// 64K of RAM, which can pause one write.
+struct RamSys {
+    mem: Vec<u8>,
+    pause: bool,
+}
+
+impl RamSys {
+    fn new() -> RamSys {
+        RamSys {
+            mem: vec![0u8; 0x10000],
+            pause: false,
+        }
+    }
+}
+
+impl Sys for RamSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        Some(self.mem[addr as usize])
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        if self.pause {
+            self.pause = false;
+            return None;
+        }
+        self.mem[addr as usize] = val;
+        Some(())
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{write_reg_log, Apu, Cpu, LoadError, Nsf, NsfPlayer, RegWrite};
+
+#[rustfmt::skip]
+const DRIVER: [u8; 0x1d] = [
+    // init:
+    0x85, 0x00,             // STA $00
+    0xa9, 0x01,             // LDA #$01
+    0x8d, 0x15, 0x40,       // STA $4015
+    0x60,                   // RTS
+    0xea, 0xea, 0xea, 0xea, 0xea, 0xea, 0xea, 0xea,
+    // play:
+    0xe6, 0x01,             // INC $01
+    0xa5, 0x00,             // LDA $00
+    0x8d, 0x00, 0x40,       // STA $4000
+    0xa5, 0x01,             // LDA $01
+    0x8d, 0x02, 0x40,       // STA $4002
+    0x60,                   // RTS
+];
+
+fn nsf_file(load: u16, banks: [u8; 8], data: &[u8]) -> Vec<u8> {
+    let mut file = vec![0; 0x80];
+    file[0..5].copy_from_slice(b"NESM\x1a");
+    file[5] = 1;
+    file[6] = 2;
+    file[7] = 1;
+    file[8..10].copy_from_slice(&load.to_le_bytes());
+    file[0x0a..0x0c].copy_from_slice(&0x8000u16.to_le_bytes());
+    file[0x0c..0x0e].copy_from_slice(&0x8010u16.to_le_bytes());
+    file[0x0e..0x13].copy_from_slice(b"Title");
+    file[0x6e..0x70].copy_from_slice(&16666u16.to_le_bytes());
+    file[0x70..0x78].copy_from_slice(&banks);
+    file.extend_from_slice(data);
+    file
+}
+
+#[test]
+fn nsf_header() {
+    let nsf = Nsf::parse(&nsf_file(0x8000, [0; 8], &DRIVER)).unwrap();
+    assert_eq!(nsf.songs, 2);
+    assert_eq!(nsf.start_song, 0);
+    assert_eq!(nsf.init_addr, 0x8000);
+    assert_eq!(nsf.play_addr, 0x8010);
+    assert_eq!(nsf.title, "Title");
+    assert_eq!(nsf.banks, None);
+    assert!(!nsf.pal);
+    assert_eq!(nsf.frame_cycles(), 29828);
+    assert_eq!(nsf.data, &DRIVER[..]);
+
+    // No rate given.
+    let mut file = nsf_file(0x8000, [0; 8], &DRIVER);
+    file[0x6e..0x70].copy_from_slice(&[0, 0]);
+    let nsf = Nsf::parse(&file).unwrap();
+    assert_eq!(nsf.frame_cycles(), 29780);
+    let mut player = NsfPlayer::new(&nsf);
+    player.init(0);
+    player.run_frames(1);
+    assert_eq!(player.log().len(), 3);
+
+    assert_eq!(Nsf::parse(b"NESM"), Err(LoadError::BadMagic));
+    assert_eq!(Nsf::parse(b"NESM\x1a\x01"), Err(LoadError::Truncated));
+    assert!(Nsf::parse(&nsf_file(0x6000, [0; 8], &DRIVER)).is_err());
+    assert_eq!(
+        Nsf::parse(&nsf_file(0x8000, [0, 1, 0, 0, 0, 0, 0, 0], &[])),
+        Err(LoadError::Invalid("no data"))
+    );
+}
+
+#[test]
+fn nsf_play() {
+    let nsf = Nsf::parse(&nsf_file(0x8000, [0; 8], &DRIVER)).unwrap();
+    let mut player = NsfPlayer::new(&nsf);
+    player.init(1);
+    assert_eq!(player.sys.ram[0], 1);
+    assert_eq!(player.log().len(), 1);
+    assert_eq!(player.log()[0].addr, 0x4015);
+    player.take_log();
+
+    player.run_frames(3);
+    let log = player.take_log();
+    let writes: Vec<_> = log.iter().map(|w| (w.addr, w.val)).collect();
+    assert_eq!(
+        writes,
+        [
+            (0x4000, 1),
+            (0x4002, 1),
+            (0x4000, 1),
+            (0x4002, 2),
+            (0x4000, 1),
+            (0x4002, 3)
+        ]
+    );
+    // PLAY is called exactly once a frame.
+    assert_eq!(log[2].cycle - log[0].cycle, 29828);
+    assert_eq!(log[4].cycle - log[2].cycle, 29828);
+
+    // Playing again gives the same log.
+    let restart = player.cycles();
+    player.init(1);
+    player.run_frames(3);
+    let again = player.take_log();
+    assert_eq!(again.len(), 7);
+    for (a, b) in again[1..].iter().zip(&log) {
+        assert_eq!(
+            (a.cycle - restart, a.addr, a.val),
+            (b.cycle, b.addr, b.val)
+        );
+    }
+}
+
+#[test]
+fn nsf_slow_play() {
+    #[rustfmt::skip]
+    let play = [
+        0xe6, 0x00,             // INC $00
+        0xa0, 0x20,             // LDY #$20
+        0xa2, 0x00,             // LDX #$00
+        0xca,                   // DEX
+        0xd0, 0xfd,             // BNE $8016
+        0x88,                   // DEY
+        0xd0, 0xf8,             // BNE $8014
+        0x60,                   // RTS
+    ];
+    let mut data = vec![0x60; 0x10];
+    data.extend_from_slice(&play);
+    let nsf = Nsf::parse(&nsf_file(0x8000, [0; 8], &data)).unwrap();
+    let mut player = NsfPlayer::new(&nsf);
+    player.init(0);
+    assert_eq!(player.cpu.sp(), 0xfd);
+    // PLAY takes about a frame and a half, so it runs every other
+    // frame, and leaves nothing on the stack.
+    player.run_frames(4);
+    assert_eq!(player.sys.ram[0], 2);
+    assert_eq!(player.cpu.sp(), 0xfd);
+}
+
+#[test]
+fn nsf_bankswitch() {
+    #[rustfmt::skip]
+    let init = [
+        0xad, 0x00, 0x90,       // LDA $9000
+        0x8d, 0x10, 0x40,       // STA $4010
+        0xa9, 0x00,             // LDA #$00
+        0x8d, 0xf9, 0x5f,       // STA $5FF9
+        0xad, 0x00, 0x90,       // LDA $9000
+        0x8d, 0x11, 0x40,       // STA $4011
+        0x60,                   // RTS
+    ];
+    let mut data = vec![0; 0x2000];
+    data[..init.len()].copy_from_slice(&init);
+    data[0x1000] = 0x77;
+    let file = nsf_file(0x8000, [0, 1, 0, 0, 0, 0, 0, 0], &data);
+    let nsf = Nsf::parse(&file).unwrap();
+    assert_eq!(nsf.banks, Some([0, 1, 0, 0, 0, 0, 0, 0]));
+
+    let mut player = NsfPlayer::new(&nsf);
+    player.init(0);
+    let log = player.log();
+    let writes: Vec<_> = log.iter().map(|w| (w.addr, w.val)).collect();
+    assert_eq!(writes, [(0x4010, 0x77), (0x4011, 0xad)]);
+}
+
+#[test]
+fn nsf_nsfe() {
+    let mut file = b"NSFE".to_vec();
+    let mut chunk = |id: &[u8], data: &[u8]| {
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        file.extend_from_slice(id);
+        file.extend_from_slice(data);
+    };
+    chunk(b"INFO", &[0x00, 0x80, 0x00, 0x80, 0x10, 0x80, 0, 0, 3, 2]);
+    chunk(b"auth", b"Song\0Artist\0");
+    chunk(b"text", b"skipped");
+    chunk(b"DATA", &DRIVER);
+    chunk(b"NEND", &[]);
+    let nsf = Nsf::parse(&file).unwrap();
+    assert_eq!(nsf.songs, 3);
+    assert_eq!(nsf.start_song, 2);
+    assert_eq!(nsf.play_addr, 0x8010);
+    assert_eq!(nsf.title, "Song");
+    assert_eq!(nsf.artist, "Artist");
+    assert_eq!(nsf.copyright, "");
+    assert_eq!(nsf.data, &DRIVER[..]);
+
+    let mut player = NsfPlayer::new(&nsf);
+    player.init(2);
+    player.run_frames(1);
+    assert_eq!(player.log().len(), 3);
+
+    assert_eq!(
+        Nsf::parse(&file[..file.len() - 4]),
+        Err(LoadError::Truncated)
+    );
+    let mut bad = file.clone();
+    let text = file.windows(4).position(|id| id == b"text").unwrap();
+    bad[text..text + 4].copy_from_slice(b"TEXT");
+    assert_eq!(
+        Nsf::parse(&bad),
+        Err(LoadError::Invalid("unknown NSFe chunk"))
+    );
+}
+
+#[test]
+fn nsf_apu() {
+    #[rustfmt::skip]
+    let tone = [
+        0xa9, 0x01,             // LDA #$01
+        0x8d, 0x15, 0x40,       // STA $4015
+        0xa9, 0xbf,             // LDA #$bf
+        0x8d, 0x00, 0x40,       // STA $4000
+        0xa9, 0xfd,             // LDA #$fd
+        0x8d, 0x02, 0x40,       // STA $4002
+        0xa9, 0x00,             // LDA #$00
+        0x8d, 0x03, 0x40,       // STA $4003
+        0x60,                   // RTS
+    ];
+    let mut data = vec![0x60; 0x20];
+    data[..tone.len()].copy_from_slice(&tone);
+    let nsf = Nsf::parse(&nsf_file(0x8000, [0; 8], &data)).unwrap();
+    let mut player = NsfPlayer::new(&nsf);
+    player.sys.apu = Some(Apu::new(44_100));
+    player.init(0);
+    player.run_frames(60);
+    let samples = player.sys.apu.as_mut().unwrap().take_samples();
+    assert!(samples.len() > 44_000);
+    assert!(samples.iter().any(|&s| s > 0.05));
+}
+
+#[test]
+fn nsf_log_text() {
+    let log = [
+        RegWrite {
+            cycle: 7,
+            addr: 0x4015,
+            val: 0x0f,
+        },
+        RegWrite {
+            cycle: 29835,
+            addr: 0x4000,
+            val: 0xbf,
+        },
+    ];
+    let mut text = Vec::new();
+    write_reg_log(&mut text, &log).unwrap();
+    assert_eq!(text, b"7 4015 0f\n29835 4000 bf\n");
+}
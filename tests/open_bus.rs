@@ -0,0 +1,79 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cmos, Cpu, MappedSys, Nmos, OpenBus, Sys};
+
+#[test]
+fn open_bus_operand() {
+    // LDA $c000
+    let code = [0xad, 0x00, 0xc0];
+    assert_eq!(run(Nmos::standard(), &code, 1).a(), 0xc0);
+    assert_eq!(run(Cmos::new(), &code, 1).a(), 0xc0);
+}
+
+#[test]
+fn open_bus_dummy_read() {
+    // LDX #$20; LDA $0ff0,X
+    let code = [0xa2, 0x20, 0xbd, 0xf0, 0x0f];
+    // NMOS reads $0f10 before the final read
+    assert_eq!(run(Nmos::standard(), &code, 2).a(), 0x5a);
+    // CMOS reads the high operand byte again
+    assert_eq!(run(Cmos::new(), &code, 2).a(), 0x0f);
+}
+
+#[test]
+fn open_bus_write() {
+    let mut sys = OpenBus::new(HoleSys::new(&[]));
+    sys.write(0x2000, 0x77).unwrap();
+    assert_eq!(sys.read(0x3000), Some(0x77));
+    assert_eq!(sys.read(0x0f10), Some(0x5a));
+    assert_eq!(sys.read(0x3000), Some(0x5a));
+    assert_eq!(sys.data(), 0x5a);
+}
+
+fn run<C: Cpu>(mut cpu: C, code: &[u8], count: usize) -> C {
+    let mut sys = OpenBus::new(HoleSys::new(code));
+    cpu.set_pc(0x0200);
+    for _ in 0..count {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    cpu
+}
+
+// RAM from $0000-$0fff; nothing else is mapped.
+struct HoleSys {
+    mem: Vec<u8>,
+}
+
+impl HoleSys {
+    fn new(code: &[u8]) -> HoleSys {
+        let mut mem = vec![0u8; 0x1000];
+        mem[0x0200..(0x0200 + code.len())].copy_from_slice(code);
+        mem[0x0f10] = 0x5a;
+        HoleSys { mem }
+    }
+}
+
+impl Sys for HoleSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        Some(self.mem[addr as usize])
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        if self.mapped(addr) {
+            self.mem[addr as usize] = val;
+        }
+        Some(())
+    }
+}
+
+impl MappedSys for HoleSys {
+    fn mapped(&self, addr: u16) -> bool {
+        addr < 0x1000
+    }
+}
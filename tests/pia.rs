@@ -0,0 +1,162 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cpu, Device, Nmos, Pia, Sys};
+
+const PRA: u8 = 0;
+const CRA: u8 = 1;
+const PRB: u8 = 2;
+const CRB: u8 = 3;
+
+#[test]
+fn pia_ddr_select() {
+    let mut pia = Pia::new();
+    pia.write(PRA, 0x0f);
+    pia.write(CRA, 0x04);
+    pia.write(PRA, 0x5a);
+    pia.set_pa(0x30);
+    assert_eq!(pia.read(PRA), 0x3a);
+    assert_eq!(pia.pa(), 0x3a);
+    pia.write(CRA, 0x00);
+    assert_eq!(pia.read(PRA), 0x0f);
+}
+
+#[test]
+fn pia_c1_flags() {
+    let mut pia = Pia::new();
+    // Positive CA1 edge, interrupt enabled.
+    pia.write(CRA, 0x07);
+    pia.set_ca1(false);
+    assert!(!pia.irq());
+    pia.set_ca1(true);
+    assert!(pia.irqa());
+    assert_eq!(pia.read(CRA), 0x87);
+    // Writing the control register does not clear the flag.
+    pia.write(CRA, 0x06);
+    assert!(!pia.irq());
+    assert_eq!(pia.read(CRA), 0x86);
+    pia.read(PRA);
+    assert_eq!(pia.read(CRA), 0x06);
+}
+
+#[test]
+fn pia_c2_input() {
+    let mut pia = Pia::new();
+    // Negative CB2 edge, interrupt enabled.
+    pia.write(CRB, 0x0c);
+    pia.set_cb2(false);
+    assert!(pia.irqb());
+    assert!(!pia.irqa());
+    assert_eq!(pia.read(CRB), 0x4c);
+    pia.read(PRB);
+    assert!(!pia.irq());
+}
+
+#[test]
+fn pia_c2_output() {
+    let mut pia = Pia::new();
+    // CA2 read strobe, restored by CA1.
+    pia.write(CRA, 0x24);
+    assert!(pia.ca2());
+    pia.read(PRA);
+    assert!(!pia.ca2());
+    pia.set_ca1(false);
+    assert!(pia.ca2());
+
+    // CA2 read strobe, restored after a cycle.
+    pia.write(CRA, 0x2c);
+    pia.read(PRA);
+    assert!(!pia.ca2());
+    pia.tick();
+    assert!(!pia.ca2());
+    pia.tick();
+    assert!(pia.ca2());
+
+    // CB2 strobes on writes, and set manually.
+    pia.write(CRB, 0x24);
+    pia.read(PRB);
+    assert!(pia.cb2());
+    pia.write(PRB, 0x00);
+    assert!(!pia.cb2());
+    pia.write(CRB, 0x3c);
+    assert!(pia.cb2());
+    pia.write(CRB, 0x34);
+    assert!(!pia.cb2());
+}
+
+#[test]
+fn pia_keys() {
+    #[rustfmt::skip]
+    let code = [
+        0xa9, 0x7f,       // LDA #$7f
+        0x8d, 0x12, 0xd0, // STA $d012
+        0xa9, 0xa7,       // LDA #$a7
+        0x8d, 0x11, 0xd0, // STA $d011
+        0x8d, 0x13, 0xd0, // STA $d013
+        0xad, 0x11, 0xd0, // LDA $d011
+        0x10, 0xfb,       // BPL $020d
+        0xad, 0x10, 0xd0, // LDA $d010
+        0x8d, 0x12, 0xd0, // STA $d012
+        0x4c, 0x0d, 0x02, // JMP $020d
+    ];
+    let mut sys = PiaSys::new(&code);
+    sys.pia.set_capture(true);
+    sys.pia.send_keys(b"\xc1\xc2\xc3");
+    let mut cpu = Nmos::standard();
+    cpu.set_pc(0x0200);
+    for _ in 0..100 {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    assert_eq!(sys.pia.take_output(), b"\xc1\xc2\xc3");
+    assert_eq!(sys.pia.pending_keys(), 0);
+
+    sys.pia.set_capture(false);
+    sys.pia.send_keys(b"\xc4");
+    for _ in 0..20 {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    assert_eq!(sys.pia.pending_keys(), 0);
+    assert_eq!(sys.pia.take_output(), b"");
+}
+
+// RAM, with a PIA at $d010.
+struct PiaSys {
+    mem: Vec<u8>,
+    pia: Pia,
+}
+
+impl PiaSys {
+    fn new(code: &[u8]) -> PiaSys {
+        let mut mem = vec![0u8; 0x10000];
+        mem[0x0200..(0x0200 + code.len())].copy_from_slice(code);
+        PiaSys {
+            mem,
+            pia: Pia::new(),
+        }
+    }
+}
+
+impl Sys for PiaSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let val = match addr {
+            0xd010..=0xd013 => self.pia.read(addr as u8),
+            _ => self.mem[addr as usize],
+        };
+        self.pia.tick();
+        Some(val)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        match addr {
+            0xd010..=0xd013 => self.pia.write(addr as u8, val),
+            _ => self.mem[addr as usize] = val,
+        }
+        self.pia.tick();
+        Some(())
+    }
+}
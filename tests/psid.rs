@@ -0,0 +1,193 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{LoadError, Psid, PsidPlayer, RegWrite};
+
+#[rustfmt::skip]
+const TUNE: [u8; 0x1a] = [
+    0x4c, 0x06, 0x10,       // JMP init
+    0x4c, 0x0e, 0x10,       // JMP play
+    // init:
+    0x8d, 0x18, 0xd4,       // STA $D418
+    0xa9, 0x00,             // LDA #$00
+    0x85, 0xfb,             // STA $FB
+    0x60,                   // RTS
+    // play:
+    0xe6, 0xfb,             // INC $FB
+    0xa5, 0xfb,             // LDA $FB
+    0x8d, 0x00, 0xd4,       // STA $D400
+    0x60,                   // RTS
+    0xea, 0xea, 0xea, 0xea,
+];
+
+// Installs its own IRQ handler, with the CIA timer at $2000.
+#[rustfmt::skip]
+const RSID: [u8; 0x28] = [
+    0x78,                   // SEI
+    0xa9, 0x1b,             // LDA #<irq
+    0x8d, 0x14, 0x03,       // STA $0314
+    0xa9, 0x10,             // LDA #>irq
+    0x8d, 0x15, 0x03,       // STA $0315
+    0xa9, 0x00,             // LDA #$00
+    0x8d, 0x04, 0xdc,       // STA $DC04
+    0xa9, 0x20,             // LDA #$20
+    0x8d, 0x05, 0xdc,       // STA $DC05
+    0xa9, 0x11,             // LDA #$11
+    0x8d, 0x0e, 0xdc,       // STA $DC0E
+    0x60,                   // RTS
+    // irq:
+    0xe6, 0xfc,             // INC $FC
+    0xa5, 0xfc,             // LDA $FC
+    0x8d, 0x01, 0xd4,       // STA $D401
+    0x4c, 0x31, 0xea,       // JMP $EA31
+    0xea, 0xea, 0xea,
+];
+
+fn psid_file(magic: &[u8], play: u16, speed: u32, flags: u16) -> Vec<u8> {
+    let mut file = vec![0; 0x7c];
+    file[0..4].copy_from_slice(magic);
+    file[4..6].copy_from_slice(&2u16.to_be_bytes());
+    file[6..8].copy_from_slice(&0x7cu16.to_be_bytes());
+    file[0x0a..0x0c].copy_from_slice(&0x1000u16.to_be_bytes());
+    file[0x0c..0x0e].copy_from_slice(&play.to_be_bytes());
+    file[0x0e..0x10].copy_from_slice(&3u16.to_be_bytes());
+    file[0x10..0x12].copy_from_slice(&2u16.to_be_bytes());
+    file[0x12..0x16].copy_from_slice(&speed.to_be_bytes());
+    file[0x16..0x1a].copy_from_slice(b"Tune");
+    file[0x76..0x78].copy_from_slice(&flags.to_be_bytes());
+    // The load address is taken from the data.
+    file.extend_from_slice(&[0x00, 0x10]);
+    file
+}
+
+fn tune(play: u16, speed: u32, flags: u16) -> Psid {
+    let mut file = psid_file(b"PSID", play, speed, flags);
+    file.extend_from_slice(&TUNE);
+    Psid::parse(&file).unwrap()
+}
+
+fn gaps(log: &[RegWrite], addr: u16) -> Vec<u64> {
+    let cycles: Vec<_> = log
+        .iter()
+        .filter(|w| w.addr == addr)
+        .map(|w| w.cycle)
+        .collect();
+    cycles.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+// The IRQ is taken at the end of an instruction in the driver's JMP
+// loop, so each gap may be off by a few cycles, but not on average.
+fn assert_period(gaps: &[u64], period: u64) {
+    assert!(!gaps.is_empty());
+    for &gap in gaps {
+        assert!(gap + 3 >= period && gap <= period + 3, "{}", gap);
+    }
+    let total: u64 = gaps.iter().sum();
+    let expected = period * gaps.len() as u64;
+    assert!(total + 3 >= expected && total <= expected + 3);
+}
+
+#[test]
+fn psid_header() {
+    let psid = tune(0x1003, 0, 0);
+    assert!(!psid.rsid);
+    assert_eq!(psid.version, 2);
+    assert_eq!(psid.load_addr, 0x1000);
+    assert_eq!(psid.init_addr, 0x1000);
+    assert_eq!(psid.play_addr, 0x1003);
+    assert_eq!(psid.songs, 3);
+    assert_eq!(psid.start_song, 1);
+    assert_eq!(psid.name, "Tune");
+    assert!(!psid.ntsc);
+    assert_eq!(psid.clock_hz(), 985_248);
+    assert_eq!(psid.data, &TUNE[..]);
+
+    assert!(tune(0x1003, 0, 0x08).ntsc);
+    assert_eq!(Psid::parse(b"MUS\0"), Err(LoadError::BadMagic));
+    assert_eq!(Psid::parse(b"PSID\0\x02"), Err(LoadError::Truncated));
+    let file = psid_file(b"RSID", 0, 0, 0x02);
+    assert!(Psid::parse(&file).is_err());
+}
+
+#[test]
+fn psid_vbi() {
+    let mut player = PsidPlayer::new(&tune(0x1003, 0, 0));
+    player.init(1);
+    player.run_cycles(player.clock_hz());
+    let log = player.take_log();
+    // INIT gets the song number.
+    assert_eq!((log[0].addr, log[0].val), (0xd418, 1));
+
+    // PLAY is called by the raster interrupt, 50 times a second.
+    let plays: Vec<_> = log.iter().filter(|w| w.addr == 0xd400).collect();
+    assert!((49..=51).contains(&plays.len()));
+    assert_eq!(plays[0].val, 1);
+    assert_period(&gaps(&log, 0xd400), 312 * 63);
+
+    let mut ntsc = PsidPlayer::new(&tune(0x1003, 0, 0x08));
+    ntsc.init(0);
+    ntsc.run_cycles(100_000);
+    assert_period(&gaps(ntsc.log(), 0xd400), 263 * 65);
+}
+
+#[test]
+fn psid_cia_speed() {
+    // Only the second song uses the CIA timer.
+    let mut player = PsidPlayer::new(&tune(0x1003, 0x02, 0));
+    player.init(1);
+    player.run_cycles(player.clock_hz());
+    let plays = gaps(player.log(), 0xd400);
+    assert!((58..=61).contains(&plays.len()));
+    assert_period(&plays, 0x4026);
+
+    player.init(0);
+    player.take_log();
+    player.run_cycles(100_000);
+    assert_period(&gaps(player.log(), 0xd400), 312 * 63);
+}
+
+#[test]
+fn psid_rsid() {
+    let mut file = psid_file(b"RSID", 0, 0, 0);
+    file.extend_from_slice(&RSID);
+    let psid = Psid::parse(&file).unwrap();
+    assert!(psid.rsid);
+    let mut player = PsidPlayer::new(&psid);
+    player.init(0);
+    player.run_cycles(200_000);
+    let log = player.take_log();
+    assert!(log.len() > 20);
+    assert_eq!(log[0].val, 1);
+    assert_period(&gaps(&log, 0xd401), 0x2001);
+    assert_eq!(player.sys.ram[0x00fc] as usize, log.len());
+}
+
+#[test]
+fn psid_sid_mirrors() {
+    // $D5E0 mirrors $D400, and $D41D isn't a register.
+    #[rustfmt::skip]
+    let code = [
+        0xa9, 0x42,             // LDA #$42
+        0x8d, 0xe0, 0xd5,       // STA $D5E0
+        0x8d, 0x1d, 0xd4,       // STA $D41D
+        0x60,                   // RTS
+    ];
+    let mut file = psid_file(b"PSID", 0, 0, 0);
+    file.extend_from_slice(&code);
+    let mut player = PsidPlayer::new(&Psid::parse(&file).unwrap());
+    player.init(0);
+    player.run_cycles(100);
+    assert_eq!(
+        player.log(),
+        &[RegWrite {
+            cycle: 11,
+            addr: 0xd400,
+            val: 0x42
+        }]
+    );
+}
@@ -0,0 +1,103 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Device, Riot};
+
+const TIM64T: u8 = 0x16;
+const INTIM: u8 = 0x04;
+const TIMINT: u8 = 0x05;
+
+#[test]
+fn riot_timer() {
+    let mut riot = Riot::new();
+    riot.write(TIM64T, 2);
+    riot.tick();
+    assert_eq!(riot.read(INTIM), 2);
+    riot.tick();
+    assert_eq!(riot.read(INTIM), 1);
+    for _ in 0..63 {
+        riot.tick();
+        assert_eq!(riot.read(INTIM), 1);
+    }
+    riot.tick();
+    assert_eq!(riot.read(INTIM), 0);
+    for _ in 0..63 {
+        riot.tick();
+    }
+    assert_eq!(riot.read(TIMINT), 0x00);
+    riot.tick();
+    assert_eq!(riot.read(TIMINT), 0x80);
+    // Counting down every cycle after underflow.
+    riot.tick();
+    assert_eq!(riot.read(TIMINT), 0x80);
+    assert_eq!(riot.read(INTIM), 0xfe);
+    assert_eq!(riot.read(TIMINT), 0x00);
+    riot.tick();
+    assert_eq!(riot.read(INTIM), 0xfd);
+}
+
+#[test]
+fn riot_prescale() {
+    for &(reg, prescale) in &[(0x14, 1), (0x15, 8), (0x16, 64), (0x17, 1024)] {
+        let mut riot = Riot::new();
+        riot.write(reg, 1);
+        riot.tick();
+        let mut cycles = 0;
+        while riot.read(TIMINT) == 0 {
+            riot.tick();
+            cycles += 1;
+        }
+        assert_eq!(cycles, prescale + 1);
+    }
+}
+
+#[test]
+fn riot_timer_irq() {
+    let mut riot = Riot::new();
+    // Write TIM1T with the interrupt enabled.
+    riot.write(0x1c, 0);
+    riot.tick();
+    assert!(!riot.irq());
+    riot.tick();
+    assert!(riot.irq());
+    // Reading INTIM without A3 disables it.
+    riot.read(INTIM);
+    riot.tick();
+    assert!(!riot.irq());
+}
+
+#[test]
+fn riot_pa7() {
+    let mut riot = Riot::new();
+    // Interrupt on a positive edge.
+    riot.write(0x07, 0);
+    riot.set_pa(0x7f);
+    assert!(!riot.irq());
+    riot.set_pa(0xff);
+    assert!(riot.irq());
+    assert_eq!(riot.read(TIMINT), 0x40);
+    assert!(!riot.irq());
+
+    // Negative edge, without the interrupt; driven from the output side.
+    riot.write(0x04, 0);
+    riot.write(0x01, 0x80);
+    riot.write(0x00, 0x80);
+    riot.write(0x00, 0x00);
+    assert!(!riot.irq());
+    assert_eq!(riot.read(TIMINT), 0x40);
+}
+
+#[test]
+fn riot_ports() {
+    let mut riot = Riot::new();
+    riot.write(0x03, 0x0f);
+    riot.write(0x02, 0x5a);
+    riot.set_pb(0x30);
+    assert_eq!(riot.read(0x02), 0x3a);
+    assert_eq!(riot.pb(), 0x3a);
+}
@@ -0,0 +1,126 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use robo6502::{Clocked, Cmos, Cpu, Nmos, Scheduler, Sys, Task};
+
+#[test]
+fn sched_order() {
+    // JMP $0200
+    let code = [0x4c, 0x00, 0x02];
+    let shared = Rc::new(RefCell::new(Shared::default()));
+    let mut sched = Scheduler::new();
+    sched.add(clocked(Nmos::standard(), &code, &shared, 0, 2, 1));
+    sched.add(clocked(Cmos::new(), &code, &shared, 1, 1, 1));
+    let ids: Vec<_> = (0..9).map(|_| sched.step()).collect();
+    assert_eq!(ids, [0, 1, 0, 0, 1, 0, 0, 1, 0]);
+}
+
+#[test]
+fn sched_rates() {
+    // JMP $0200
+    let code = [0x4c, 0x00, 0x02];
+    let shared = Rc::new(RefCell::new(Shared::default()));
+    let mut c64 = clocked(Nmos::standard(), &code, &shared, 0, 985_248, 1);
+    let mut drive = clocked(Cmos::new(), &code, &shared, 1, 1_000_000, 1);
+    {
+        let mut sched = Scheduler::new();
+        let id = sched.add(&mut c64);
+        sched.add(&mut drive);
+        sched.run_cycles(id, 985_248);
+    }
+    assert_eq!(c64.cycles(), 985_248);
+    assert_eq!(drive.cycles(), 1_000_000);
+}
+
+#[test]
+fn sched_mailbox() {
+    // LDA #$42; STA $d000; JMP $0205
+    let writer = [0xa9, 0x42, 0x8d, 0x00, 0xd0, 0x4c, 0x05, 0x02];
+    // LDA $d000; BEQ $0200; JMP $0205
+    let reader = [0xad, 0x00, 0xd0, 0xf0, 0xfb, 0x4c, 0x05, 0x02];
+    let shared = Rc::new(RefCell::new(Shared::default()));
+    let mut slow = clocked(Cmos::new(), &writer, &shared, 0, 1, 3);
+    let mut fast = clocked(Cmos::new(), &reader, &shared, 1, 1, 1);
+    {
+        let mut sched = Scheduler::new();
+        let id = sched.add(&mut slow);
+        sched.add(&mut fast);
+        sched.run_cycles(id, 10);
+    }
+    // The write is the sixth cycle of the writer, at time 15; the reader
+    // loops every seven cycles, reading $d000 at times 3, 10, 17, ...
+    let log = &shared.borrow().log;
+    let first = log.iter().position(|e| e == &(1, 0x42)).unwrap();
+    assert_eq!(log[..first], [(1, 0), (1, 0), (0, 0x42)]);
+    assert_eq!(fast.cycles(), 30);
+}
+
+#[test]
+#[should_panic(expected = "no tasks to schedule")]
+fn sched_empty() {
+    Scheduler::new().step();
+}
+
+#[derive(Default)]
+struct Shared {
+    mailbox: u8,
+    log: Vec<(usize, u8)>,
+}
+
+fn clocked<C: Cpu>(
+    mut cpu: C,
+    code: &[u8],
+    shared: &Rc<RefCell<Shared>>,
+    id: usize,
+    num: u64,
+    den: u64,
+) -> Clocked<C, MailSys> {
+    cpu.set_pc(0x0200);
+    let mut mem = vec![0u8; 0x1000];
+    mem[0x0200..(0x0200 + code.len())].copy_from_slice(code);
+    let sys = MailSys {
+        id,
+        mem,
+        shared: shared.clone(),
+    };
+    Clocked::new(cpu, sys, num, den)
+}
+
+// RAM from $0000-$0fff, and a register at $d000 shared by all CPUs.
+struct MailSys {
+    id: usize,
+    mem: Vec<u8>,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Sys for MailSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        if addr == 0xd000 {
+            let mut shared = self.shared.borrow_mut();
+            let val = shared.mailbox;
+            shared.log.push((self.id, val));
+            Some(val)
+        } else {
+            Some(self.mem[(addr & 0x0fff) as usize])
+        }
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        if addr == 0xd000 {
+            let mut shared = self.shared.borrow_mut();
+            shared.mailbox = val;
+            shared.log.push((self.id, val));
+        } else {
+            self.mem[(addr & 0x0fff) as usize] = val;
+        }
+        Some(())
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{LoadError, SourceLine, Symbols};
+
+const DBG: &str = "\
+version\tmajor=2,minor=0
+info\tcsym=0,file=2,lib=0,line=4,mod=1,scope=1,seg=2,span=4,sym=4,type=0
+file\tid=0,name=\"main.s\",size=200,mtime=0x5b000000,mod=0
+file\tid=1,name=\"macros.inc\",size=50,mtime=0x5b000000,mod=0
+line\tid=0,file=0,line=10,span=0
+line\tid=1,file=0,line=12,span=1+3
+line\tid=2,file=1,line=3,type=2,count=1,span=1
+line\tid=3,file=0,line=20,span=2
+mod\tid=0,name=\"main.o\",file=0
+seg\tid=0,name=\"CODE\",start=0x008000,size=0x0010,addrsize=absolute,type=ro
+seg\tid=1,name=\"ZEROPAGE\",start=0x000080,size=0x0002,addrsize=zeropage,type=rw
+span\tid=0,seg=0,start=0,size=2
+span\tid=1,seg=0,start=2,size=3
+span\tid=2,seg=1,start=0,size=2
+span\tid=3,seg=0,start=5,size=1
+scope\tid=0,name=\"\",mod=0,size=16
+sym\tid=0,name=\"PTR\",addrsize=zeropage,scope=0,def=0,val=0x80,type=equ
+sym\tid=1,name=\"reset\",addrsize=absolute,size=5,val=0x8000,seg=0,type=lab
+sym\tid=2,name=\"ptr\",addrsize=zeropage,size=2,val=0x80,seg=1,type=lab
+sym\tid=3,name=\"ext\",addrsize=absolute,scope=0,ref=1,type=imp
+";
+
+fn line(file: &str, line: u32) -> Option<SourceLine> {
+    Some(SourceLine {
+        file: file.to_string(),
+        line,
+    })
+}
+
+#[test]
+fn symbols_dbg() {
+    let syms = Symbols::parse_dbg(DBG).unwrap();
+    assert_eq!(syms.symbols().len(), 3);
+    assert_eq!(syms.lookup("reset"), Some(0x8000));
+    assert_eq!(syms.lookup("ext"), None);
+    // The label is shown in place of the equate.
+    assert_eq!(syms.name(0x80), Some("ptr"));
+    assert_eq!(syms.lookup("PTR"), Some(0x80));
+    assert_eq!(syms.label(0x8003), "reset+3");
+    assert_eq!(syms.label(0x8005), "$8005");
+    assert_eq!(syms.label(0x0081), "ptr+1");
+
+    assert_eq!(syms.source(0x8000).cloned(), line("main.s", 10));
+    assert_eq!(syms.source(0x8001).cloned(), line("main.s", 10));
+    // The macro's line is skipped for the line that used it.
+    assert_eq!(syms.source(0x8004).cloned(), line("main.s", 12));
+    assert_eq!(syms.source(0x8005).cloned(), line("main.s", 12));
+    assert_eq!(syms.source(0x0081).cloned(), line("main.s", 20));
+    assert_eq!(syms.source(0x8006), None);
+
+    assert_eq!(
+        Symbols::parse_dbg("al C:8000 .reset").err(),
+        Some(LoadError::BadMagic)
+    );
+    let bad = DBG.replace("span=1+3", "span=1+9");
+    assert_eq!(
+        Symbols::parse_dbg(&bad).err(),
+        Some(LoadError::Invalid("unknown dbg span"))
+    );
+}
+
+#[test]
+fn symbols_vice() {
+    let text = "al C:8000 .reset\nal C:fffa .nmi_vec\n\nal 00fb .ptr\n";
+    let syms = Symbols::parse_vice(text).unwrap();
+    assert_eq!(syms.lookup("reset"), Some(0x8000));
+    assert_eq!(syms.name(0xfffa), Some("nmi_vec"));
+    assert_eq!(syms.name(0x00fb), Some("ptr"));
+    assert_eq!(syms.label(0xfffb), "$fffb");
+
+    assert!(Symbols::parse_vice("break 8000").is_err());
+    assert!(Symbols::parse_vice("al C:80000 .big").is_err());
+}
+
+#[test]
+fn symbols_labels() {
+    // As NESASM writes a .fns file.
+    let text = "\
+        ;File main.asm\n\
+        reset\t\t= $C000\n\
+        nmi = $c123 ; the NMI handler\n\
+        count = 0x10\n\
+        ten = 10\n";
+    let syms = Symbols::parse_labels(text).unwrap();
+    assert_eq!(syms.lookup("reset"), Some(0xc000));
+    assert_eq!(syms.lookup("nmi"), Some(0xc123));
+    assert_eq!(syms.lookup("count"), Some(0x10));
+    assert_eq!(syms.lookup("ten"), Some(10));
+
+    assert!(Symbols::parse_labels("reset $C000").is_err());
+    assert!(Symbols::parse_labels("reset = $10000").is_err());
+}
+
+#[test]
+fn symbols_merge() {
+    let mut syms = Symbols::parse_dbg(DBG).unwrap();
+    let labels = Symbols::parse_labels("start = $8000\nvec = $fffc").unwrap();
+    syms.extend(labels);
+    assert_eq!(syms.name(0x8000), Some("reset"));
+    assert_eq!(syms.lookup("start"), Some(0x8000));
+    assert_eq!(syms.label(0xfffc), "vec");
+
+    let mut syms = Symbols::new();
+    syms.add("table", 0x2000, 0x100);
+    syms.add_line(0x2000, 0x100, "data.s", 5);
+    assert_eq!(syms.label(0x20ff), "table+255");
+    assert_eq!(syms.label(0x2100), "$2100");
+    assert_eq!(syms.source(0x2080).cloned(), line("data.s", 5));
+}
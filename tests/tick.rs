@@ -0,0 +1,148 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Access, Cmos, Cpu, CycleKind, Nmos, Phase, Status, Ticker};
+
+use self::common::{running_ops, setup_cpu, LogSys};
+
+mod common;
+
+#[test]
+fn tick_matches_run() {
+    fn check<C: Cpu>(cpu: C) {
+        for op in running_ops(&cpu) {
+            let code = [op, 0x10, 0x12];
+            let mut sys = LogSys::new(&code);
+            let mut full = setup_cpu(cpu.clone());
+            full.run_instruction(&mut sys).unwrap();
+
+            let mut ticker = Ticker::new(setup_cpu(cpu.clone()));
+            let mut mem = LogSys::new(&code);
+            let log = tick(&mut ticker, &mut mem, sys.log.len());
+            assert_eq!(sys.log, log, "op {:02x}", op);
+            assert_eq!(sys.mem, mem.mem, "op {:02x}", op);
+
+            let req = ticker.next_bus_request().unwrap();
+            assert_eq!(req.kind, CycleKind::Opcode);
+            assert!(req.sync);
+            assert_eq!(ticker.cpu.pc(), full.pc(), "op {:02x}", op);
+        }
+    }
+
+    check(Nmos::standard());
+    check(Cmos::new());
+}
+
+#[test]
+fn tick_irq() {
+    // CLI; NOP; NOP
+    let code = [0x58, 0xea, 0xea];
+    let mut ticker = Ticker::new(setup_cpu(Cmos::new()));
+    ticker.cpu.set_flag(Status::I, true);
+    ticker.set_irq(true);
+    let mut mem = LogSys::new(&code);
+    mem.mem[0xfffe] = 0x00;
+    mem.mem[0xffff] = 0x03;
+    let log = tick(&mut ticker, &mut mem, 2 + 2 + 7);
+    let vectors: Vec<_> = log
+        .iter()
+        .filter(|e| e.2 == CycleKind::Vector)
+        .map(|e| e.0)
+        .collect();
+    assert_eq!(vectors, [0xfffe, 0xffff]);
+    assert_eq!(ticker.cpu.pc(), 0x0300);
+    assert!(ticker.cpu.flag(Status::I));
+}
+
+#[test]
+fn tick_nmi() {
+    // NOP; NOP
+    let code = [0xea, 0xea];
+    let mut ticker = Ticker::new(setup_cpu(Nmos::standard()));
+    let mut mem = LogSys::new(&code);
+    mem.mem[0xfffa] = 0x00;
+    mem.mem[0xfffb] = 0x04;
+    ticker.set_nmi(true);
+    tick(&mut ticker, &mut mem, 2 + 7);
+    assert_eq!(ticker.cpu.pc(), 0x0400);
+
+    // The line is still held, so there is no new edge.
+    mem.mem[0x0400] = 0xea;
+    tick(&mut ticker, &mut mem, 2);
+    assert_eq!(ticker.cpu.pc(), 0x0401);
+}
+
+#[test]
+fn tick_halt() {
+    // KIL
+    let code = [0x02];
+    let mut ticker = Ticker::new(setup_cpu(Nmos::standard()));
+    let mut mem = LogSys::new(&code);
+    let mut cycles = 0;
+    while ticker.next_bus_request().is_some() {
+        tick(&mut ticker, &mut mem, 1);
+        cycles += 1;
+        assert!(cycles < 20);
+    }
+    assert!(ticker.cpu.halted());
+
+    ticker.cpu.reset();
+    let req = ticker.next_bus_request().unwrap();
+    assert_eq!(req.addr, 0x0200);
+}
+
+#[test]
+fn half_cycles() {
+    // LDA #$5a; STA $10; INC $10
+    let code = [0xa9, 0x5a, 0x85, 0x10, 0xe6, 0x10];
+    let mut ticker = Ticker::new(setup_cpu(Cmos::new()));
+    let mut mem = LogSys::new(&code);
+    let mut log = Vec::new();
+    for _ in 0..(2 + 3 + 5) {
+        assert_eq!(ticker.phase(), Phase::Phi1);
+        let req = ticker.phi1().unwrap();
+        assert_eq!(ticker.phase(), Phase::Phi2);
+        let data = ticker.phi2(mem.mem[req.addr as usize]).unwrap();
+        if let Access::Write(_) = req.access {
+            mem.mem[req.addr as usize] = data;
+        }
+        log.push((req.addr, data, req.ml));
+    }
+    #[rustfmt::skip]
+    let exp = [
+        (0x0200, 0xa9, false), (0x0201, 0x5a, false),
+        (0x0202, 0x85, false), (0x0203, 0x10, false), (0x0010, 0x5a, false),
+        (0x0204, 0xe6, false), (0x0205, 0x10, false),
+        (0x0010, 0x5a, true), (0x0010, 0x5a, true), (0x0010, 0x5b, true),
+    ];
+    assert_eq!(log, exp);
+}
+
+fn tick<C: Cpu>(
+    ticker: &mut Ticker<C>,
+    mem: &mut LogSys,
+    cycles: usize,
+) -> Vec<(u16, bool, CycleKind)> {
+    let mut log = Vec::new();
+    for _ in 0..cycles {
+        let req = ticker.next_bus_request().unwrap();
+        let data = match req.access {
+            Access::Read => {
+                log.push((req.addr, false, req.kind));
+                mem.mem[req.addr as usize]
+            }
+            Access::Write(val) => {
+                log.push((req.addr, true, req.kind));
+                mem.mem[req.addr as usize] = val;
+                val
+            }
+        };
+        ticker.complete(data);
+    }
+    log
+}
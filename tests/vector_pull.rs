@@ -0,0 +1,139 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cmos, Cpu, Nmos, Status, Sys};
+
+use self::common::{StepSys, TestSys};
+
+mod common;
+
+#[test]
+fn vp_brk() {
+    // BRK
+    let code = [0x00];
+    let exp = vec![
+        (0x0200, false, false),
+        (0x0201, false, false),
+        (0x01ff, false, false),
+        (0x01fe, false, false),
+        (0x01fd, false, false),
+        (0xfffe, true, false),
+        (0xffff, true, false),
+    ];
+    assert_eq!(run(Cmos::new(), &code), exp);
+    assert_eq!(run_step(Cmos::new(), &code), exp);
+
+    let log = run(Nmos::standard(), &code);
+    assert!(log.iter().all(|&(_, vp, ml)| !vp && !ml));
+}
+
+#[test]
+fn ml_rmw() {
+    // INC $10
+    let code = [0xe6, 0x10];
+    let exp = vec![
+        (0x0200, false, false),
+        (0x0201, false, false),
+        (0x0010, false, true),
+        (0x0010, false, true),
+        (0x0010, false, true),
+    ];
+    assert_eq!(run(Cmos::new(), &code), exp);
+    assert_eq!(run_step(Cmos::new(), &code), exp);
+
+    let log = run(Nmos::standard(), &code);
+    assert!(log.iter().all(|&(_, vp, ml)| !vp && !ml));
+}
+
+#[test]
+fn vp_vectored_irq() {
+    // NOP; NOP
+    let code = [0xea, 0xea];
+    let mut sys = SignalSys::new(&code);
+    sys.irq_vec = Some(0x0340);
+    let mut cpu = Cmos::new();
+    cpu.set_pc(0x0200);
+    cpu.set_sp(0xff);
+    cpu.set_flag(Status::I, false);
+    cpu.run_instruction(&mut sys);
+    cpu.run_instruction(&mut sys);
+    assert_eq!(cpu.pc(), 0x0340);
+}
+
+fn run<C: Cpu>(mut cpu: C, code: &[u8]) -> Vec<(u16, bool, bool)> {
+    let mut sys = SignalSys::new(code);
+    cpu.set_pc(0x0200);
+    cpu.set_sp(0xff);
+    cpu.run_instruction(&mut sys);
+    sys.log
+}
+
+fn run_step<C: Cpu>(mut cpu: C, code: &[u8]) -> Vec<(u16, bool, bool)> {
+    let mut sys = StepSys::new(SignalSys::new(code));
+    cpu.set_pc(0x0200);
+    cpu.set_sp(0xff);
+    sys.run_instruction(&mut cpu);
+    let mut log = sys.sys.log;
+    // Drop the fetch of the next opcode, if it happened.
+    log.truncate(code.len() + if code[0] == 0 { 6 } else { 3 });
+    log
+}
+
+struct SignalSys {
+    mem: Vec<u8>,
+    log: Vec<(u16, bool, bool)>,
+    vp: bool,
+    ml: bool,
+    irq_vec: Option<u16>,
+}
+
+impl SignalSys {
+    fn new(code: &[u8]) -> SignalSys {
+        let mut mem = vec![0u8; 0x10000];
+        mem[0x0200..(0x0200 + code.len())].copy_from_slice(code);
+        SignalSys {
+            mem,
+            log: Vec::new(),
+            vp: false,
+            ml: false,
+            irq_vec: None,
+        }
+    }
+}
+
+impl Sys for SignalSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.log.push((addr, self.vp, self.ml));
+        if let (true, Some(vec)) = (self.vp, self.irq_vec) {
+            match addr {
+                0xfffe => return Some(vec as u8),
+                0xffff => return Some((vec >> 8) as u8),
+                _ => (),
+            }
+        }
+        Some(self.mem[addr as usize])
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        self.log.push((addr, self.vp, self.ml));
+        self.mem[addr as usize] = val;
+        Some(())
+    }
+
+    fn set_vp(&mut self, set: bool) {
+        self.vp = set;
+    }
+
+    fn set_ml(&mut self, set: bool) {
+        self.ml = set;
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_vec.is_some()
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{write_vgm, ChipWrite, Cpu, Nmos, SoundChip, SoundTap, Sys};
+
+use self::common::VecSys;
+
+mod common;
+
+fn le32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn write(cycle: u64, chip: SoundChip, unit: u8, reg: u8, val: u8) -> ChipWrite {
+    ChipWrite {
+        cycle,
+        chip,
+        unit,
+        reg,
+        val,
+    }
+}
+
+#[test]
+fn vgm_tap_apu() {
+    #[rustfmt::skip]
+    let code = [
+        0xa9, 0x0f,             // LDA #$0f
+        0x8d, 0x15, 0x40,       // STA $4015
+        0x8d, 0x00, 0x02,       // STA $0200
+        0xad, 0x00, 0x40,       // LDA $4000
+        0x8d, 0x14, 0x40,       // STA $4014
+        0x8d, 0x03, 0x40,       // STA $4003
+    ];
+    let mut sys = SoundTap::new(VecSys::with_code(&code));
+    sys.add(SoundChip::Apu);
+    let mut cpu = Nmos::nes();
+    cpu.set_pc(0x0200);
+    for _ in 0..6 {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    assert_eq!(sys.cycles(), 22);
+    assert_eq!(
+        sys.take_writes(),
+        [
+            write(5, SoundChip::Apu, 0, 0x15, 0x0f),
+            write(21, SoundChip::Apu, 0, 0x03, 0x00)
+        ]
+    );
+    // The writes still reach the system.
+    assert_eq!(sys.sys.mem[0x4015], 0x0f);
+}
+
+#[test]
+fn vgm_tap_mockingboard() {
+    let mut sys = SoundTap::new(VecSys::with_code(&[]));
+    sys.add(SoundChip::Mockingboard(4));
+    for &base in &[0xc400, 0xc480] {
+        sys.write(base + 3, 0xff);
+        sys.write(base + 2, 0x07);
+        // Latch register 7, then write $3E to it.
+        sys.write(base + 1, 0x07);
+        sys.write(base, 0x07);
+        sys.write(base, 0x04);
+        sys.write(base + 1, 0x3e);
+        sys.write(base, 0x06);
+        sys.write(base, 0x04);
+    }
+    // Slot 5 isn't watched.
+    sys.write(0xc500, 0x06);
+    assert_eq!(
+        sys.writes(),
+        [
+            write(6, SoundChip::Mockingboard(4), 0, 7, 0x3e),
+            write(14, SoundChip::Mockingboard(4), 1, 7, 0x3e)
+        ]
+    );
+}
+
+#[test]
+fn vgm_tap_units() {
+    let mut sys = SoundTap::new(VecSys::with_code(&[]));
+    sys.add(SoundChip::Pokey(0xd200));
+    sys.add(SoundChip::Pokey(0xd210));
+    sys.add(SoundChip::Sid(0xd400));
+    sys.write(0xd201, 0xa8);
+    sys.write(0xd211, 0xa4);
+    sys.write(0xd418, 0x0f);
+    sys.write(0xd41d, 0x0f);
+    assert_eq!(
+        sys.writes(),
+        [
+            write(0, SoundChip::Pokey(0xd200), 0, 1, 0xa8),
+            write(1, SoundChip::Pokey(0xd210), 1, 1, 0xa4),
+            write(2, SoundChip::Sid(0xd400), 0, 0x18, 0x0f)
+        ]
+    );
+}
+
+#[test]
+fn vgm_file() {
+    let hz = 1_764_000;
+    let writes = [
+        write(0, SoundChip::Apu, 0, 0x15, 0x0f),
+        // 1/60 second, then 1/50 second.
+        write(29_400, SoundChip::Apu, 0, 0x00, 0xbf),
+        write(64_680, SoundChip::Apu, 0, 0x02, 0xfd),
+        write(64_720, SoundChip::Apu, 0, 0x03, 0x00),
+        write(64_720, SoundChip::Apu, 0, 0x17, 0x40),
+    ];
+    let mut vgm = Vec::new();
+    write_vgm(&mut vgm, hz, 3 * hz as u64, &writes).unwrap();
+
+    assert_eq!(&vgm[0..4], b"Vgm ");
+    assert_eq!(le32(&vgm[0x04..]) as usize, vgm.len() - 4);
+    assert_eq!(le32(&vgm[0x08..]), 0x171);
+    assert_eq!(le32(&vgm[0x18..]), 3 * 44_100);
+    assert_eq!(le32(&vgm[0x34..]), 0xcc);
+    assert_eq!(le32(&vgm[0x74..]), 0);
+    assert_eq!(le32(&vgm[0x84..]), hz);
+    #[rustfmt::skip]
+    let data = [
+        0xb4, 0x15, 0x0f,
+        0x62,
+        0xb4, 0x00, 0xbf,
+        0x63,
+        0xb4, 0x02, 0xfd,
+        0x70,
+        0xb4, 0x03, 0x00,
+        0xb4, 0x17, 0x40,
+        // The rest of the three seconds.
+        0x61, 0xff, 0xff,
+        0x61, 0x7b, 0xfe,
+        0x66,
+    ];
+    assert_eq!(&vgm[0x100..], &data[..]);
+}
+
+#[test]
+fn vgm_file_chips() {
+    let mb = SoundChip::Mockingboard(4);
+    let writes = [
+        write(0, mb, 0, 7, 0x3e),
+        write(0, mb, 1, 7, 0x3c),
+        write(0, SoundChip::Pokey(0xd200), 0, 1, 0xa8),
+    ];
+    let mut vgm = Vec::new();
+    write_vgm(&mut vgm, 1_022_727, 0, &writes).unwrap();
+    assert_eq!(le32(&vgm[0x74..]), 1_022_727 | 0x4000_0000);
+    assert_eq!(vgm[0x79], 0x01);
+    assert_eq!(le32(&vgm[0xb0..]), 1_022_727);
+    assert_eq!(
+        &vgm[0x100..],
+        &[0xa0, 0x07, 0x3e, 0xa0, 0x87, 0x3c, 0xbb, 0x01, 0xa8, 0x66]
+    );
+
+    let sid = [write(0, SoundChip::Sid(0xd400), 0, 0x18, 0x0f)];
+    assert!(write_vgm(&mut Vec::new(), 985_248, 0, &sid).is_err());
+    let third = [write(0, SoundChip::Pokey(0xd220), 2, 1, 0xa8)];
+    assert!(write_vgm(&mut Vec::new(), 1_789_773, 0, &third).is_err());
+}
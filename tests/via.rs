@@ -0,0 +1,278 @@
+// Copyright 2018 Ed McCardell
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use robo6502::{Cmos, Cpu, CycleKind, Device, Sys, Via};
+
+const IFR: u8 = 0xd;
+const IER: u8 = 0xe;
+
+#[test]
+fn via_t1_one_shot() {
+    let mut via = Via::new();
+    via.write(IER, 0xc0);
+    via.write(0x4, 0x03);
+    via.write(0x5, 0x00);
+    via.tick();
+    // The IRQ comes N + 2 cycles after the write.
+    assert_eq!(ticks_until_irq(&mut via), 4);
+    assert_eq!(via.read(IFR), 0xc0);
+    assert_eq!(via.read(0x4), 0xff);
+    assert!(!via.irq());
+    assert!(ticks_until_irq(&mut via) > 0x10000);
+}
+
+#[test]
+fn via_t1_free_run() {
+    let mut via = Via::new();
+    via.write(IER, 0xc0);
+    via.write(0xb, 0xc0);
+    via.write(0x4, 0x03);
+    via.write(0x5, 0x00);
+    assert_eq!(via.pb() & 0x80, 0x00);
+    via.tick();
+    assert_eq!(ticks_until_irq(&mut via), 4);
+    assert_eq!(via.pb() & 0x80, 0x80);
+    via.read(0x4);
+    assert_eq!(ticks_until_irq(&mut via), 5);
+    assert_eq!(via.pb() & 0x80, 0x00);
+    via.write(IFR, 0x40);
+    assert_eq!(ticks_until_irq(&mut via), 5);
+}
+
+#[test]
+fn via_t2() {
+    let mut via = Via::new();
+    via.write(IER, 0xa0);
+    via.write(0x8, 0x02);
+    via.write(0x9, 0x00);
+    via.tick();
+    assert_eq!(ticks_until_irq(&mut via), 3);
+    via.read(0x8);
+    assert!(ticks_until_irq(&mut via) > 0x10000);
+
+    // Pulse counting on PB6.
+    via.write(0xb, 0x20);
+    via.write(0x8, 0x01);
+    via.write(0x9, 0x00);
+    for _ in 0..10 {
+        via.tick();
+    }
+    for _ in 0..2 {
+        assert!(!via.irq());
+        via.set_pb(0xbf);
+        via.set_pb(0xff);
+    }
+    assert!(via.irq());
+}
+
+#[test]
+fn via_sr_out_phi2() {
+    let mut via = Via::new();
+    via.write(IER, 0x84);
+    via.write(0xb, 0x18);
+    via.write(0xa, 0xa5);
+    let mut bits = Vec::new();
+    for _ in 0..8 {
+        assert!(!via.irq());
+        via.tick();
+        bits.push(via.cb2() as u8);
+    }
+    assert_eq!(bits, [1, 0, 1, 0, 0, 1, 0, 1]);
+    assert!(via.irq());
+    assert_eq!(via.read(0xa), 0xa5);
+    assert!(!via.irq());
+}
+
+#[test]
+fn via_sr_out_t2() {
+    let mut via = Via::new();
+    via.write(0xb, 0x14);
+    via.write(0x8, 0x00);
+    via.write(0x9, 0x00);
+    via.write(0xa, 0x80);
+    // With a latch of zero, each half of a CB1 cycle is two cycles.
+    let mut cb1 = Vec::new();
+    for _ in 0..8 {
+        via.tick();
+        cb1.push(via.cb1() as u8);
+    }
+    assert_eq!(cb1[1..], [0, 0, 1, 1, 0, 0, 1]);
+    assert_eq!(via.read(IFR) & 0x04, 0);
+}
+
+#[test]
+fn via_sr_in_cb1() {
+    let mut via = Via::new();
+    via.write(0xb, 0x0c);
+    via.read(0xa);
+    for i in 0..8 {
+        via.set_cb2((0x5a << i) & 0x80 != 0);
+        via.set_cb1(false);
+        via.set_cb1(true);
+    }
+    assert_eq!(via.read(IFR) & 0x04, 0x04);
+    assert_eq!(via.read(0xa), 0x5a);
+}
+
+#[test]
+fn via_handshake() {
+    let mut via = Via::new();
+    // CA2 handshake output, CA1 active on negative edge.
+    via.write(0xc, 0x08);
+    via.set_pa(0x3c);
+    assert!(via.ca2());
+    assert_eq!(via.read(0x1), 0x3c);
+    assert!(!via.ca2());
+    via.set_ca1(false);
+    assert!(via.ca2());
+    assert_eq!(via.read(IFR), 0x02);
+    via.read(0xf);
+    assert_eq!(via.read(IFR), 0x02);
+    via.read(0x1);
+    assert_eq!(via.read(IFR), 0x00);
+
+    // CA2 pulse output.
+    via.write(0xc, 0x0a);
+    via.write(0x1, 0x00);
+    assert!(!via.ca2());
+    via.tick();
+    assert!(!via.ca2());
+    via.tick();
+    assert!(via.ca2());
+
+    // CB2 handshake only responds to writes.
+    via.write(0xc, 0x80);
+    via.read(0x0);
+    assert!(via.cb2());
+    via.write(0x0, 0x00);
+    assert!(!via.cb2());
+    via.set_cb1(false);
+    assert!(via.cb2());
+}
+
+#[test]
+fn via_latching() {
+    let mut via = Via::new();
+    // Latch PA on a positive CA1 edge.
+    via.write(0xb, 0x01);
+    via.write(0xc, 0x01);
+    via.set_ca1(false);
+    via.set_pa(0x11);
+    via.set_ca1(true);
+    via.set_pa(0x22);
+    assert_eq!(via.read(0x1), 0x11);
+
+    via.write(0x2, 0xf0);
+    via.write(0x0, 0xa0);
+    via.set_pb(0x0c);
+    assert_eq!(via.read(0x0), 0xac);
+}
+
+#[test]
+fn via_ier() {
+    let mut via = Via::new();
+    via.set_ca1(false);
+    assert!(!via.irq());
+    assert_eq!(via.read(IFR), 0x02);
+    via.write(IER, 0x82);
+    assert!(via.irq());
+    assert_eq!(via.read(IFR), 0x82);
+    assert_eq!(via.read(IER), 0x82);
+    via.write(IER, 0x02);
+    assert!(!via.irq());
+    assert_eq!(via.read(IER), 0x80);
+    via.write(IFR, 0x7f);
+    assert_eq!(via.read(IFR), 0x00);
+}
+
+#[test]
+fn via_cpu_timer() {
+    #[rustfmt::skip]
+    let code = [
+        0xa9, 0xc0,       // LDA #$c0
+        0x8d, 0x0e, 0x60, // STA $600e
+        0xa9, 0x10,       // LDA #$10
+        0x8d, 0x04, 0x60, // STA $6004
+        0xa9, 0x00,       // LDA #$00
+        0x8d, 0x05, 0x60, // STA $6005
+        0x58,             // CLI
+        0x4c, 0x10, 0x02, // JMP $0210
+    ];
+    let mut sys = ViaSys::new(&code);
+    let mut cpu = Cmos::new();
+    cpu.set_pc(0x0200);
+    while sys.vector.is_none() {
+        cpu.run_instruction(&mut sys).unwrap();
+    }
+    // The write to $6005 is cycle 17, so the flag is set at the end
+    // of cycle 35, during the JMP from 35 to 37.
+    assert_eq!(sys.vector, Some(43));
+}
+
+fn ticks_until_irq(via: &mut Via) -> usize {
+    let mut ticks = 0;
+    while !via.irq() && ticks <= 0x10000 {
+        via.tick();
+        ticks += 1;
+    }
+    ticks + if via.irq() { 0 } else { 1 }
+}
+
+// RAM, with a VIA at $6000.
+struct ViaSys {
+    mem: Vec<u8>,
+    via: Via,
+    cycle: u64,
+    vector: Option<u64>,
+}
+
+impl ViaSys {
+    fn new(code: &[u8]) -> ViaSys {
+        let mut mem = vec![0u8; 0x10000];
+        mem[0x0200..(0x0200 + code.len())].copy_from_slice(code);
+        ViaSys {
+            mem,
+            via: Via::new(),
+            cycle: 0,
+            vector: None,
+        }
+    }
+}
+
+impl Sys for ViaSys {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let val = match addr {
+            0x6000..=0x600f => self.via.read(addr as u8),
+            _ => self.mem[addr as usize],
+        };
+        self.via.tick();
+        self.cycle += 1;
+        Some(val)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) -> Option<()> {
+        match addr {
+            0x6000..=0x600f => self.via.write(addr as u8, val),
+            _ => self.mem[addr as usize] = val,
+        }
+        self.via.tick();
+        self.cycle += 1;
+        Some(())
+    }
+
+    fn read_kind(&mut self, addr: u16, kind: CycleKind) -> Option<u8> {
+        if kind == CycleKind::Vector && self.vector.is_none() {
+            self.vector = Some(self.cycle);
+        }
+        self.read(addr)
+    }
+
+    fn irq(&self) -> bool {
+        self.via.irq()
+    }
+}